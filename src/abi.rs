@@ -0,0 +1,320 @@
+use serde::ser::Error;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// An entry of a contract abi. Only the keys needed for annotating are typed, everything else
+/// (outputs, anonymous, our own conflictFields ...) is kept untouched in `extra`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct AbiEntry {
+    #[serde(default)]
+    pub(crate) name: Option<String>,
+    #[serde(rename = "type", default)]
+    pub(crate) ty: Option<String>,
+    #[serde(default)]
+    pub(crate) inputs: Option<Vec<AbiParam>>,
+    #[serde(rename = "stateMutability", default)]
+    pub(crate) state_mutability: Option<String>,
+    #[serde(flatten)]
+    pub(crate) extra: Map<String, Value>,
+    /// an entry that is no object, written back as it was
+    #[serde(skip)]
+    pub(crate) raw: Option<Value>,
+}
+
+/// Written with the keys sorted like the untyped entries of the abi were, so rewriting an abi
+/// doesn't reorder the keys of every entry.
+impl Serialize for AbiEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(raw) = &self.raw {
+            return raw.serialize(serializer);
+        }
+        let mut map = self.extra.clone();
+        if let Some(name) = &self.name {
+            map.insert("name".into(), name.clone().into());
+        }
+        if let Some(ty) = &self.ty {
+            map.insert("type".into(), ty.clone().into());
+        }
+        if let Some(inputs) = &self.inputs {
+            let inputs = serde_json::to_value(inputs).map_err(S::Error::custom)?;
+            map.insert("inputs".into(), inputs);
+        }
+        if let Some(state_mutability) = &self.state_mutability {
+            map.insert("stateMutability".into(), state_mutability.clone().into());
+        }
+        map.serialize(serializer)
+    }
+}
+
+impl AbiEntry {
+    /// The entry of `value`, or the error and the object kept verbatim in `extra` if it doesn't
+    /// parse, like a parameter without type from an experimental compiler.
+    pub(crate) fn parse(value: Value) -> Result<AbiEntry, (String, AbiEntry)> {
+        let opaque = |extra: Map<String, Value>, raw: Option<Value>| AbiEntry {
+            name: None,
+            ty: None,
+            inputs: None,
+            state_mutability: None,
+            extra,
+            raw,
+        };
+        let object = match value {
+            Value::Object(object) => object,
+            value => {
+                let message = format!("abi entry {} is not an object", value);
+                return Err((message, opaque(Map::new(), Some(value))));
+            }
+        };
+        serde_json::from_value(Value::Object(object.clone()))
+            .map_err(|e| (e.to_string(), opaque(object, None)))
+    }
+
+    /// Why a function entry can't be annotated, none if it can.
+    pub(crate) fn malformed(&self) -> Option<&'static str> {
+        if self.ty.as_deref() != Some("function") {
+            None
+        } else if self.name.is_none() {
+            Some("function without name")
+        } else if self.inputs.is_none() {
+            Some("function without inputs")
+        } else {
+            None
+        }
+    }
+
+    pub fn is_function(&self) -> bool {
+        self.name.is_some() && self.ty.as_deref() == Some("function")
+    }
+
+    /// Whether the function may change state, older abis only carry `constant` for view functions.
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self.state_mutability.as_deref() {
+            Some(state_mutability) => state_mutability != "view" && state_mutability != "pure",
+            None => self.extra.get("constant").and_then(Value::as_bool) != Some(true),
+        }
+    }
+
+    /// Whether this is the entry reserved for the provenance of the annotation.
+    pub fn is_provenance(&self) -> bool {
+        self.ty.as_deref() == Some(crate::provenance::PROVENANCE_TYPE)
+    }
+
+    pub(crate) fn inputs(&self) -> &[AbiParam] {
+        self.inputs.as_deref().unwrap_or_default()
+    }
+
+    /// The ordinal and parameter whose head contains the calldata word `index`, none if the word
+    /// is past the head, i.e. in the tail holding the dynamic values.
+    pub(crate) fn param_at_word(&self, index: u32) -> Option<(usize, &AbiParam)> {
+        let mut start = 0;
+        for (ordinal, param) in self.inputs().iter().enumerate() {
+            start += param.head_words();
+            if index < start {
+                return Some((ordinal, param));
+            }
+        }
+        None
+    }
+
+    /// Number of 32 bytes words of the calldata head, after the selector.
+    pub(crate) fn head_words(&self) -> u32 {
+        self.inputs().iter().map(AbiParam::head_words).sum()
+    }
+
+    pub(crate) fn has_dynamic_inputs(&self) -> bool {
+        self.inputs().iter().any(AbiParam::is_dynamic)
+    }
+
+    /// The signature for humans, e.g. `place(Market.Order order, uint256 amount)`.
+    pub(crate) fn display_signature(&self) -> String {
+        let params = self
+            .inputs()
+            .iter()
+            .map(|param| {
+                if param.name.is_empty() {
+                    param.display_ty()
+                } else {
+                    format!("{} {}", param.display_ty(), param.name)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("{}({})", self.name.as_deref().unwrap_or_default(), params)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct AbiParam {
+    #[serde(default)]
+    pub(crate) name: String,
+    #[serde(rename = "type")]
+    pub(crate) ty: String,
+    #[serde(default)]
+    pub(crate) components: Option<Vec<AbiParam>>,
+    #[serde(flatten)]
+    pub(crate) extra: Map<String, Value>,
+}
+
+/// Written with the keys sorted too.
+impl Serialize for AbiParam {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = self.extra.clone();
+        map.insert("name".into(), self.name.clone().into());
+        map.insert("type".into(), self.ty.clone().into());
+        if let Some(components) = &self.components {
+            let components = serde_json::to_value(components).map_err(S::Error::custom)?;
+            map.insert("components".into(), components);
+        }
+        map.serialize(serializer)
+    }
+}
+
+/// Splits `uint256[2][]` into `uint256[2]` and the outermost array length, `None` for `[]`.
+fn split_array(ty: &str) -> Option<(&str, Option<u32>)> {
+    let ty = ty.strip_suffix(']')?;
+    let open = ty.rfind('[')?;
+    Some((&ty[..open], ty[open + 1..].parse().ok()))
+}
+
+fn is_dynamic_ty(ty: &str, components: &[AbiParam]) -> bool {
+    match split_array(ty) {
+        Some((_, None)) => true,
+        Some((element, Some(_))) => is_dynamic_ty(element, components),
+        None if ty == "tuple" => components.iter().any(AbiParam::is_dynamic),
+        None => ty == "string" || ty == "bytes",
+    }
+}
+
+fn head_words_of(ty: &str, components: &[AbiParam]) -> u32 {
+    if is_dynamic_ty(ty, components) {
+        return 1;
+    }
+    match split_array(ty) {
+        Some((element, Some(len))) => len * head_words_of(element, components),
+        _ if ty == "tuple" => components.iter().map(AbiParam::head_words).sum(),
+        _ => 1,
+    }
+}
+
+impl AbiParam {
+    /// Dynamic values are only referenced by an offset from the head.
+    pub(crate) fn is_dynamic(&self) -> bool {
+        is_dynamic_ty(&self.ty, self.components())
+    }
+
+    /// Number of 32 bytes words the parameter takes in the head of the calldata.
+    pub(crate) fn head_words(&self) -> u32 {
+        head_words_of(&self.ty, self.components())
+    }
+
+    /// The struct name from internalType (`struct Foo.Bar[]` gives `Foo.Bar[]`) instead of an
+    /// anonymous tuple, the plain type otherwise.
+    pub(crate) fn display_ty(&self) -> String {
+        let internal_type = self.extra.get("internalType").and_then(Value::as_str);
+        match internal_type.and_then(|internal_type| internal_type.strip_prefix("struct ")) {
+            Some(struct_name) => struct_name.to_string(),
+            None => self.ty.clone(),
+        }
+    }
+
+    pub(crate) fn components(&self) -> &[AbiParam] {
+        self.components.as_deref().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_signature() {
+        let fixture = r#"{
+                "inputs": [
+                    {
+                        "components": [
+                            {"internalType": "uint256", "name": "id", "type": "uint256"}
+                        ],
+                        "internalType": "struct Market.Order[]",
+                        "name": "orders",
+                        "type": "tuple[]"
+                    },
+                    {"internalType": "uint256", "name": "", "type": "uint256"}
+                ],
+                "name": "place",
+                "outputs": [],
+                "stateMutability": "nonpayable",
+                "type": "function"
+            }"#;
+        let entry: AbiEntry = serde_json::from_str(fixture).unwrap();
+        assert!(entry.is_function());
+        assert!(entry.is_mutating());
+        assert_eq!(
+            entry.display_signature(),
+            "place(Market.Order[] orders, uint256)"
+        );
+        assert!(entry.extra.contains_key("outputs"));
+        // the keys are written sorted like those of the untyped value
+        let value: Value = serde_json::from_str(fixture).unwrap();
+        assert_eq!(serde_json::to_string(&entry).unwrap(), value.to_string());
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        let value =
+            serde_json::json!({"inputs": [{"name": "to"}], "name": "f", "type": "function"});
+        let (_, opaque) = AbiEntry::parse(value.clone()).unwrap_err();
+        assert!(!opaque.is_function());
+        assert_eq!(serde_json::to_value(&opaque).unwrap(), value);
+        let entry = AbiEntry::parse(serde_json::json!({"name": null, "type": "function"})).unwrap();
+        assert_eq!(entry.malformed(), Some("function without name"));
+        let entry = AbiEntry::parse(serde_json::json!({"name": "f", "type": "function"})).unwrap();
+        assert_eq!(entry.malformed(), Some("function without inputs"));
+        let (message, opaque) = AbiEntry::parse(serde_json::json!("f()")).unwrap_err();
+        assert!(message.contains("not an object"));
+        assert!(!opaque.is_function());
+        assert_eq!(serde_json::to_value(&opaque).unwrap(), "f()");
+    }
+
+    #[test]
+    fn test_param_at_word() {
+        let entry: AbiEntry = serde_json::from_str(
+            r#"{
+                "inputs": [
+                    {"name": "name", "type": "string"},
+                    {"name": "pair", "type": "uint256[2]"},
+                    {
+                        "components": [
+                            {"name": "id", "type": "uint256"},
+                            {"name": "owner", "type": "address"}
+                        ],
+                        "name": "item",
+                        "type": "tuple"
+                    },
+                    {"name": "ids", "type": "uint256[3][]"},
+                    {"name": "to", "type": "address"}
+                ],
+                "name": "set",
+                "type": "function"
+            }"#,
+        )
+        .unwrap();
+        let ordinals = (0..8)
+            .map(|word| entry.param_at_word(word).map(|(ordinal, _)| ordinal))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ordinals,
+            vec![
+                Some(0),
+                Some(1),
+                Some(1),
+                Some(2),
+                Some(2),
+                Some(3),
+                Some(4),
+                None
+            ]
+        );
+        assert!(entry.has_dynamic_inputs());
+        assert_eq!(entry.head_words(), 7);
+    }
+}
@@ -0,0 +1,937 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use colored::Colorize;
+use log::info;
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::Deserializer;
+use serde_json::Value;
+
+use crate::abi::AbiEntry;
+use crate::cache::{AnnotationCache, SelectorCache};
+use crate::conflict::{
+    conflict_fields, parse_conflict_fields, ConflictInfo, ConflictType, EnvironmentType,
+    SlotFormat, CONFLICT_FIELDS_VERSION,
+};
+use crate::get_method_signature;
+use crate::library::CallerLayout;
+use crate::lint::matches_pattern;
+use crate::review::Reviewer;
+use crate::rules::{rule_conflicts, ConflictRule};
+use crate::script::Script;
+
+/// State shared by annotating all functions of an abi.
+pub struct Annotator<'a> {
+    /// sorted by selector
+    pub conflicts: &'a [ConflictInfo],
+    pub gm: bool,
+    pub slot_format: SlotFormat,
+    /// write parameter ordinals instead of calldata word offsets into Var conflicts
+    pub param_ordinals: bool,
+    /// set `"parallel": true` on functions that can run in parallel
+    pub mark_parallel: bool,
+    /// serialize mutating functions the analyzer knows nothing about with an All entry
+    pub default_all: bool,
+    /// write an explicit None entry for the functions still without entries
+    pub mark_none: bool,
+    pub cache: Option<AnnotationCache>,
+    pub selectors: SelectorCache,
+    pub unchanged: usize,
+    pub rewritten: usize,
+    pub functions: Vec<FunctionSummary>,
+    pub warnings: Vec<Warning>,
+    /// problems failing the run, the abi is left untouched
+    pub errors: Vec<Warning>,
+    /// replaces the provenance entry of a previous run at the end of the abi
+    pub provenance: Option<AbiEntry>,
+    /// only show what would change, nothing is written
+    pub dry_run: bool,
+    /// asks before writing the conflicts of every function
+    pub reviewer: Option<Reviewer>,
+    /// transforms the conflicts of every function
+    pub script: Option<Script>,
+    /// also write the selectors by hash algorithm, for clients of either crypto suite
+    pub named_selectors: bool,
+    /// more conflicts for one function usually mean the analyzer blew up
+    pub max_conflicts: Option<usize>,
+    pub cap_policy: CapPolicy,
+    /// fail if a function whose name or signature matches one of the patterns, or any if there
+    /// are none, is serialized with All
+    pub forbid_all: Option<Vec<String>>,
+    /// fail the run on warnings and unmatched selectors too
+    pub fail_on_warning: bool,
+    /// the conflicts on these slots are dropped
+    pub ignore_slots: BTreeSet<u32>,
+    /// the number of conflicts dropped for their slot
+    pub ignored: usize,
+    /// only the conflicts of these kinds are kept, all if none, for a reduced conflict model
+    pub kinds: Option<Vec<ConflictType>>,
+    /// rules adding conflicts to the functions matching them
+    pub rules: Vec<ConflictRule>,
+    /// the abi is known to be of an interface or abstract contract, which can't be annotated
+    pub expect_interface: bool,
+    /// the number of mutating functions something had conflicts for
+    pub covered_mutating: usize,
+    /// the storage layout of the contract calling the library the abi is of
+    pub library: Option<CallerLayout>,
+    /// only the functions with these selectors are annotated, all if empty, the others are left
+    /// untouched
+    pub only_selectors: BTreeSet<u32>,
+}
+
+/// What happens to a function with more conflicts than the maximum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapPolicy {
+    /// serialize it with a single All entry
+    Truncate,
+    /// fail the run
+    Fail,
+}
+
+impl FromStr for CapPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "truncate" => Ok(CapPolicy::Truncate),
+            "fail" => Ok(CapPolicy::Fail),
+            _ => Err(format!("unknown policy {}", s)),
+        }
+    }
+}
+
+/// Something about a function that looks wrong but doesn't stop the annotation.
+pub struct Warning {
+    /// names the kind of problem in machine readable reports
+    pub(crate) rule: &'static str,
+    pub(crate) signature: String,
+    pub(crate) message: String,
+}
+
+/// What was found for one function, kept for the summaries printed or written after the run.
+pub struct FunctionSummary {
+    pub(crate) name: String,
+    pub(crate) signature: String,
+    pub(crate) display_signature: String,
+    pub(crate) selector: u32,
+    pub(crate) mutating: bool,
+    pub(crate) conflicts: Vec<ConflictInfo>,
+    /// the conflictFields the function carried before, null if none
+    pub(crate) previous_fields: Value,
+    /// the conflictFields written, an empty array if none
+    pub(crate) fields: Value,
+    /// the sorted ordinals of the parameters keying the Var conflicts, none if some keys are no
+    /// parameters or the conflicts were not computed but reviewed or kept
+    pub(crate) key_params: Option<Vec<usize>>,
+}
+
+#[cfg(test)]
+impl FunctionSummary {
+    /// A mutating function that had no conflictFields, for the tests of what is built from the
+    /// summaries.
+    pub(crate) fn for_test(signature: &str, conflicts: Vec<ConflictInfo>) -> FunctionSummary {
+        FunctionSummary {
+            name: signature.split('(').next().unwrap().to_string(),
+            signature: signature.into(),
+            display_signature: signature.into(),
+            selector: crate::get_method_id(signature, false),
+            mutating: true,
+            conflicts,
+            previous_fields: Value::Null,
+            fields: Value::Null,
+            key_params: None,
+        }
+    }
+}
+
+impl Annotator<'_> {
+    /// The entry `index` of the abi, malformed ones are kept as they are and reported instead of
+    /// failing the run.
+    pub fn entry(&mut self, index: usize, value: Value) -> AbiEntry {
+        AbiEntry::parse(value).unwrap_or_else(|(message, opaque)| {
+            self.warnings.push(Warning {
+                rule: "malformed-entry",
+                signature: format!("entry #{}", index),
+                message: format!("{}, left as is", message),
+            });
+            opaque
+        })
+    }
+
+    /// Inserts the conflicts and selectors into `method` if it is a function.
+    pub fn annotate(&mut self, method: &mut AbiEntry) {
+        if let Some(message) = method.malformed() {
+            self.warnings.push(Warning {
+                rule: "malformed-entry",
+                signature: method.display_signature(),
+                message: format!("{}, left as is", message),
+            });
+            return;
+        }
+        if !method.is_function() {
+            return;
+        }
+        let signature = get_method_signature(method);
+        let method_id = self.selectors.method_id(&signature, self.gm);
+        if !self.only_selectors.is_empty() && !self.only_selectors.contains(&method_id) {
+            self.keep(method, signature, method_id);
+            return;
+        }
+        let mut method_conflicts = self.conflicts_of(method_id).to_vec();
+        let unknown = [EnvironmentType::Unknown as u32];
+        if method_conflicts
+            .iter()
+            .any(|conflict| conflict.kind == ConflictType::Env && conflict.value == unknown)
+        {
+            self.warnings.push(Warning {
+                rule: "unknown-environment",
+                signature: signature.clone(),
+                message: "the analyzer output has an unknown environment".into(),
+            });
+        }
+        let name = method.name.as_deref().unwrap_or_default();
+        if let Some(other) = method_conflicts
+            .iter()
+            .filter_map(|conflict| conflict.function.as_deref())
+            .find(|other| *other != name)
+        {
+            self.warnings.push(Warning {
+                rule: "function-name-mismatch",
+                signature: signature.clone(),
+                message: format!(
+                    "the analyzer output names the function {}, abi and analyzer may not match",
+                    other
+                ),
+            });
+        }
+        if let Some(caller_layout) = &self.library {
+            if let Err(message) = caller_layout.rebase(method, &mut method_conflicts) {
+                self.errors.push(Warning {
+                    rule: "library-slot",
+                    signature: signature.clone(),
+                    message,
+                });
+            }
+        }
+        let found = method_conflicts.len();
+        let ignore_slots = &self.ignore_slots;
+        method_conflicts.retain(|conflict| {
+            conflict
+                .slot
+                .map_or(true, |slot| !ignore_slots.contains(&slot))
+        });
+        self.ignored += found - method_conflicts.len();
+        if let Some(kinds) = &self.kinds {
+            method_conflicts.retain(|conflict| kinds.contains(&conflict.kind));
+        }
+        for conflict in rule_conflicts(&self.rules, name, &signature, method_id) {
+            if !method_conflicts.contains(&conflict) {
+                method_conflicts.push(conflict);
+            }
+        }
+        if method.is_mutating() && (found > 0 || !method_conflicts.is_empty()) {
+            self.covered_mutating += 1;
+        }
+        // conflicts all on ignored slots or of skipped kinds are no missing analyzer data
+        if found == 0 && method_conflicts.is_empty() && self.default_all && method.is_mutating() {
+            info!("{} has no analyzer data, default to All", signature);
+            method_conflicts.push(ConflictInfo::new(ConflictType::All, method_id));
+        }
+        if method_conflicts.is_empty() && self.mark_none {
+            method_conflicts.push(ConflictInfo::new(ConflictType::None, method_id));
+        }
+        if let Some(script) = &self.script {
+            method_conflicts = script.transform(name, &signature, method_id, method_conflicts);
+        }
+        if let Some(max_conflicts) = self.max_conflicts {
+            if method_conflicts.len() > max_conflicts {
+                let message = format!(
+                    "{} conflicts, more than the maximum of {}",
+                    method_conflicts.len(),
+                    max_conflicts
+                );
+                match self.cap_policy {
+                    CapPolicy::Truncate => {
+                        self.warnings.push(Warning {
+                            rule: "too-many-conflicts",
+                            signature: signature.clone(),
+                            message: format!("{}, serialized with All instead", message),
+                        });
+                        method_conflicts = vec![ConflictInfo::new(ConflictType::All, method_id)];
+                    }
+                    CapPolicy::Fail => self.errors.push(Warning {
+                        rule: "too-many-conflicts",
+                        signature: signature.clone(),
+                        message,
+                    }),
+                }
+            }
+        }
+        // the entries of a group must be adjacent, the stable sort keeps the order otherwise
+        method_conflicts.sort_by_key(|conflict| conflict.group);
+        let mut key_params = Some(Vec::new());
+        for conflict in &mut method_conflicts {
+            if conflict.kind == ConflictType::Var {
+                let params = self.resolve_params(method, &signature, conflict);
+                key_params = key_params.zip(params).map(|(mut key_params, params)| {
+                    key_params.extend(params);
+                    key_params
+                });
+            }
+        }
+        let mut fields = conflict_fields(&method_conflicts, self.slot_format);
+        let previous_fields = method
+            .extra
+            .get("conflictFields")
+            .cloned()
+            .unwrap_or_default();
+        if let Some(reviewer) = self.reviewer.as_mut() {
+            if previous_fields != fields {
+                match reviewer.review(&method.display_signature(), &previous_fields, fields) {
+                    Some(reviewed) => {
+                        if reviewed != conflict_fields(&method_conflicts, self.slot_format) {
+                            method_conflicts = parse_conflict_fields(&reviewed, method_id);
+                            key_params = None;
+                        }
+                        fields = reviewed;
+                    }
+                    None => {
+                        self.keep(method, signature, method_id);
+                        return;
+                    }
+                }
+            }
+        }
+        let has_conflicts = fields.as_array().map_or(false, |fields| !fields.is_empty());
+        if let Some(patterns) = &self.forbid_all {
+            let name = method.name.as_deref().unwrap_or_default();
+            let forbidden = patterns.is_empty()
+                || patterns.iter().any(|pattern| {
+                    matches_pattern(pattern, name) || matches_pattern(pattern, &signature)
+                });
+            let all = fields
+                .as_array()
+                .into_iter()
+                .flatten()
+                .any(|field| field["kind"].as_u64() == Some(ConflictType::All as u64));
+            if forbidden && all {
+                self.errors.push(Warning {
+                    rule: "forbidden-all",
+                    signature: signature.clone(),
+                    message: String::from("serialized with All, which --forbid-all forbids"),
+                });
+            }
+        }
+        let parallel = !method_conflicts.is_empty()
+            && method_conflicts
+                .iter()
+                .all(|conflict| conflict.kind != ConflictType::All);
+        let summary = FunctionSummary {
+            name: method.name.clone().unwrap_or_default(),
+            signature: signature.clone(),
+            display_signature: method.display_signature(),
+            selector: method_id,
+            mutating: method.is_mutating(),
+            conflicts: method_conflicts,
+            previous_fields,
+            fields,
+            key_params: key_params.map(|mut key_params| {
+                key_params.sort_unstable();
+                key_params.dedup();
+                key_params
+            }),
+        };
+        if let Some(cache) = self.cache.as_mut() {
+            // the abi may have been replaced since the last run, so also check that it
+            // still carries the annotation the cache remembers
+            // so are the keys the options write besides the conflictFields, toggling one of them
+            // rewrites the function
+            let written = serde_json::json!({
+                "conflictFields": summary.fields,
+                "parallel": self.mark_parallel && parallel,
+                "namedSelectors": self.named_selectors,
+            });
+            if cache.update(method_id, &written)
+                && method.extra.contains_key("selector")
+                && method.extra.contains_key("conflictFields") == has_conflicts
+            {
+                info!("{} unchanged", signature);
+                self.unchanged += 1;
+                self.functions.push(summary);
+                return;
+            }
+        }
+        self.rewritten += 1;
+        method.extra.remove("conflictFields");
+        method.extra.remove("conflictFieldsVersion");
+        // only copied into the entry when it is rewritten, the summary keeps the original
+        if has_conflicts {
+            method
+                .extra
+                .insert("conflictFields".into(), summary.fields.clone());
+            method.extra.insert(
+                "conflictFieldsVersion".into(),
+                CONFLICT_FIELDS_VERSION.into(),
+            );
+        }
+        self.functions.push(summary);
+        method.extra.remove("parallel");
+        if self.mark_parallel && parallel {
+            method.extra.insert("parallel".into(), true.into());
+        }
+        let selectors = self.selectors.method_ids(&signature);
+        method.extra.remove("selectors");
+        if self.named_selectors {
+            let named = ["keccak256", "sm3"]
+                .iter()
+                .zip(&selectors)
+                .map(|(algorithm, selector)| (algorithm.to_string(), Value::from(*selector)))
+                .collect::<serde_json::Map<String, Value>>();
+            method.extra.insert("selectors".into(), named.into());
+        }
+        method
+            .extra
+            .insert("selector".into(), serde_json::to_value(selectors).unwrap());
+    }
+
+    /// Leaves `method` as it is, summarized with the conflictFields it already carries.
+    fn keep(&mut self, method: &AbiEntry, signature: String, selector: u32) {
+        let previous_fields = method
+            .extra
+            .get("conflictFields")
+            .cloned()
+            .unwrap_or_default();
+        let fields = if previous_fields.is_null() {
+            Value::Array(Vec::new())
+        } else {
+            previous_fields.clone()
+        };
+        self.unchanged += 1;
+        self.functions.push(FunctionSummary {
+            name: method.name.clone().unwrap_or_default(),
+            signature,
+            display_signature: method.display_signature(),
+            selector,
+            mutating: method.is_mutating(),
+            conflicts: parse_conflict_fields(&fields, selector),
+            previous_fields,
+            fields,
+            key_params: None,
+        });
+    }
+
+    /// The conflicts of the function with `selector`, found by bisecting instead of scanning all
+    /// conflicts for every function.
+    fn conflicts_of(&self, selector: u32) -> &[ConflictInfo] {
+        let start = self
+            .conflicts
+            .partition_point(|conflict| conflict.selector < selector);
+        let len = self.conflicts[start..].partition_point(|conflict| conflict.selector == selector);
+        &self.conflicts[start..start + len]
+    }
+
+    /// The analyzer gives calldata word offsets, which are only the parameter positions as long
+    /// as every parameter before is a single word, so map them through the head layout. Returns
+    /// the ordinals of the key parameters, none if some words are no parameter heads.
+    fn resolve_params(
+        &mut self,
+        method: &AbiEntry,
+        signature: &str,
+        conflict: &mut ConflictInfo,
+    ) -> Option<Vec<usize>> {
+        let mut ordinals = Vec::with_capacity(conflict.value.len());
+        let mut resolved = true;
+        conflict.names.clear();
+        for index in &conflict.value {
+            match method.param_at_word(*index) {
+                Some((ordinal, param)) => {
+                    ordinals.push(ordinal as u32);
+                    conflict.names.push(param.name.clone());
+                }
+                None => {
+                    let message = if method.has_dynamic_inputs() {
+                        format!("Var index {} points into the dynamic tail", index)
+                    } else {
+                        format!(
+                            "Var index {} is past the {} head words of the parameters, the \
+                             analyzer output probably doesn't belong to this abi",
+                            index,
+                            method.head_words()
+                        )
+                    };
+                    self.warnings.push(Warning {
+                        rule: "unresolved-key",
+                        signature: signature.to_string(),
+                        message,
+                    });
+                    ordinals.push(*index);
+                    conflict.names.push(String::new());
+                    resolved = false;
+                }
+            }
+        }
+        let key_params = ordinals.iter().map(|ordinal| *ordinal as usize).collect();
+        if self.param_ordinals {
+            conflict.value = ordinals;
+        }
+        if resolved {
+            Some(key_params)
+        } else {
+            None
+        }
+    }
+
+    /// Fails the run on abis of interfaces or abstract contracts, which have no storage for the
+    /// analyzer to find conflicts on: the abi would be written without any.
+    fn check_interface(&mut self) {
+        let mutating = self
+            .functions
+            .iter()
+            .filter(|function| function.mutating)
+            .count();
+        let message = if self.expect_interface {
+            String::from("--expect-interface is given, interfaces have no storage to analyze")
+        } else if mutating > 0 && self.covered_mutating == 0 && self.only_selectors.is_empty() {
+            format!(
+                "none of the {} mutating functions has conflicts, is this the abi of an interface \
+                 or abstract contract? they have no storage to analyze",
+                mutating
+            )
+        } else {
+            return;
+        };
+        self.errors.push(Warning {
+            rule: "interface-abi",
+            signature: String::from("abi"),
+            message: format!(
+                "{}, annotate the abi of the implementing contract, or the proxy abi with the \
+                 proxy command",
+                message
+            ),
+        });
+    }
+
+    /// Fails the run on selectors given to annotate only which match no function, likely typos
+    /// or of another version of the abi.
+    fn check_only_selectors(&mut self) {
+        let matched = self
+            .functions
+            .iter()
+            .map(|function| function.selector)
+            .collect::<BTreeSet<u32>>();
+        for selector in self.only_selectors.difference(&matched) {
+            self.errors.push(Warning {
+                rule: "unknown-selector",
+                signature: format!("0x{:08x}", selector),
+                message: String::from("given with --selector but matches no function of the abi"),
+            });
+        }
+    }
+
+    /// Persists the caches, returns false if the abi doesn't need to be written at all, this
+    /// is a dry run or there were errors.
+    pub fn finish(&mut self) -> bool {
+        self.check_unmatched();
+        self.check_interface();
+        self.check_only_selectors();
+        self.print_overloads();
+        let level = if self.fail_on_warning {
+            "error".red()
+        } else {
+            "warning".yellow()
+        };
+        for warning in &self.warnings {
+            eprintln!("{}: {}: {}", level, warning.signature, warning.message);
+        }
+        if self.fail_on_warning {
+            for selector in self.unmatched_selectors() {
+                eprintln!("{}: 0x{:08x}: matches no function", level, selector);
+            }
+        }
+        for error in &self.errors {
+            eprintln!("{}: {}: {}", "error".red(), error.signature, error.message);
+        }
+        if self.dry_run || self.failed() {
+            return false;
+        }
+        self.selectors.save();
+        if let Some(cache) = &self.cache {
+            cache.save();
+            if self.rewritten == 0 {
+                return false;
+            }
+            eprintln!(
+                "{} functions rewritten, {} unchanged",
+                self.rewritten, self.unchanged
+            );
+        }
+        true
+    }
+
+    /// Whether the run failed, on errors or, with `fail_on_warning`, on warnings and unmatched
+    /// selectors.
+    pub fn failed(&self) -> bool {
+        !self.errors.is_empty()
+            || (self.fail_on_warning
+                && (!self.warnings.is_empty() || !self.unmatched_selectors().is_empty()))
+    }
+
+    /// The selectors of the analyzer output matching no function of the abi.
+    pub(crate) fn unmatched_selectors(&self) -> BTreeSet<u32> {
+        let matched = self
+            .functions
+            .iter()
+            .map(|function| function.selector)
+            .collect::<BTreeSet<u32>>();
+        self.conflicts
+            .iter()
+            .map(|conflict| conflict.selector)
+            .filter(|selector| !matched.contains(selector))
+            .collect()
+    }
+
+    /// Selectors of the analyzer output matching no function usually come from hashing with the
+    /// other algorithm, warn about those that match under it.
+    fn check_unmatched(&mut self) {
+        if !self.gm && cfg!(not(feature = "gm")) {
+            return;
+        }
+        let mut other = BTreeMap::new();
+        for function in &self.functions {
+            let selector = self.selectors.method_id(&function.signature, !self.gm);
+            other.insert(selector, function.signature.clone());
+        }
+        let unmatched = self.unmatched_selectors();
+        let (algorithm, hint) = if self.gm {
+            ("keccak256", "was the analyzer run without --gm?")
+        } else {
+            ("SM3", "did you mean --gm?")
+        };
+        for selector in unmatched {
+            if let Some(signature) = other.get(&selector) {
+                self.warnings.push(Warning {
+                    rule: "other-hash-algorithm",
+                    signature: signature.clone(),
+                    message: format!(
+                        "selector 0x{:08x} matches {} under {}, {}",
+                        selector, signature, algorithm, hint
+                    ),
+                });
+            }
+        }
+    }
+
+    /// Conflicts are matched by selector, so list all overloads of a conflicting function to
+    /// make clear which of them the conflicts belong to.
+    fn print_overloads(&self) {
+        let mut by_name: BTreeMap<&str, Vec<&FunctionSummary>> = BTreeMap::new();
+        for function in &self.functions {
+            by_name.entry(&function.name).or_default().push(function);
+        }
+        for (name, overloads) in by_name {
+            if overloads.len() < 2 || overloads.iter().all(|f| f.conflicts.is_empty()) {
+                continue;
+            }
+            eprintln!("{} is overloaded:", name.yellow());
+            for function in overloads {
+                eprintln!(
+                    "    {} 0x{:08x} {} conflicts",
+                    function.signature,
+                    function.selector,
+                    function.conflicts.len()
+                );
+            }
+        }
+    }
+}
+
+/// Annotates the abi array at `input` entry by entry and writes the result to `output` as it
+/// goes, so huge proxy or aggregator abis are never held in memory as a whole.
+pub fn annotate_stream(
+    annotator: &mut Annotator,
+    input: &Path,
+    output: &Path,
+) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(output)?);
+    let mut deserializer =
+        serde_json::Deserializer::from_reader(BufReader::new(File::open(input)?));
+    deserializer.deserialize_seq(EntryVisitor {
+        annotator,
+        writer: &mut writer,
+    })?;
+    deserializer.end()?;
+    writer.flush()
+}
+
+struct EntryVisitor<'a, 'b, W> {
+    annotator: &'a mut Annotator<'b>,
+    writer: W,
+}
+
+impl<'de, W: Write> Visitor<'de> for EntryVisitor<'_, '_, W> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an abi array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        self.writer.write_all(b"[").map_err(A::Error::custom)?;
+        let mut first = true;
+        let mut index = 0;
+        while let Some(value) = seq.next_element::<Value>()? {
+            let mut entry = self.annotator.entry(index, value);
+            index += 1;
+            if self.annotator.provenance.is_some() && entry.is_provenance() {
+                continue;
+            }
+            self.annotator.annotate(&mut entry);
+            if !first {
+                self.writer.write_all(b",").map_err(A::Error::custom)?;
+            }
+            first = false;
+            serde_json::to_writer(&mut self.writer, &entry).map_err(A::Error::custom)?;
+        }
+        if let Some(provenance) = &self.annotator.provenance {
+            if !first {
+                self.writer.write_all(b",").map_err(A::Error::custom)?;
+            }
+            serde_json::to_writer(&mut self.writer, provenance).map_err(A::Error::custom)?;
+        }
+        self.writer.write_all(b"]").map_err(A::Error::custom)
+    }
+}
+
+/// The selectors of all functions of the abi array at `path`, read entry by entry.
+pub fn abi_selectors(path: &Path, gm: bool) -> std::io::Result<BTreeSet<u32>> {
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(File::open(path)?));
+    let selectors = deserializer.deserialize_seq(SelectorVisitor { gm })?;
+    deserializer.end()?;
+    Ok(selectors)
+}
+
+struct SelectorVisitor {
+    gm: bool,
+}
+
+impl<'de> Visitor<'de> for SelectorVisitor {
+    type Value = BTreeSet<u32>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an abi array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<BTreeSet<u32>, A::Error> {
+        let mut selectors = BTreeSet::new();
+        while let Some(value) = seq.next_element::<Value>()? {
+            // the annotation warns about the malformed entries, which it leaves as they are
+            let entry = match AbiEntry::parse(value) {
+                Ok(entry) if entry.malformed().is_none() => entry,
+                _ => continue,
+            };
+            if entry.is_function() {
+                selectors.insert(crate::get_method_id(&get_method_signature(&entry), self.gm));
+            }
+        }
+        Ok(selectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::AnnotationOptions;
+
+    /// A mutating function of one static parameter.
+    fn set() -> AbiEntry {
+        serde_json::from_str(
+            r#"{
+                "inputs": [{"name": "value", "type": "uint256"}],
+                "name": "set",
+                "type": "function"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_abi_selectors() {
+        let path = std::env::temp_dir().join("test_abi_selectors.abi");
+        std::fs::write(
+            &path,
+            r#"[
+                7,
+                {"name": "get", "type": "function"},
+                {"inputs": [{"name": "value", "type": "uint256"}], "name": "set", "type": "function"}
+            ]"#,
+        )
+        .unwrap();
+        let selectors = abi_selectors(&path, false).unwrap();
+        assert_eq!(selectors, vec![0x60fe47b1].into_iter().collect());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_max_conflicts() {
+        let mut conflicts = vec![
+            ConflictInfo::new(ConflictType::All, 0x60fe47b1),
+            ConflictInfo::new(ConflictType::None, 0x60fe47b1),
+        ];
+        conflicts[0].function = Some("put".into());
+        let mut annotator = AnnotationOptions::new()
+            .max_conflicts(Some(1), CapPolicy::Fail)
+            .dry_run(true)
+            .annotator(&conflicts);
+        let mut entry = set();
+        annotator.annotate(&mut entry);
+        assert_eq!(annotator.errors.len(), 1);
+        assert_eq!(annotator.errors[0].rule, "too-many-conflicts");
+        assert_eq!(annotator.warnings[0].rule, "function-name-mismatch");
+    }
+
+    #[test]
+    fn test_cache_options() {
+        let abi = std::env::temp_dir().join("test_cache_options.abi");
+        let mut conflict = ConflictInfo::new(ConflictType::Var, 0x60fe47b1);
+        conflict.slot = Some(0);
+        conflict.value = vec![0];
+        let conflicts = vec![conflict];
+        let mut entry = set();
+        let run = |entry: &mut AbiEntry, mark_parallel: bool| {
+            let mut annotator = AnnotationOptions::new()
+                .mark_parallel(mark_parallel)
+                .annotator(&conflicts);
+            annotator.cache = Some(AnnotationCache::load(&abi, false));
+            annotator.annotate(entry);
+            annotator.cache.as_ref().unwrap().save();
+            (annotator.rewritten, annotator.unchanged)
+        };
+        run(&mut entry, false);
+        assert_eq!(run(&mut entry, false), (0, 1));
+        assert_eq!(run(&mut entry, true), (1, 0));
+        assert!(entry.extra["parallel"].as_bool().unwrap());
+        assert_eq!(run(&mut entry, false), (1, 0));
+        assert!(!entry.extra.contains_key("parallel"));
+        let mut cache = abi.file_name().unwrap().to_os_string();
+        cache.push(".conflicts_cache");
+        std::fs::remove_file(abi.with_file_name(cache)).unwrap();
+    }
+
+    #[test]
+    fn test_fail_on_warning() {
+        let conflicts = vec![ConflictInfo::new(ConflictType::All, 7)];
+        assert!(!AnnotationOptions::new().annotator(&conflicts).failed());
+        let annotator = AnnotationOptions::new()
+            .fail_on_warning(true)
+            .annotator(&conflicts);
+        assert!(annotator.failed());
+    }
+
+    #[test]
+    fn test_unknown_environment() {
+        let mut conflict = ConflictInfo::new(ConflictType::Env, 0x60fe47b1);
+        conflict.slot = Some(0);
+        conflict.value = vec![EnvironmentType::Unknown as u32];
+        let conflicts = vec![conflict];
+        let mut annotator = AnnotationOptions::new()
+            .fail_on_warning(true)
+            .dry_run(true)
+            .annotator(&conflicts);
+        let mut entry = set();
+        annotator.annotate(&mut entry);
+        assert_eq!(annotator.warnings[0].rule, "unknown-environment");
+        assert!(annotator.failed());
+    }
+
+    #[test]
+    fn test_interface() {
+        let conflicts = vec![ConflictInfo::new(ConflictType::All, 7)];
+        let mut annotator = AnnotationOptions::new().annotator(&conflicts);
+        let mut entry: AbiEntry = serde_json::from_str(
+            r#"{"inputs": [], "name": "f", "stateMutability": "nonpayable", "type": "function"}"#,
+        )
+        .unwrap();
+        annotator.annotate(&mut entry);
+        assert!(!annotator.finish());
+        assert_eq!(annotator.errors[0].rule, "interface-abi");
+
+        let mut annotator = AnnotationOptions::new().annotator(&[]);
+        assert!(annotator.finish());
+        let mut annotator = AnnotationOptions::new()
+            .expect_interface(true)
+            .annotator(&[]);
+        assert!(!annotator.finish());
+    }
+
+    #[test]
+    fn test_ignore_slots() {
+        let mut counter = ConflictInfo::new(ConflictType::Const, 0x60fe47b1);
+        counter.slot = Some(0);
+        let mut balance = ConflictInfo::new(ConflictType::Var, 0x60fe47b1);
+        balance.slot = Some(1);
+        let conflicts = vec![counter, balance];
+        let mut annotator = AnnotationOptions::new()
+            .ignore_slots(vec![0].into_iter().collect())
+            .annotator(&conflicts);
+        let mut entry = set();
+        annotator.annotate(&mut entry);
+        assert_eq!(annotator.ignored, 1);
+        assert_eq!(annotator.functions[0].conflicts, conflicts[1..]);
+
+        let mut annotator = AnnotationOptions::new()
+            .kinds(Some(vec![ConflictType::Const]))
+            .annotator(&conflicts);
+        annotator.annotate(&mut entry);
+        assert_eq!(annotator.functions[0].conflicts, conflicts[..1]);
+    }
+
+    #[test]
+    fn test_group_adjacency() {
+        let var = |group: Option<u32>, slot: u32| {
+            let mut conflict = ConflictInfo::new(ConflictType::Var, 0x60fe47b1);
+            conflict.group = group;
+            conflict.slot = Some(slot);
+            conflict
+        };
+        let conflicts = vec![var(Some(1), 0), var(None, 1), var(Some(1), 2), var(None, 3)];
+        let mut annotator = AnnotationOptions::new().annotator(&conflicts);
+        let mut entry = set();
+        annotator.annotate(&mut entry);
+        let slots = annotator.functions[0]
+            .conflicts
+            .iter()
+            .map(|conflict| conflict.slot.unwrap())
+            .collect::<Vec<u32>>();
+        assert_eq!(slots, vec![1, 3, 0, 2]);
+    }
+
+    #[test]
+    fn test_only_selectors() {
+        let conflicts = vec![ConflictInfo::new(ConflictType::All, 0x60fe47b1)];
+        let mut annotator = AnnotationOptions::new()
+            .only_selectors(vec![0x60fe47b1, 7].into_iter().collect())
+            .annotator(&conflicts);
+        let mut set = set();
+        let mut get: AbiEntry = serde_json::from_str(
+            r#"{"conflictFields": [{"kind": 0}], "inputs": [], "name": "get", "type": "function"}"#,
+        )
+        .unwrap();
+        let untouched = get.clone();
+        annotator.annotate(&mut set);
+        annotator.annotate(&mut get);
+        assert_eq!(get, untouched);
+        assert_eq!((annotator.rewritten, annotator.unchanged), (1, 1));
+        // kept for the sinks writing all functions, with what it carries
+        assert_eq!(annotator.functions[1].signature, "get()");
+        assert_eq!(
+            annotator.functions[1].fields,
+            serde_json::json!([{"kind": 0}])
+        );
+        assert!(!annotator.finish());
+        assert_eq!(annotator.errors[0].signature, "0x00000007");
+    }
+}
@@ -0,0 +1,193 @@
+use std::str::FromStr;
+
+use crate::conflict::{ConflictInfo, ConflictType};
+use crate::source::ConflictSource;
+
+/// A function of a system contract by signature, keyed by these calldata words in the slot of
+/// the table it writes, or serialized with All if none. The slots only tell the tables of one
+/// contract apart.
+type BuiltinFunction = (&'static str, Option<(u32, &'static [u32])>);
+
+/// The precompiled contracts of FISCO BCOS 2.x the analyzer can't see into, by the name of
+/// `--builtin`. Only static parameters can key a function: the head word of a `string` is the
+/// offset of its value, the same on every call, so functions of string parameters only are
+/// serialized with All.
+const BUILTINS: [(&str, &[BuiltinFunction]); 7] = [
+    (
+        "table-factory",
+        &[("createTable(string,string,string)", None)],
+    ),
+    (
+        "crud",
+        &[
+            ("insert(string,string,string,string)", None),
+            ("update(string,string,string,string,string)", None),
+            ("remove(string,string,string,string)", None),
+        ],
+    ),
+    ("cns", &[("insert(string,string,string,string)", None)]),
+    // the sealer list is read by every block, changes of it are serialized
+    (
+        "consensus",
+        &[
+            ("addSealer(string)", None),
+            ("addObserver(string)", None),
+            ("remove(string)", None),
+        ],
+    ),
+    ("system-config", &[("setValueByKey(string,string)", None)]),
+    (
+        "permission",
+        &[
+            ("insert(string,string)", None),
+            ("remove(string,string)", None),
+            // the write permissions of contracts are another table than those of tables
+            ("grantWrite(address,address)", Some((1, &[0]))),
+            ("revokeWrite(address,address)", Some((1, &[0]))),
+        ],
+    ),
+    (
+        "parallel-config",
+        &[
+            (
+                "registerParallelFunctionInternal(address,string,uint256)",
+                Some((0, &[0])),
+            ),
+            (
+                "unregisterParallelFunctionInternal(address,string)",
+                Some((0, &[0])),
+            ),
+        ],
+    ),
+];
+
+/// The shipped conflicts of a system contract, given instead of analyzer outputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Builtin {
+    name: &'static str,
+    functions: &'static [BuiltinFunction],
+}
+
+impl FromStr for Builtin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BUILTINS
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(name, functions)| Builtin { name, functions })
+            .ok_or_else(|| {
+                let names = BUILTINS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<&str>>();
+                format!("unknown builtin {}, one of {}", s, names.join(", "))
+            })
+    }
+}
+
+impl Builtin {
+    pub(crate) fn conflicts(&self, gm: bool) -> Vec<ConflictInfo> {
+        self.functions
+            .iter()
+            .map(|(signature, words)| {
+                let selector = crate::get_method_id(signature, gm);
+                match words {
+                    Some((slot, words)) => ConflictInfo {
+                        slot: Some(*slot),
+                        value: words.to_vec(),
+                        ..ConflictInfo::new(ConflictType::Var, selector)
+                    },
+                    None => ConflictInfo::new(ConflictType::All, selector),
+                }
+            })
+            .collect()
+    }
+}
+
+pub struct BuiltinSource {
+    pub builtin: Builtin,
+    pub gm: bool,
+}
+
+impl ConflictSource for BuiltinSource {
+    fn name(&self) -> String {
+        format!("builtin {}", self.builtin.name)
+    }
+
+    fn load(&self) -> Result<Vec<ConflictInfo>, String> {
+        Ok(self.builtin.conflicts(self.gm))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::AbiEntry;
+    use crate::conflict::{conflict_fields, Environments, SlotFormat, CONFLICT_FIELDS_VERSION};
+    use crate::validate::validate_entries;
+
+    #[test]
+    fn test_builtin() {
+        let consensus: Builtin = "consensus".parse().unwrap();
+        assert_eq!(consensus.functions.len(), 3);
+        assert!(consensus.functions.iter().all(|(_, words)| words.is_none()));
+        assert!("kv"
+            .parse::<Builtin>()
+            .unwrap_err()
+            .contains("one of table-factory, crud"));
+    }
+
+    #[test]
+    fn test_builtin_static_keys() {
+        for (name, functions) in BUILTINS.iter() {
+            for (signature, words) in functions.iter() {
+                let (function, params) = signature.trim_end_matches(')').split_once('(').unwrap();
+                let inputs = params
+                    .split(',')
+                    .map(|ty| serde_json::json!({"name": "", "type": ty}))
+                    .collect::<Vec<serde_json::Value>>();
+                let entry = AbiEntry::parse(serde_json::json!({
+                    "inputs": inputs,
+                    "name": function,
+                    "type": "function"
+                }))
+                .unwrap();
+                for word in words.iter().flat_map(|(_, words)| words.iter()) {
+                    let (_, param) = entry.param_at_word(*word).unwrap();
+                    assert!(!param.is_dynamic(), "{}: {} word {}", name, signature, word);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_builtin_validates() {
+        for (name, functions) in BUILTINS.iter() {
+            let conflicts = Builtin { name, functions }.conflicts(false);
+            let entries = functions
+                .iter()
+                .zip(&conflicts)
+                .map(|((signature, _), conflict)| {
+                    let (name, params) = signature.trim_end_matches(')').split_once('(').unwrap();
+                    let inputs = params
+                        .split(',')
+                        .map(|ty| serde_json::json!({"name": "", "type": ty}))
+                        .collect::<Vec<serde_json::Value>>();
+                    let fields =
+                        conflict_fields(std::slice::from_ref(conflict), SlotFormat::Decimal);
+                    AbiEntry::parse(serde_json::json!({
+                        "conflictFields": fields,
+                        "conflictFieldsVersion": CONFLICT_FIELDS_VERSION,
+                        "inputs": inputs,
+                        "name": name,
+                        "type": "function"
+                    }))
+                    .unwrap()
+                })
+                .collect::<Vec<AbiEntry>>();
+            let violations = validate_entries(&entries, &Environments::default());
+            assert!(violations.is_empty(), "{}: {}", name, violations[0].message);
+        }
+    }
+}
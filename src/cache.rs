@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use rayon::prelude::*;
+use serde_json::Value;
+use sha3::Digest;
+
+/// Digest of the conflicts written for every selector by the previous run, stored next to the abi
+/// file so that re-runs only rewrite functions whose analyzer output actually changed. Entries
+/// are keyed by hash algorithm as well, the selectors written differ between them.
+pub struct AnnotationCache {
+    path: PathBuf,
+    algorithm: &'static str,
+    previous: BTreeMap<String, String>,
+    current: BTreeMap<String, String>,
+}
+
+impl AnnotationCache {
+    pub fn load(abi: &Path, gm: bool) -> AnnotationCache {
+        let mut file_name = abi.file_name().unwrap().to_os_string();
+        file_name.push(".conflicts_cache");
+        let path = abi.with_file_name(file_name);
+        let previous = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("ignore broken cache {}: {}", path.display(), e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        info!("load {} cached selectors", previous.len());
+        AnnotationCache {
+            path,
+            algorithm: if gm { "sm3" } else { "keccak256" },
+            previous,
+            current: BTreeMap::new(),
+        }
+    }
+
+    /// Records the digest of what is `written` for `selector`, returns true if it equals the
+    /// cached one.
+    pub(crate) fn update(&mut self, selector: u32, written: &Value) -> bool {
+        let key = format!("{}:{:08x}", self.algorithm, selector);
+        let serialized = written.to_string();
+        let digest = hex::encode(sha3::Keccak256::digest(serialized.as_bytes()));
+        let unchanged = self.previous.get(&key) == Some(&digest);
+        self.current.insert(key, digest);
+        unchanged
+    }
+
+    pub(crate) fn save(&self) {
+        let content = serde_json::to_string(&self.current).unwrap();
+        std::fs::write(&self.path, content)
+            .unwrap_or_else(|e| panic!("could not write cache {}: {}", self.path.display(), e));
+    }
+}
+
+/// Signature to selector table per hash algorithm, persisted across runs since hashing thousands
+/// of signatures with SM3 is measurable.
+#[derive(Default)]
+pub struct SelectorCache {
+    path: Option<PathBuf>,
+    selectors: BTreeMap<String, BTreeMap<String, u32>>,
+    dirty: bool,
+    /// the time spent hashing signatures missing from the table
+    pub hashing: Duration,
+}
+
+impl SelectorCache {
+    pub fn load(path: &Path) -> SelectorCache {
+        let selectors = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("ignore broken selector cache {}: {}", path.display(), e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        SelectorCache {
+            path: Some(path.to_path_buf()),
+            selectors,
+            dirty: false,
+            hashing: Duration::default(),
+        }
+    }
+
+    pub(crate) fn method_id(&mut self, signature: &str, gm: bool) -> u32 {
+        let algorithm = if gm { "sm3" } else { "keccak256" };
+        let selectors = self.selectors.entry(algorithm.into()).or_default();
+        if let Some(selector) = selectors.get(signature) {
+            return *selector;
+        }
+        let start = Instant::now();
+        let selector = crate::get_method_id(signature, gm);
+        self.hashing += start.elapsed();
+        selectors.insert(signature.into(), selector);
+        self.dirty = true;
+        selector
+    }
+
+    /// Hashes the signatures missing from the table on all threads, for huge abis under SM3.
+    /// Fills the table the annotation then only looks up.
+    pub fn prefill(&mut self, signatures: &[String]) {
+        let start = Instant::now();
+        let algorithms: &[(&str, bool)] = if cfg!(feature = "gm") {
+            &[("keccak256", false), ("sm3", true)]
+        } else {
+            &[("keccak256", false)]
+        };
+        for (algorithm, gm) in algorithms {
+            let selectors = self.selectors.entry(algorithm.to_string()).or_default();
+            let missing = signatures
+                .par_iter()
+                .filter(|signature| !selectors.contains_key(signature.as_str()))
+                .map(|signature| (signature.clone(), crate::get_method_id(signature, *gm)))
+                .collect::<Vec<(String, u32)>>();
+            self.dirty |= !missing.is_empty();
+            selectors.extend(missing);
+        }
+        self.hashing += start.elapsed();
+    }
+
+    /// The selectors written into an abi, the keccak256 and, if built with the gm feature, the
+    /// SM3 one.
+    pub(crate) fn method_ids(&mut self, signature: &str) -> Vec<u32> {
+        let mut ids = vec![self.method_id(signature, false)];
+        if cfg!(feature = "gm") {
+            ids.push(self.method_id(signature, true));
+        }
+        ids
+    }
+
+    pub(crate) fn save(&self) {
+        if let (Some(path), true) = (&self.path, self.dirty) {
+            let content = serde_json::to_string(&self.selectors).unwrap();
+            std::fs::write(path, content)
+                .unwrap_or_else(|e| panic!("could not write cache {}: {}", path.display(), e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotation_cache() {
+        let abi = std::env::temp_dir().join("test_annotation_cache.abi");
+        let fields = serde_json::json!([{"kind": 0}]);
+        let mut cache = AnnotationCache::load(&abi, false);
+        cache.previous.clear();
+        assert!(!cache.update(0x60fe47b1, &fields));
+        cache.save();
+
+        let mut cache = AnnotationCache::load(&abi, false);
+        assert!(cache.update(0x60fe47b1, &fields));
+        assert!(!cache.update(0x60fe47b1, &serde_json::json!([{"kind": 1}])));
+        let mut gm = AnnotationCache::load(&abi, true);
+        assert!(!gm.update(0x60fe47b1, &fields));
+        std::fs::remove_file(&cache.path).unwrap();
+    }
+
+    #[test]
+    fn test_selector_cache() {
+        let path = std::env::temp_dir().join("test_selector_cache.json");
+        let _ = std::fs::remove_file(&path);
+        let mut cache = SelectorCache::load(&path);
+        assert_eq!(cache.method_id("set(uint256)", false), 0x60fe47b1);
+        assert!(cache.dirty);
+        cache.save();
+
+        let mut cache = SelectorCache::load(&path);
+        assert_eq!(cache.method_id("set(uint256)", false), 0x60fe47b1);
+        assert!(!cache.dirty);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::conflict::Environments;
+use crate::layout::{CsvColumns, CsvLayout};
+use crate::lint::LintRule;
+use crate::rules::ConflictRule;
+
+/// Looked up in the working directory when no --config is given.
+pub(crate) const DEFAULT_PATH: &str = "conflicts_info_parse.toml";
+
+/// Settings read from the config file.
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    /// more csv layouts by name, each lists the columns of the csvs that differ from v1
+    #[serde(default)]
+    pub(crate) layouts: BTreeMap<String, BTreeMap<String, CsvColumns>>,
+    /// the `[[lint]]` rules of the lint command
+    #[serde(default, rename = "lint")]
+    pub lints: Vec<LintRule>,
+    /// environment names of newer analyzer outputs and their codes, like `BLOBBASEFEE = 9`
+    #[serde(default)]
+    pub(crate) environments: BTreeMap<String, u32>,
+    /// more spellings of the environment names, like `"block.coinbase" = "COINBASE"`
+    #[serde(default)]
+    pub(crate) environment_aliases: BTreeMap<String, String>,
+    /// the `[[rule]]`s adding conflicts to every function matching them
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<ConflictRule>,
+    /// slots whose conflicts are dropped, like `ignore_slots = ["0x0"]`
+    #[serde(default)]
+    pub ignore_slots: Vec<String>,
+}
+
+impl Config {
+    pub fn load(path: Option<&Path>) -> Config {
+        let content = match path {
+            Some(path) => std::fs::read_to_string(path)
+                .unwrap_or_else(|e| panic!("could not read config {}: {}", path.display(), e)),
+            None => match std::fs::read_to_string(DEFAULT_PATH) {
+                Ok(content) => content,
+                Err(_) => return Config::default(),
+            },
+        };
+        toml::from_str(&content).unwrap_or_else(|e| panic!("invalid config: {}", e))
+    }
+
+    pub fn environments(&self) -> Environments {
+        Environments::new(&self.environments, &self.environment_aliases)
+    }
+
+    /// The builtin layouts followed by the ones of the config, which replace builtins of the same
+    /// name.
+    pub fn csv_layouts(&self) -> Vec<CsvLayout> {
+        let mut layouts = CsvLayout::builtin();
+        let v1 = layouts[0].clone();
+        for (name, csvs) in &self.layouts {
+            let mut layout = CsvLayout {
+                name: name.clone(),
+                csvs: v1.csvs.clone(),
+            };
+            layout.csvs.extend(csvs.clone());
+            layouts.retain(|builtin| builtin.name != *name);
+            layouts.push(layout);
+        }
+        layouts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_layouts() {
+        let config: Config = toml::from_str(
+            r#"
+            [layouts.v3.Conflict_FunArgConflict]
+            selector = 2
+            value = 3
+            slot = 4
+            "#,
+        )
+        .unwrap();
+        let layouts = config.csv_layouts();
+        assert_eq!(
+            layouts
+                .iter()
+                .map(|layout| layout.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["v1", "v2", "v3"]
+        );
+        let v3 = &layouts[2];
+        assert_eq!(v3.columns("Conflict_FunArgConflict").slot, Some(4));
+        assert_eq!(v3.columns("Conflict_FunArgConflict").len, None);
+        assert_eq!(
+            v3.columns("Conflict_EnvConflict"),
+            layouts[0].columns("Conflict_EnvConflict")
+        );
+        let record = csv::StringRecord::from(vec!["Token", "transfer", "0xa9059cbb", "0", "0x1"]);
+        let detected = CsvLayout::detect(&layouts, "Conflict_FunArgConflict", &record);
+        assert_eq!(detected.unwrap().name, "v3");
+    }
+
+    #[test]
+    fn test_environments() {
+        let config: Config = toml::from_str(
+            r#"
+            [environments]
+            BLOBBASEFEE = 9
+
+            [environment_aliases]
+            "block.blobbasefee" = "BLOBBASEFEE"
+            "#,
+        )
+        .unwrap();
+        let environments = config.environments();
+        assert_eq!(environments.code("BLOBBASEFEE"), Some(9));
+        assert_eq!(environments.code("CALLER"), Some(0));
+        assert_eq!(environments.code("PREVRANDAO"), None);
+        assert_eq!(environments.code("Block.BlobBaseFee"), Some(9));
+        assert_eq!(environments.code("msg.sender"), Some(0));
+        assert_eq!(environments.code("caller"), Some(0));
+        assert!(environments.is_valid(9));
+        assert!(!Environments::default().is_valid(9));
+    }
+}
@@ -0,0 +1,551 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use serde_repr::Serialize_repr;
+
+#[derive(Debug, Serialize_repr, PartialOrd, Ord, PartialEq, Eq, Clone)]
+#[repr(u8)]
+pub enum ConflictType {
+    All = 0,
+    Len,
+    Env,
+    Var,
+    Const,
+    None,
+}
+
+pub const CONFLICT_TYPES: [(&str, ConflictType); 6] = [
+    ("All", ConflictType::All),
+    ("Len", ConflictType::Len),
+    ("Env", ConflictType::Env),
+    ("Var", ConflictType::Var),
+    ("Const", ConflictType::Const),
+    ("None", ConflictType::None),
+];
+
+/// The kind by name, in any case.
+impl FromStr for ConflictType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CONFLICT_TYPES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+            .map(|(_, kind)| kind.clone())
+            .ok_or_else(|| format!("unknown conflict kind {}", s))
+    }
+}
+
+/// Accepts the numeric code that is written as well as the kind name found in older or hand
+/// written annotations.
+impl<'de> Deserialize<'de> for ConflictType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KindVisitor;
+
+        impl<'de> Visitor<'de> for KindVisitor {
+            type Value = ConflictType;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a conflict kind code or name")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<ConflictType, E> {
+                CONFLICT_TYPES
+                    .get(v as usize)
+                    .map(|(_, kind)| kind.clone())
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+            }
+
+            /// scripting engines only know signed integers
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<ConflictType, E> {
+                match v {
+                    v if v >= 0 => self.visit_u64(v as u64),
+                    _ => Err(E::invalid_value(de::Unexpected::Signed(v), &self)),
+                }
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<ConflictType, E> {
+                v.parse()
+                    .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_any(KindVisitor)
+    }
+}
+
+impl fmt::Display for ConflictType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(CONFLICT_TYPES[self.clone() as usize].0)
+    }
+}
+
+pub enum EnvironmentType {
+    Caller = 0,
+    Origin,
+    Now,
+    BlockNumber,
+    Address,
+    Unknown,
+}
+
+/// The environment names of the analyzer outputs.
+const BUILTIN_ENVIRONMENTS: [(&str, u32); 5] = [
+    ("CALLER", EnvironmentType::Caller as u32),
+    ("ORIGIN", EnvironmentType::Origin as u32),
+    ("TIMESTAMP", EnvironmentType::Now as u32),
+    ("NUMBER", EnvironmentType::BlockNumber as u32),
+    ("ADDRESS", EnvironmentType::Address as u32),
+];
+
+/// Other spellings of the environment names used by some analyzer builds and by solidity.
+const BUILTIN_ALIASES: [(&str, &str); 7] = [
+    ("MSG.SENDER", "CALLER"),
+    ("TX.ORIGIN", "ORIGIN"),
+    ("BLOCK.TIMESTAMP", "TIMESTAMP"),
+    ("NOW", "TIMESTAMP"),
+    ("BLOCK.NUMBER", "NUMBER"),
+    ("ADDRESS(THIS)", "ADDRESS"),
+    ("THIS", "ADDRESS"),
+];
+
+/// The environment names and their codes, the builtin ones and those the config declares for
+/// newer analyzer outputs, with the aliases of the names. Names are matched case-insensitively.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Environments {
+    codes: Vec<(String, u32)>,
+    aliases: Vec<(String, String)>,
+}
+
+impl Default for Environments {
+    fn default() -> Self {
+        Environments::new(&BTreeMap::new(), &BTreeMap::new())
+    }
+}
+
+impl Environments {
+    pub(crate) fn new(
+        declared: &BTreeMap<String, u32>,
+        declared_aliases: &BTreeMap<String, String>,
+    ) -> Environments {
+        let mut codes = BUILTIN_ENVIRONMENTS
+            .iter()
+            .map(|(name, code)| (name.to_string(), *code))
+            .collect::<Vec<(String, u32)>>();
+        for (name, code) in declared {
+            if *code == EnvironmentType::Unknown as u32 {
+                panic!(
+                    "environment {} takes the code {} of unknown ones",
+                    name, code
+                );
+            }
+            if let Some((other, _)) = codes
+                .iter()
+                .find(|(other, other_code)| other_code == code && other != name)
+            {
+                panic!("environment {} takes the code {} of {}", name, code, other);
+            }
+            codes.retain(|(other, _)| other != name);
+            codes.push((name.clone(), *code));
+        }
+        let mut environments = Environments {
+            codes,
+            aliases: BUILTIN_ALIASES
+                .iter()
+                .map(|(alias, name)| (alias.to_string(), name.to_string()))
+                .collect(),
+        };
+        for (alias, name) in declared_aliases {
+            if environments.code(name).is_none() {
+                panic!("alias {} of unknown environment {}", alias, name);
+            }
+            environments
+                .aliases
+                .retain(|(other, _)| !other.eq_ignore_ascii_case(alias));
+            environments.aliases.push((alias.clone(), name.clone()));
+        }
+        environments
+    }
+
+    /// The code of the environment named or aliased `name`, ignoring case.
+    pub(crate) fn code(&self, name: &str) -> Option<u32> {
+        let name = self
+            .aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+            .map_or(name, |(_, name)| name.as_str());
+        self.codes
+            .iter()
+            .find(|(other, _)| other.eq_ignore_ascii_case(name))
+            .map(|(_, code)| *code)
+    }
+
+    /// The canonical name of the environment of `code`.
+    pub(crate) fn name(&self, code: u32) -> Option<&str> {
+        self.codes
+            .iter()
+            .find(|(_, other)| *other == code)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Whether `code` is one of the environments or the unknown one.
+    pub(crate) fn is_valid(&self, code: u32) -> bool {
+        code == EnvironmentType::Unknown as u32
+            || self.codes.iter().any(|(_, other)| *other == code)
+    }
+}
+
+/// Whether a function reads or writes the slot of a conflict.
+#[derive(Debug, Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq, Clone)]
+pub struct ConflictInfo {
+    pub kind: ConflictType,
+    /// not part of conflictFields, it is the selector of the function carrying them
+    #[serde(skip_serializing, default)]
+    pub selector: u32,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_slot"
+    )]
+    pub slot: Option<u32>,
+    /// number of slots from slot on, for structs stored across several slots
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub len: Option<u32>,
+    /// byte offset inside the slot of a packed variable, for specs with sub-slot granularity
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub offset: Option<u32>,
+    /// byte size of the packed variable at offset
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub size: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub access: Option<Access>,
+    /// entries sharing a group form one logical key, written next to each other
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub group: Option<u32>,
+    /// for Var, the value is the indices of calldata per 32Bytes forming the key in order, for Env,
+    /// the value is EnvironmentType
+    #[serde(
+        skip_serializing_if = "Vec::is_empty",
+        default,
+        deserialize_with = "deserialize_value"
+    )]
+    pub value: Vec<u32>,
+    /// for Var, the declared names of the parameters at the indices in value
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub names: Vec<String>,
+    /// free text of the analyzer about the entry, like the source location it comes from
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub note: Option<String>,
+    /// not part of conflictFields either, the function name of analyzer outputs having one
+    #[serde(skip)]
+    pub function: Option<String>,
+}
+
+impl ConflictInfo {
+    /// An entry of `kind` without slot or value, like All and None.
+    pub(crate) fn new(kind: ConflictType, selector: u32) -> ConflictInfo {
+        ConflictInfo {
+            kind,
+            selector,
+            slot: None,
+            len: None,
+            offset: None,
+            size: None,
+            value: vec![],
+            names: vec![],
+            note: None,
+            function: None,
+            access: None,
+            group: None,
+        }
+    }
+}
+
+impl fmt::Display for ConflictInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        match (self.slot, self.len) {
+            (Some(slot), Some(len)) => write!(f, " slots {}..{}", slot, slot + len)?,
+            (Some(slot), None) => write!(f, " slot {}", slot)?,
+            _ => {}
+        }
+        if let (Some(offset), Some(size)) = (self.offset, self.size) {
+            write!(f, " bytes {}..{}", offset, offset + size)?;
+        }
+        if !self.value.is_empty() {
+            write!(f, " value {:?}", self.value)?;
+        }
+        match self.access {
+            Some(Access::Read) => write!(f, " read")?,
+            Some(Access::Write) => write!(f, " write")?,
+            None => {}
+        }
+        if !self.names.is_empty() {
+            write!(f, " keyed by {}", self.names.join(", "))?;
+        }
+        if let Some(group) = self.group {
+            write!(f, " group {}", group)?;
+        }
+        Ok(())
+    }
+}
+
+/// Written as conflictFieldsVersion next to conflictFields. Annotations without it are from
+/// before the version stamp, they may carry kind names and scalar values.
+pub(crate) const CONFLICT_FIELDS_VERSION: u32 = 2;
+
+/// How slots are written into conflictFields.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotFormat {
+    /// a plain number, what the executor expects
+    Decimal,
+    /// a short hex string like `0x1f`
+    Hex,
+    /// a hex string padded to 32 bytes
+    Padded,
+}
+
+impl FromStr for SlotFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "decimal" => Ok(SlotFormat::Decimal),
+            "hex" => Ok(SlotFormat::Hex),
+            "padded" => Ok(SlotFormat::Padded),
+            _ => Err(format!("unknown slot format {}", s)),
+        }
+    }
+}
+
+impl SlotFormat {
+    pub(crate) fn format(self, slot: u32) -> Value {
+        match self {
+            SlotFormat::Decimal => Value::from(slot),
+            SlotFormat::Hex => Value::from(format!("0x{:x}", slot)),
+            SlotFormat::Padded => Value::from(format!("0x{:064x}", slot)),
+        }
+    }
+}
+
+/// The conflictFields of a function with slots written in `slot_format`.
+pub(crate) fn conflict_fields(conflicts: &[ConflictInfo], slot_format: SlotFormat) -> Value {
+    let mut fields = serde_json::to_value(conflicts).unwrap();
+    if slot_format != SlotFormat::Decimal {
+        for (field, conflict) in fields.as_array_mut().unwrap().iter_mut().zip(conflicts) {
+            if let Some(slot) = conflict.slot {
+                field["slot"] = slot_format.format(slot);
+            }
+        }
+    }
+    fields
+}
+
+/// The conflicts of the function with `selector` written in its `fields`, none if it has no
+/// valid ones.
+pub(crate) fn parse_conflict_fields(fields: &Value, selector: u32) -> Vec<ConflictInfo> {
+    let mut conflicts: Vec<ConflictInfo> =
+        serde_json::from_value(fields.clone()).unwrap_or_default();
+    for conflict in &mut conflicts {
+        conflict.selector = selector;
+    }
+    conflicts
+}
+
+/// A slot given by hand, hex with `0x` or decimal.
+pub fn parse_slot(slot: &str) -> Result<u32, String> {
+    match slot.strip_prefix("0x") {
+        Some(digits) => u32::from_str_radix(digits, 16),
+        None => slot.parse(),
+    }
+    .map_err(|e| format!("invalid slot {}: {}", slot, e))
+}
+
+/// Slots written by any of the [`SlotFormat`]s.
+fn deserialize_slot<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SlotShape {
+        Number(u32),
+        Hex(String),
+    }
+
+    match Option::<SlotShape>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(SlotShape::Number(slot)) => Ok(Some(slot)),
+        Some(SlotShape::Hex(slot)) => {
+            let digits = slot.strip_prefix("0x").ok_or_else(|| {
+                de::Error::invalid_value(de::Unexpected::Str(&slot), &"a hex slot")
+            })?;
+            u32::from_str_radix(digits, 16)
+                .map(Some)
+                .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&slot), &"a u32 slot"))
+        }
+    }
+}
+
+/// Older annotations carry a single number instead of an array.
+fn deserialize_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ValueShape {
+        Scalar(u32),
+        List(Vec<u32>),
+    }
+
+    Ok(match ValueShape::deserialize(deserializer)? {
+        ValueShape::Scalar(value) => vec![value],
+        ValueShape::List(value) => value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_conflict_fields() {
+        let conflicts = vec![
+            ConflictInfo {
+                kind: ConflictType::Env,
+                selector: 0,
+                slot: Some(3),
+                len: None,
+                offset: None,
+                size: None,
+                access: None,
+                group: None,
+                value: vec![0],
+                names: vec![],
+                note: None,
+                function: None,
+            },
+            ConflictInfo {
+                kind: ConflictType::All,
+                selector: 0,
+                slot: None,
+                len: None,
+                offset: None,
+                size: None,
+                access: None,
+                group: None,
+                value: vec![],
+                names: vec![],
+                note: None,
+                function: None,
+            },
+        ];
+        let current = serde_json::to_string(&conflicts).unwrap();
+        assert_eq!(current, r#"[{"kind":2,"slot":3,"value":[0]},{"kind":0}]"#);
+        let parsed: Vec<ConflictInfo> = serde_json::from_str(&current).unwrap();
+        assert_eq!(parsed, conflicts);
+
+        let old: Vec<ConflictInfo> =
+            serde_json::from_str(r#"[{"kind":"Env","slot":3,"value":0},{"kind":"all"}]"#).unwrap();
+        assert_eq!(old, conflicts);
+        assert!(serde_json::from_str::<ConflictInfo>(r#"{"kind":6}"#).is_err());
+
+        let spanning = ConflictInfo {
+            len: Some(2),
+            offset: None,
+            size: None,
+            access: None,
+            group: None,
+            ..conflicts[0].clone()
+        };
+        let current = serde_json::to_string(&spanning).unwrap();
+        assert_eq!(current, r#"{"kind":2,"slot":3,"len":2,"value":[0]}"#);
+        assert_eq!(spanning.to_string(), "Env slots 3..5 value [0]");
+        let packed = ConflictInfo {
+            offset: Some(20),
+            size: Some(1),
+            access: None,
+            group: None,
+            ..conflicts[0].clone()
+        };
+        let current = serde_json::to_string(&packed).unwrap();
+        assert_eq!(
+            current,
+            r#"{"kind":2,"slot":3,"offset":20,"size":1,"value":[0]}"#
+        );
+        assert_eq!(packed.to_string(), "Env slot 3 bytes 20..21 value [0]");
+        let read = ConflictInfo {
+            access: Some(Access::Read),
+            group: None,
+            ..conflicts[0].clone()
+        };
+        let current = serde_json::to_string(&read).unwrap();
+        assert_eq!(
+            current,
+            r#"{"kind":2,"slot":3,"value":[0],"access":"read"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ConflictInfo>(&current).unwrap(),
+            read
+        );
+    }
+
+    #[test]
+    fn test_slot_format() {
+        let conflicts = vec![ConflictInfo {
+            kind: ConflictType::Var,
+            selector: 0,
+            slot: Some(31),
+            len: None,
+            offset: None,
+            size: None,
+            access: None,
+            group: None,
+            value: vec![1],
+            names: vec![],
+            note: None,
+            function: None,
+        }];
+        assert_eq!(
+            conflict_fields(&conflicts, SlotFormat::Decimal).to_string(),
+            r#"[{"kind":3,"slot":31,"value":[1]}]"#
+        );
+        let hex = conflict_fields(&conflicts, SlotFormat::Hex);
+        assert_eq!(hex.to_string(), r#"[{"kind":3,"slot":"0x1f","value":[1]}]"#);
+        let padded = conflict_fields(&conflicts, SlotFormat::Padded);
+        assert_eq!(padded[0]["slot"].as_str().unwrap().len(), 66);
+        assert_eq!(parse_conflict_fields(&hex, 7)[0].selector, 7);
+        assert!(parse_conflict_fields(&Value::Null, 7).is_empty());
+        for fields in vec![hex, padded] {
+            let parsed: Vec<ConflictInfo> = serde_json::from_value(fields).unwrap();
+            assert_eq!(parsed, conflicts);
+        }
+    }
+
+    #[test]
+    fn test_environment_aliases() {
+        let environments = Environments::default();
+        assert_eq!(
+            environments.code("block.timestamp"),
+            Some(EnvironmentType::Now as u32)
+        );
+        assert_eq!(
+            environments.code("Number"),
+            Some(EnvironmentType::BlockNumber as u32)
+        );
+        assert_eq!(environments.code("msg.value"), None);
+        let mut aliases = BTreeMap::new();
+        aliases.insert("sender".to_string(), "caller".to_string());
+        let environments = Environments::new(&BTreeMap::new(), &aliases);
+        assert_eq!(
+            environments.code("SENDER"),
+            Some(EnvironmentType::Caller as u32)
+        );
+    }
+}
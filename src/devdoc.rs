@@ -0,0 +1,302 @@
+use std::path::PathBuf;
+
+use serde_json::{Map, Value};
+
+use crate::abi::AbiParam;
+use crate::conflict::{Access, ConflictInfo, ConflictType, Environments};
+use crate::source::ConflictSource;
+
+/// The custom NatSpec tag declaring a conflict, several of them are separated by `;` or lines.
+const CONFLICT_TAG: &str = "custom:conflict";
+
+/// The conflicts declared in the doc comments of the functions, read from the devdoc solc writes
+/// with `--devdoc`, `--combined-json devdoc` or in the standard json output, e.g.
+/// `@custom:conflict slot=0x3 key=arg0` for a mapping keyed by the first parameter.
+pub struct DevdocSource<'a> {
+    pub path: PathBuf,
+    pub gm: bool,
+    pub environments: &'a Environments,
+}
+
+impl ConflictSource for DevdocSource<'_> {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Vec<ConflictInfo>, String> {
+        let content = std::fs::read_to_string(&self.path).map_err(|e| e.to_string())?;
+        let output: Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        let mut conflicts = Vec::new();
+        for devdoc in devdocs(&output) {
+            conflicts.extend(devdoc_conflicts(&devdoc, self.gm, self.environments));
+        }
+        Ok(conflicts)
+    }
+}
+
+/// The devdocs of a solc output, which is a devdoc itself or has contracts keyed by name, or by
+/// file then name for the standard json output.
+fn devdocs(output: &Value) -> Vec<Map<String, Value>> {
+    if let Some(devdoc) = output
+        .as_object()
+        .filter(|devdoc| devdoc.contains_key("methods"))
+    {
+        return vec![devdoc.clone()];
+    }
+    let mut found = Vec::new();
+    let contracts = output.get("contracts").and_then(Value::as_object);
+    for contract in contracts.into_iter().flat_map(Map::values) {
+        match contract.get("devdoc") {
+            // older solc writes the devdoc of --combined-json as a string
+            Some(Value::String(devdoc)) => found.extend(
+                serde_json::from_str::<Value>(devdoc)
+                    .ok()
+                    .and_then(|devdoc| devdoc.as_object().cloned()),
+            ),
+            Some(Value::Object(devdoc)) => found.push(devdoc.clone()),
+            Some(_) => {}
+            None => {
+                let nested = contract.as_object().into_iter().flat_map(Map::values);
+                found.extend(nested.filter_map(|contract| {
+                    contract.get("devdoc").and_then(Value::as_object).cloned()
+                }));
+            }
+        }
+    }
+    found
+}
+
+fn devdoc_conflicts(
+    devdoc: &Map<String, Value>,
+    gm: bool,
+    environments: &Environments,
+) -> Vec<ConflictInfo> {
+    let methods = devdoc.get("methods").and_then(Value::as_object);
+    let mut conflicts = Vec::new();
+    for (signature, doc) in methods.into_iter().flatten() {
+        let tag = match doc.get(CONFLICT_TAG).and_then(Value::as_str) {
+            Some(tag) => tag,
+            None => continue,
+        };
+        let selector = crate::get_method_id(signature, gm);
+        let words = param_words(signature);
+        for spec in tag.split(|c| c == ';' || c == '\n') {
+            if !spec.trim().is_empty() {
+                conflicts.push(
+                    parse_spec(spec, selector, &words, environments).unwrap_or_else(|e| {
+                        panic!("invalid @{} of {}: {}", CONFLICT_TAG, signature, e)
+                    }),
+                );
+            }
+        }
+    }
+    conflicts
+}
+
+/// Splits the parameter list of `f(uint256,(address,bytes))` at its top level commas.
+fn split_types(types: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in types.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&types[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !types.is_empty() {
+        parts.push(&types[start..]);
+    }
+    parts
+}
+
+fn param_of(ty: &str) -> AbiParam {
+    let (ty, components) = match ty.strip_prefix('(') {
+        Some(tuple) => {
+            let close = tuple.rfind(')').unwrap();
+            let components = split_types(&tuple[..close]).into_iter().map(param_of);
+            (
+                format!("tuple{}", &tuple[close + 1..]),
+                Some(components.collect()),
+            )
+        }
+        None => (ty.to_string(), None),
+    };
+    AbiParam {
+        name: String::new(),
+        ty,
+        components,
+        extra: Map::new(),
+    }
+}
+
+/// The calldata word each parameter of the signature starts at.
+fn param_words(signature: &str) -> Vec<u32> {
+    let types = signature
+        .split_once('(')
+        .and_then(|(_, types)| types.strip_suffix(')'))
+        .unwrap_or_default();
+    let mut start = 0;
+    split_types(types)
+        .into_iter()
+        .map(|ty| {
+            let word = start;
+            start += param_of(ty).head_words();
+            word
+        })
+        .collect()
+}
+
+fn parse_number(value: &str) -> Result<u32, String> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+    .map_err(|_| format!("invalid number {:?}", value))
+}
+
+/// A key is `argN`, the N-th parameter, or a calldata word like in the analyzer csvs.
+fn parse_key(key: &str, words: &[u32]) -> Result<u32, String> {
+    match key.strip_prefix("arg") {
+        Some(ordinal) => {
+            let ordinal = parse_number(ordinal)?;
+            words
+                .get(ordinal as usize)
+                .copied()
+                .ok_or_else(|| format!("no parameter {}", key))
+        }
+        None => parse_number(key),
+    }
+}
+
+/// Parses `all`, `none` or the `name=value` pairs slot, key, env, const, len, offset, size,
+/// access and group into a conflict; Var with a key, Env with env, Const with const.
+fn parse_spec(
+    spec: &str,
+    selector: u32,
+    words: &[u32],
+    environments: &Environments,
+) -> Result<ConflictInfo, String> {
+    let mut conflict = ConflictInfo::new(ConflictType::Var, selector);
+    let mut kind = None;
+    for token in spec.split_whitespace() {
+        let (name, value) = match token.split_once('=') {
+            Some(pair) => pair,
+            None => match token.to_ascii_lowercase().as_str() {
+                "all" => {
+                    kind = Some(ConflictType::All);
+                    continue;
+                }
+                "none" => {
+                    kind = Some(ConflictType::None);
+                    continue;
+                }
+                _ => return Err(format!("unknown {:?}", token)),
+            },
+        };
+        match name {
+            "slot" => conflict.slot = Some(parse_number(value)?),
+            "len" => conflict.len = Some(parse_number(value)?),
+            "offset" => conflict.offset = Some(parse_number(value)?),
+            "size" => conflict.size = Some(parse_number(value)?),
+            "group" => conflict.group = Some(parse_number(value)?),
+            "access" => {
+                conflict.access = Some(match value {
+                    "r" | "read" => Access::Read,
+                    "w" | "write" => Access::Write,
+                    _ => return Err(format!("invalid access {:?}", value)),
+                })
+            }
+            "key" => {
+                kind = Some(ConflictType::Var);
+                conflict.value = value
+                    .split(',')
+                    .map(|key| parse_key(key, words))
+                    .collect::<Result<_, _>>()?;
+            }
+            "env" => {
+                kind = Some(ConflictType::Env);
+                let code = environments
+                    .code(value)
+                    .ok_or_else(|| format!("unknown environment {:?}", value))?;
+                conflict.value = vec![code];
+            }
+            "const" => {
+                kind = Some(ConflictType::Const);
+                conflict.value = crate::parse_const_value(value)?;
+            }
+            _ => return Err(format!("unknown {:?}", name)),
+        }
+    }
+    conflict.kind = kind.ok_or_else(|| String::from("neither all, none, key, env nor const"))?;
+    Ok(conflict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::EnvironmentType;
+
+    #[test]
+    fn test_param_words() {
+        assert_eq!(
+            param_words("set(string,uint256[2],(uint256,address),address)"),
+            vec![0, 1, 3, 5]
+        );
+        assert!(param_words("f()").is_empty());
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let words = param_words("transferFrom((uint256,uint256),address,uint256)");
+        let environments = Environments::default();
+        let conflict =
+            parse_spec("slot=0x3 key=arg1,0 access=write", 7, &words, &environments).unwrap();
+        assert_eq!(conflict.kind, ConflictType::Var);
+        assert_eq!(conflict.selector, 7);
+        assert_eq!(conflict.slot, Some(3));
+        assert_eq!(conflict.value, vec![2, 0]);
+        assert_eq!(conflict.access, Some(Access::Write));
+        let conflict = parse_spec("slot=1 env=msg.sender", 7, &words, &environments).unwrap();
+        assert_eq!(conflict.kind, ConflictType::Env);
+        assert_eq!(conflict.value, vec![EnvironmentType::Caller as u32]);
+        assert_eq!(
+            parse_spec("all", 7, &words, &environments).unwrap().kind,
+            ConflictType::All
+        );
+        assert!(parse_spec("slot=3 key=arg3", 7, &words, &environments).is_err());
+        assert!(parse_spec("slot=3", 7, &words, &environments).is_err());
+        let error = parse_spec("const=0xzz", 7, &words, &environments).unwrap_err();
+        assert!(error.contains("0xzz"));
+    }
+
+    #[test]
+    fn test_devdocs() {
+        let output = serde_json::json!({
+            "contracts": {
+                "Token.sol": {
+                    "Token": {
+                        "devdoc": {
+                            "methods": {
+                                "transfer(address,uint256)": {
+                                    "custom:conflict": "slot=0x3 key=arg0; env=caller slot=0x3"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        let devdocs = devdocs(&output);
+        assert_eq!(devdocs.len(), 1);
+        let conflicts = devdoc_conflicts(&devdocs[0], false, &Environments::default());
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].selector, 0xa9059cbb);
+        assert_eq!(conflicts[0].value, vec![0]);
+        assert_eq!(conflicts[1].kind, ConflictType::Env);
+    }
+}
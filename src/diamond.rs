@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use colored::Colorize;
+
+use crate::abi::AbiEntry;
+use crate::cache::SelectorCache;
+use crate::conflict::{conflict_fields, ConflictInfo, SlotFormat, CONFLICT_FIELDS_VERSION};
+use crate::merge::read_entries;
+use crate::source::CsvOptions;
+use crate::{get_method_id, get_method_signature};
+
+/// A facet of a diamond: its abi and the analyzer outputs of it, `path/to/Facet.abi:outputs`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FacetSpec {
+    abi: PathBuf,
+    path: PathBuf,
+}
+
+impl FromStr for FacetSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((abi, path)) if !abi.is_empty() && !path.is_empty() => Ok(FacetSpec {
+                abi: abi.into(),
+                path: path.into(),
+            }),
+            _ => Err(format!("facet {} is not abi:path", s)),
+        }
+    }
+}
+
+/// The functions of a facet and the conflicts the analyzer found in it.
+struct Facet {
+    name: String,
+    entries: Vec<AbiEntry>,
+    conflicts: Vec<ConflictInfo>,
+}
+
+/// Annotates the functions of `diamond` with the conflicts of the facet their selector is cut
+/// to. Returns the number annotated, or the selectors more than one facet has.
+fn aggregate(
+    diamond: &mut [AbiEntry],
+    facets: &[Facet],
+    gm: bool,
+    slot_format: SlotFormat,
+) -> Result<usize, Vec<String>> {
+    let mut owners: BTreeMap<u32, (&str, String)> = BTreeMap::new();
+    let mut clashes = Vec::new();
+    for facet in facets {
+        for entry in facet.entries.iter().filter(|entry| entry.is_function()) {
+            let signature = get_method_signature(entry);
+            let selector = get_method_id(&signature, gm);
+            if let Some((owner, owner_signature)) = owners.get(&selector) {
+                clashes.push(format!(
+                    "0x{:08x} is {} of {} and {} of {}",
+                    selector, owner_signature, owner, signature, facet.name
+                ));
+            } else {
+                owners.insert(selector, (&facet.name, signature));
+            }
+        }
+    }
+    if !clashes.is_empty() {
+        return Err(clashes);
+    }
+    let mut conflicts: BTreeMap<u32, Vec<ConflictInfo>> = BTreeMap::new();
+    for facet in facets {
+        for conflict in &facet.conflicts {
+            // a facet only speaks for its own selectors
+            if owners
+                .get(&conflict.selector)
+                .map_or(false, |(owner, _)| *owner == facet.name)
+            {
+                conflicts
+                    .entry(conflict.selector)
+                    .or_default()
+                    .push(conflict.clone());
+            }
+        }
+    }
+    let mut selectors = SelectorCache::default();
+    let mut annotated = 0;
+    for entry in diamond.iter_mut().filter(|entry| entry.is_function()) {
+        let signature = get_method_signature(entry);
+        let selector = get_method_id(&signature, gm);
+        if !owners.contains_key(&selector) {
+            eprintln!(
+                "{}: {} is in no facet, left unannotated",
+                "warning".yellow(),
+                signature
+            );
+            continue;
+        }
+        let conflicts = conflicts.remove(&selector).unwrap_or_default();
+        entry.extra.remove("conflictFields");
+        entry.extra.remove("conflictFieldsVersion");
+        // as in the abi of the facet, a function without conflicts has no conflictFields
+        if !conflicts.is_empty() {
+            entry.extra.insert(
+                "conflictFields".into(),
+                conflict_fields(&conflicts, slot_format),
+            );
+            entry.extra.insert(
+                "conflictFieldsVersion".into(),
+                CONFLICT_FIELDS_VERSION.into(),
+            );
+        }
+        let selector = selectors.method_ids(&signature);
+        entry
+            .extra
+            .insert("selector".into(), serde_json::to_value(selector).unwrap());
+        annotated += 1;
+    }
+    Ok(annotated)
+}
+
+/// Writes `diamond` annotated with the conflicts the analyzer found in each of its facets to
+/// `output`, returns false without writing if facets clash on a selector.
+pub fn annotate_diamond(
+    diamond: &Path,
+    facets: &[FacetSpec],
+    output: &Path,
+    options: &CsvOptions,
+    slot_format: SlotFormat,
+) -> bool {
+    for spec in facets.iter().filter(|spec| !spec.path.is_file()) {
+        for warning in crate::source::missing_csv_warnings(&spec.path, options.missing) {
+            eprintln!(
+                "{}: {}: {}",
+                "warning".yellow(),
+                warning.signature,
+                warning.message
+            );
+        }
+    }
+    let facets = facets
+        .iter()
+        .map(|spec| Facet {
+            name: format!("{}", spec.abi.display()),
+            entries: read_entries(&spec.abi),
+            conflicts: if spec.path.is_file() {
+                crate::parse_merged_conflict_info(&spec.path, options)
+            } else {
+                crate::parse_conflict_info(&spec.path, options)
+            }
+            .unwrap_or_else(|e| panic!("{}", e)),
+        })
+        .collect::<Vec<Facet>>();
+    let mut entries = read_entries(diamond);
+    let annotated = match aggregate(&mut entries, &facets, options.gm, slot_format) {
+        Ok(annotated) => annotated,
+        Err(clashes) => {
+            for clash in &clashes {
+                eprintln!("{}: selector clash, {}", "error".red(), clash);
+            }
+            return false;
+        }
+    };
+    std::fs::write(output, serde_json::to_string(&entries).unwrap())
+        .unwrap_or_else(|e| panic!("could not write {}: {}", output.display(), e));
+    print!(
+        "annotated {} functions of {} facets into {}",
+        annotated,
+        facets.len(),
+        format!("{}", output.display()).green()
+    );
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::ConflictType;
+
+    fn facet(name: &str, functions: &[&str], conflicts: Vec<ConflictInfo>) -> Facet {
+        let entries = functions
+            .iter()
+            .map(|function| AbiEntry {
+                name: Some(function.to_string()),
+                ty: Some(String::from("function")),
+                inputs: Some(Vec::new()),
+                state_mutability: None,
+                extra: Default::default(),
+                raw: None,
+            })
+            .collect();
+        Facet {
+            name: name.into(),
+            entries,
+            conflicts,
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let mint = get_method_id("mint()", false);
+        let facets = vec![
+            facet(
+                "Mint",
+                &["mint"],
+                vec![ConflictInfo::new(ConflictType::All, mint)],
+            ),
+            facet(
+                "Burn",
+                &["burn"],
+                vec![ConflictInfo::new(ConflictType::All, mint)],
+            ),
+        ];
+        let mut diamond = facet("Diamond", &["mint", "burn", "facets"], Vec::new()).entries;
+        assert_eq!(
+            aggregate(&mut diamond, &facets, false, SlotFormat::Decimal),
+            Ok(2)
+        );
+        assert!(diamond[0].extra.contains_key("conflictFields"));
+        // the conflict Burn has for mint is not of its own functions
+        assert!(!diamond[1].extra.contains_key("conflictFields"));
+        assert!(!diamond[1].extra.contains_key("conflictFieldsVersion"));
+        assert!(diamond[1].extra.contains_key("selector"));
+        assert!(!diamond[2].extra.contains_key("conflictFields"));
+        assert!(!diamond[2].extra.contains_key("selector"));
+
+        let clashing = vec![
+            facet("Mint", &["mint"], Vec::new()),
+            facet("Again", &["mint"], Vec::new()),
+        ];
+        let clashes = aggregate(&mut diamond, &clashing, false, SlotFormat::Decimal).unwrap_err();
+        assert_eq!(clashes.len(), 1);
+        assert!(clashes[0].contains("mint() of Mint and mint() of Again"));
+        assert_eq!(
+            "Facet.abi:out".parse::<FacetSpec>().unwrap().path,
+            PathBuf::from("out")
+        );
+    }
+}
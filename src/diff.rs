@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use colored::Colorize;
+use serde_json::Value;
+
+use crate::annotate::FunctionSummary;
+
+/// When the terminal output is colored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    /// if the output is a terminal and neither NO_COLOR nor CLICOLOR=0 is set
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("unknown color choice {}", s)),
+        }
+    }
+}
+
+impl ColorChoice {
+    pub fn apply(self) {
+        match self {
+            ColorChoice::Auto => colored::control::unset_override(),
+            ColorChoice::Always => colored::control::set_override(true),
+            ColorChoice::Never => colored::control::set_override(false),
+        }
+    }
+}
+
+fn entries(fields: &Value) -> &[Value] {
+    fields.as_array().map_or(&[], Vec::as_slice)
+}
+
+/// The conflictFields entries of `function` the annotation removes and adds.
+pub(crate) fn function_diff(function: &FunctionSummary) -> (Vec<&Value>, Vec<&Value>) {
+    let previous = entries(&function.previous_fields);
+    let current = entries(&function.fields);
+    let removed = previous
+        .iter()
+        .filter(|entry| !current.contains(entry))
+        .collect();
+    let added = current
+        .iter()
+        .filter(|entry| !previous.contains(entry))
+        .collect();
+    (removed, added)
+}
+
+/// Prints the changed conflictFields entries grouped by function, removed ones red and added
+/// ones green.
+pub fn print_diff(functions: &[FunctionSummary]) {
+    for function in functions {
+        let (removed, added) = function_diff(function);
+        if removed.is_empty() && added.is_empty() {
+            continue;
+        }
+        println!(
+            "{} 0x{:08x}",
+            function.display_signature.as_str().bold(),
+            function.selector
+        );
+        for entry in removed {
+            println!("{}", format!("- {}", entry).as_str().red());
+        }
+        for entry in added {
+            println!("{}", format!("+ {}", entry).as_str().green());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_function_diff() {
+        let mut function = FunctionSummary::for_test("set(uint256)", Vec::new());
+        function.previous_fields = json!([{"kind": 0}, {"kind": 2, "slot": 1, "value": [0]}]);
+        function.fields = json!([{"kind": 2, "slot": 1, "value": [0]}, {"kind": 3, "slot": 2}]);
+        let (removed, added) = function_diff(&function);
+        assert_eq!(removed, vec![&json!({"kind": 0})]);
+        assert_eq!(added, vec![&json!({"kind": 3, "slot": 2})]);
+    }
+}
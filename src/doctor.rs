@@ -0,0 +1,226 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use colored::Colorize;
+use serde_json::Value;
+
+use crate::abi::AbiEntry;
+use crate::get_method_signature;
+use crate::hash::algorithms;
+use crate::layout::{Column, CsvLayout, Row, SelectorRadix};
+
+/// At most this many selectors of the csvs are hashed against, enough to tell the algorithm.
+const SAMPLE_SIZE: usize = 100;
+
+/// Prints the checks as they pass or fail, with what to do about the failed ones.
+#[derive(Default)]
+struct Doctor {
+    problems: usize,
+}
+
+impl Doctor {
+    fn ok(&self, message: &str) {
+        println!("{} {}", "ok".green(), message);
+    }
+
+    fn problem(&mut self, message: &str, suggestion: &str) {
+        self.problems += 1;
+        println!("{} {}\n    {}", "problem".red(), message, suggestion);
+    }
+
+    /// Checks every analyzer output is there and has a known layout, returns a sample of their
+    /// selectors.
+    fn check_csvs(&mut self, path: &Path, layouts: &[CsvLayout]) -> BTreeSet<u32> {
+        let mut sample = BTreeSet::new();
+        if !path.is_dir() {
+            self.problem(
+                &format!("{} is no directory", path.display()),
+                "pass the directory the analyzer wrote its csvs into",
+            );
+            return sample;
+        }
+        for (file_name, _) in crate::CONFLICT_CSVS.iter() {
+            let csv_path = path.join(file_name);
+            let bytes = match std::fs::read(&csv_path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    self.problem(
+                        &format!("could not read {}: {}", file_name, e),
+                        "was the analyzer run on this contract? it writes every csv, even empty",
+                    );
+                    continue;
+                }
+            };
+            let content = crate::encoding::decode_csv(&bytes, &csv_path);
+            let mut rdr = crate::csv_reader(&content);
+            let records = rdr
+                .records()
+                .filter_map(Result::ok)
+                .filter(|record| record.iter().any(|field| !field.trim().is_empty()))
+                .collect::<Vec<csv::StringRecord>>();
+            let csv = file_name.trim_end_matches(".csv");
+            let layout = match records.first() {
+                Some(record) => CsvLayout::detect(layouts, csv, record),
+                None => {
+                    self.ok(&format!("{} is empty", file_name));
+                    continue;
+                }
+            };
+            let layout = match layout {
+                Some(layout) => layout,
+                None => {
+                    self.problem(
+                        &format!("the columns of {} match no layout", file_name),
+                        "pick a layout with --csv-layout or declare one in the config",
+                    );
+                    continue;
+                }
+            };
+            self.ok(&format!(
+                "{} has {} rows in the {} layout",
+                file_name,
+                records.len(),
+                layout.name
+            ));
+            for record in &records {
+                if sample.len() == SAMPLE_SIZE {
+                    break;
+                }
+                let row = Row {
+                    record,
+                    columns: layout.columns(csv),
+                };
+                let selector = row.get(Column::Selector).unwrap_or_default();
+                // signatures instead of selectors hash under any algorithm
+                if let Ok(selector) = SelectorRadix::Auto.try_parse(selector) {
+                    sample.insert(selector);
+                }
+            }
+        }
+        sample
+    }
+
+    /// Checks the abi parses, returns the signatures of its functions.
+    fn check_abi(&mut self, abi: &Path) -> Vec<String> {
+        let entries = std::fs::read_to_string(abi)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<Vec<Value>>(&content).map_err(|e| e.to_string())
+            });
+        let entries = match entries {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.problem(
+                    &format!("could not parse {}: {}", abi.display(), e),
+                    "pass the json abi solc writes with --abi",
+                );
+                return Vec::new();
+            }
+        };
+        let mut signatures = Vec::new();
+        let mut malformed = 0;
+        for value in entries.into_iter().filter(Value::is_object) {
+            match AbiEntry::parse(value) {
+                Ok(entry) if entry.is_function() => signatures.push(get_method_signature(&entry)),
+                Ok(_) => {}
+                Err(_) => malformed += 1,
+            }
+        }
+        if malformed > 0 {
+            self.problem(
+                &format!("{} entries of {} don't parse", malformed, abi.display()),
+                "they are left as is, was the abi written by an unusual compiler?",
+            );
+        }
+        self.ok(&format!(
+            "{} has {} functions",
+            abi.display(),
+            signatures.len()
+        ));
+        signatures
+    }
+
+    /// Checks the selectors of the csvs match the functions under the algorithm of the run.
+    fn check_hash(&mut self, signatures: &[String], sample: &BTreeSet<u32>, gm: bool) {
+        if sample.is_empty() || signatures.is_empty() {
+            return;
+        }
+        let matches = algorithms()
+            .into_iter()
+            .map(|(name, hasher)| {
+                let selectors = signatures
+                    .iter()
+                    .map(|signature| hasher.selector(signature))
+                    .collect::<BTreeSet<u32>>();
+                (name, sample.intersection(&selectors).count())
+            })
+            .collect::<Vec<(&str, usize)>>();
+        let counts = matches
+            .iter()
+            .map(|(name, count)| {
+                format!("{}/{} selectors match under {}", count, sample.len(), name)
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        let chosen = if gm { "sm3" } else { "keccak256" };
+        let (best, best_count) = *matches.iter().max_by_key(|(_, count)| *count).unwrap();
+        let chosen_count = matches
+            .iter()
+            .find(|(name, _)| *name == chosen)
+            .map_or(0, |(_, count)| *count);
+        if best_count == 0 {
+            let suggestion = if cfg!(feature = "gm") {
+                "are the csvs of another contract or version of the abi?"
+            } else {
+                "did the analyzer hash with SM3? build with the gm feature to check"
+            };
+            self.problem(&counts, suggestion);
+        } else if chosen_count < best_count {
+            let suggestion = if best == "sm3" {
+                "pass --gm"
+            } else {
+                "drop --gm"
+            };
+            self.problem(&counts, suggestion);
+        } else if chosen_count < sample.len() {
+            self.problem(
+                &counts,
+                "the abi and the analyzer output may be of different versions of the contract",
+            );
+        } else {
+            self.ok(&counts);
+        }
+    }
+}
+
+/// Diagnoses the usual setup problems of annotating `abi` from the csvs in `path`, returns
+/// whether there are none.
+pub fn doctor(abi: &Path, path: &Path, layouts: &[CsvLayout], gm: bool) -> bool {
+    let mut doctor = Doctor::default();
+    let sample = doctor.check_csvs(path, layouts);
+    let signatures = doctor.check_abi(abi);
+    doctor.check_hash(&signatures, &sample, gm);
+    if doctor.problems > 0 {
+        println!("{} problems found", doctor.problems);
+    }
+    doctor.problems == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_hash() {
+        let signatures = vec![String::from("transfer(address,uint256)")];
+        let mut doctor = Doctor::default();
+        doctor.check_hash(&signatures, &vec![0xa9059cbb].into_iter().collect(), false);
+        assert_eq!(doctor.problems, 0);
+        doctor.check_hash(
+            &signatures,
+            &vec![0xa9059cbb, 7].into_iter().collect(),
+            false,
+        );
+        assert_eq!(doctor.problems, 1);
+    }
+}
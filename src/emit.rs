@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::abi::{AbiEntry, AbiParam};
+use crate::conflict::{CONFLICT_FIELDS_VERSION, CONFLICT_TYPES};
+
+/// The languages declarations are generated for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lang {
+    TypeScript,
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ts" => Ok(Lang::TypeScript),
+            _ => Err(format!("unknown language {}", s)),
+        }
+    }
+}
+
+/// `transferFrom` gives `TransferFrom`.
+fn pascal_case(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// A literal type of the parameter, so SDKs notice parameters changing.
+fn ts_param(param: &AbiParam) -> String {
+    let mut fields = vec![
+        format!("name: {:?}", param.name),
+        format!("type: {:?}", param.ty),
+    ];
+    if let Some(internal_type) = param.extra.get("internalType").and_then(|ty| ty.as_str()) {
+        fields.push(format!("internalType: {:?}", internal_type));
+    }
+    if let Some(components) = &param.components {
+        fields.push(format!("components: {}", ts_params(components)));
+    }
+    format!("{{ {} }}", fields.join("; "))
+}
+
+fn ts_params(params: &[AbiParam]) -> String {
+    format!(
+        "[{}]",
+        params
+            .iter()
+            .map(ts_param)
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// TypeScript declarations of an annotated abi, one interface per function with the
+/// conflictFields shape shared.
+pub(crate) fn typescript(entries: &[AbiEntry], abi_name: &str) -> String {
+    let mut ts = String::new();
+    writeln!(
+        ts,
+        "// generated by conflicts_info_parse from {}\n",
+        abi_name
+    )
+    .unwrap();
+    let kinds = CONFLICT_TYPES
+        .iter()
+        .enumerate()
+        .map(|(code, (name, _))| format!("  {} = {},", name, code))
+        .collect::<Vec<String>>();
+    writeln!(
+        ts,
+        "export enum ConflictKind {{\n{}\n}}\n",
+        kinds.join("\n")
+    )
+    .unwrap();
+    ts.push_str(
+        "export interface ConflictField {
+  kind: ConflictKind;
+  slot?: number | string;
+  len?: number;
+  offset?: number;
+  size?: number;
+  value?: number[];
+  names?: string[];
+  access?: \"read\" | \"write\";
+  group?: number;
+  note?: string;
+}
+
+export interface AbiParam {
+  name: string;
+  type: string;
+  internalType?: string;
+  components?: AbiParam[];
+}
+
+",
+    );
+    writeln!(
+        ts,
+        "export interface AnnotatedFunction {{
+  type: \"function\";
+  name: string;
+  inputs: AbiParam[];
+  outputs?: AbiParam[];
+  stateMutability?: string;
+  selector?: number[];
+  selectors?: {{ keccak256: number; sm3?: number }};
+  conflictFields?: ConflictField[];
+  conflictFieldsVersion?: {};
+  parallel?: boolean;
+}}\n",
+        CONFLICT_FIELDS_VERSION
+    )
+    .unwrap();
+
+    let mut overloads: BTreeMap<String, usize> = BTreeMap::new();
+    let mut names = Vec::new();
+    for entry in entries.iter().filter(|entry| entry.is_function()) {
+        let name = entry.name.as_deref().unwrap_or_default();
+        let overload = overloads.entry(name.to_string()).or_default();
+        *overload += 1;
+        let interface = match *overload {
+            1 => format!("{}Function", pascal_case(name)),
+            n => format!("{}Function{}", pascal_case(name), n),
+        };
+        writeln!(ts, "/** {} */", entry.display_signature()).unwrap();
+        writeln!(
+            ts,
+            "export interface {} extends AnnotatedFunction {{",
+            interface
+        )
+        .unwrap();
+        writeln!(ts, "  name: {:?};", name).unwrap();
+        writeln!(ts, "  inputs: {};", ts_params(entry.inputs())).unwrap();
+        writeln!(ts, "}}\n").unwrap();
+        names.push(interface);
+    }
+    names.push(String::from("{ type: string; [key: string]: unknown }"));
+    writeln!(
+        ts,
+        "export type Abi = Array<\n  | {}\n>;",
+        names.join("\n  | ")
+    )
+    .unwrap();
+    ts
+}
+
+/// Writes the declarations of `abi` in `lang` to `output` or prints them.
+pub fn emit_types(abi: &Path, lang: Lang, output: Option<&Path>) {
+    let abi_content = std::fs::read_to_string(abi)
+        .unwrap_or_else(|_| panic!("could not read file {}", abi.display()));
+    let entries: Vec<AbiEntry> = serde_json::from_str(&abi_content).unwrap();
+    let abi_name = abi.file_name().unwrap().to_string_lossy();
+    let types = match lang {
+        Lang::TypeScript => typescript(&entries, &abi_name),
+    };
+    match output {
+        Some(output) => std::fs::write(output, types)
+            .unwrap_or_else(|e| panic!("could not write {}: {}", output.display(), e)),
+        None => print!("{}", types),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typescript() {
+        let entries: Vec<AbiEntry> = serde_json::from_str(
+            r#"[
+                {"name": "set", "type": "function",
+                 "inputs": [{"internalType": "uint256", "name": "v", "type": "uint256"}]},
+                {"name": "set", "type": "function", "inputs": []},
+                {"type": "event", "name": "Set", "inputs": []}
+            ]"#,
+        )
+        .unwrap();
+        let ts = typescript(&entries, "Store.abi");
+        assert!(ts.contains("  Const = 4,\n"));
+        assert!(ts.contains(
+            "export interface SetFunction extends AnnotatedFunction {\n  name: \"set\";\n  \
+             inputs: [{ name: \"v\"; type: \"uint256\"; internalType: \"uint256\" }];\n}"
+        ));
+        assert!(ts.contains("export interface SetFunction2 extends AnnotatedFunction {"));
+        assert!(!ts.contains("SetFunction3"));
+    }
+}
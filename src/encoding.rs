@@ -0,0 +1,50 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use encoding_rs::{Encoding, GBK, UTF_16BE, UTF_16LE, UTF_8};
+use log::warn;
+
+/// Analyzer outputs produced on Windows may be UTF-16 or GBK and start with a BOM, hand them to the
+/// csv reader as UTF-8. Plain UTF-8 is passed through without copying.
+pub(crate) fn decode_csv<'a>(bytes: &'a [u8], path: &Path) -> Cow<'a, [u8]> {
+    let (encoding, content) = match Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) if encoding == UTF_8 => return Cow::Borrowed(&bytes[bom_len..]),
+        Some((encoding, bom_len)) => (encoding, &bytes[bom_len..]),
+        // selectors and slots are ascii, so UTF-16 without BOM has a nul in every other byte
+        None if bytes.len() >= 2 && bytes[0] != 0 && bytes[1] == 0 => (UTF_16LE, bytes),
+        None if bytes.len() >= 2 && bytes[0] == 0 && bytes[1] != 0 => (UTF_16BE, bytes),
+        None if std::str::from_utf8(bytes).is_err() => (GBK, bytes),
+        None => return Cow::Borrowed(bytes),
+    };
+    let (text, had_errors) = encoding.decode_without_bom_handling(content);
+    if had_errors {
+        warn!("{} isn't valid {}", path.display(), encoding.name());
+    }
+    Cow::Owned(text.into_owned().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_csv() {
+        let path = Path::new("Conflict_NoConflict.csv");
+        let utf8 = b"0x12345678\n";
+        assert!(matches!(decode_csv(utf8, path), Cow::Borrowed(_)));
+        assert_eq!(&*decode_csv(b"\xef\xbb\xbf0x12345678\n", path), utf8);
+        let utf16 = "0x12345678\n"
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes().to_vec())
+            .collect::<Vec<u8>>();
+        assert_eq!(&*decode_csv(&utf16, path), utf8);
+        let mut utf16_bom = vec![0xff, 0xfe];
+        utf16_bom.extend(&utf16);
+        assert_eq!(&*decode_csv(&utf16_bom, path), utf8);
+        // "名" in GBK
+        assert_eq!(
+            &*decode_csv(b"0x1\t\xc3\xfb\n", path),
+            "0x1\t名\n".as_bytes()
+        );
+    }
+}
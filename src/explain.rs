@@ -0,0 +1,167 @@
+use std::path::Path;
+
+use crate::abi::AbiEntry;
+use crate::conflict::{ConflictInfo, ConflictType, EnvironmentType};
+use crate::get_method_signature;
+
+/// The solidity expression of an environment code.
+fn environment(code: u32) -> &'static str {
+    match code {
+        c if c == EnvironmentType::Caller as u32 => "msg.sender",
+        c if c == EnvironmentType::Origin as u32 => "tx.origin",
+        c if c == EnvironmentType::Now as u32 => "block.timestamp",
+        c if c == EnvironmentType::BlockNumber as u32 => "block.number",
+        c if c == EnvironmentType::Address as u32 => "address(this)",
+        _ => "an unknown environment value",
+    }
+}
+
+/// `#0 (`to`)` for the argument, with the name from the annotation or else the abi.
+fn argument(entry: &AbiEntry, conflict: &ConflictInfo, position: usize) -> String {
+    let index = conflict.value[position];
+    let name = match conflict.names.get(position) {
+        Some(name) => Some(name.as_str()),
+        None => entry
+            .param_at_word(index)
+            .map(|(_, param)| param.name.as_str()),
+    };
+    match name {
+        Some(name) if !name.is_empty() => format!("#{} (`{}`)", index, name),
+        _ => format!("#{}", index),
+    }
+}
+
+fn join(parts: &[String]) -> String {
+    match parts.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+fn explain_conflict(entry: &AbiEntry, conflict: &ConflictInfo) -> String {
+    let slot = match (conflict.slot, conflict.len) {
+        (Some(slot), Some(len)) if len > 1 => {
+            format!("storage slots 0x{:x}..0x{:x}", slot, slot + len)
+        }
+        (Some(slot), _) => format!("storage slot 0x{:x}", slot),
+        (None, _) => String::from("storage"),
+    };
+    match conflict.kind {
+        ConflictType::All => String::from("with every other transaction"),
+        ConflictType::None => String::from("with no other transaction"),
+        ConflictType::Len => format!("on the length of the array at {}", slot),
+        ConflictType::Env => join(
+            &conflict
+                .value
+                .iter()
+                .map(|code| format!("on {}", environment(*code)))
+                .collect::<Vec<String>>(),
+        ),
+        ConflictType::Var => {
+            let arguments = (0..conflict.value.len())
+                .map(|position| argument(entry, conflict, position))
+                .collect::<Vec<String>>();
+            let plural = if arguments.len() > 1 { "s" } else { "" };
+            format!(
+                "on {} keyed by argument{} {}",
+                slot,
+                plural,
+                join(&arguments)
+            )
+        }
+        ConflictType::Const => format!("on {}", slot),
+    }
+}
+
+/// Plain sentences describing the conflicts of an annotated function, e.g. "transfer(address,
+/// uint256) conflicts on storage slot 0x3 keyed by argument #0 (`to`) and on msg.sender".
+pub(crate) fn explain_entry(entry: &AbiEntry) -> String {
+    let signature = get_method_signature(entry);
+    let conflicts: Vec<ConflictInfo> = match entry.extra.get("conflictFields") {
+        Some(fields) => serde_json::from_value(fields.clone())
+            .unwrap_or_else(|e| panic!("invalid conflictFields of {}: {}", signature, e)),
+        None => return format!("{} is not annotated", signature),
+    };
+    if conflicts.is_empty() {
+        return format!("{} conflicts with nothing", signature);
+    }
+    let parts = conflicts
+        .iter()
+        .map(|conflict| explain_conflict(entry, conflict))
+        .collect::<Vec<String>>();
+    format!("{} conflicts {}", signature, join(&parts))
+}
+
+/// Whether `function` names `entry`, by name, signature or one of its 0x selectors.
+pub(crate) fn matches(entry: &AbiEntry, function: &str) -> bool {
+    if entry.name.as_deref() == Some(function) || get_method_signature(entry) == function {
+        return true;
+    }
+    let selector = match function
+        .strip_prefix("0x")
+        .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+    {
+        Some(selector) => selector,
+        None => return false,
+    };
+    entry
+        .extra
+        .get("selector")
+        .and_then(|selectors| selectors.as_array())
+        .map_or(false, |selectors| {
+            selectors
+                .iter()
+                .any(|s| s.as_u64() == Some(selector.into()))
+        })
+}
+
+/// Prints the conflicts of the functions of `abi` matching `function`.
+pub fn explain(abi: &Path, function: &str) {
+    let abi_content = std::fs::read_to_string(abi)
+        .unwrap_or_else(|_| panic!("could not read file {}", abi.display()));
+    let entries: Vec<AbiEntry> = serde_json::from_str(&abi_content).unwrap();
+    let mut found = false;
+    for entry in entries.iter().filter(|entry| entry.is_function()) {
+        if matches(entry, function) {
+            println!("{}", explain_entry(entry));
+            found = true;
+        }
+    }
+    if !found {
+        panic!("no function {} in {}", function, abi.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_entry() {
+        let entry: AbiEntry = serde_json::from_str(
+            r#"{
+                "inputs": [
+                    {"name": "to", "type": "address"},
+                    {"name": "amount", "type": "uint256"}
+                ],
+                "name": "transfer",
+                "type": "function",
+                "selector": [2835717307, 1],
+                "conflictFields": [
+                    {"kind": 3, "slot": 3, "value": [0]},
+                    {"kind": 2, "value": [0]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            explain_entry(&entry),
+            "transfer(address,uint256) conflicts on storage slot 0x3 keyed by argument #0 (`to`) \
+             and on msg.sender"
+        );
+        assert!(matches(&entry, "transfer"));
+        assert!(matches(&entry, "0xa9059cbb"));
+        assert!(!matches(&entry, "0xa9059cbc"));
+    }
+}
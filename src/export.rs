@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use colored::Colorize;
+use serde_json::Value;
+
+/// A compact binary encoding of the annotated abi, for light clients and embedded workers
+/// without a json parser to spare.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryFormat {
+    Cbor,
+    MessagePack,
+}
+
+impl FromStr for BinaryFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cbor" => Ok(BinaryFormat::Cbor),
+            "msgpack" => Ok(BinaryFormat::MessagePack),
+            _ => Err(format!("unknown binary format {}", s)),
+        }
+    }
+}
+
+impl BinaryFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            BinaryFormat::Cbor => "cbor",
+            BinaryFormat::MessagePack => "msgpack",
+        }
+    }
+
+    #[cfg_attr(
+        not(all(feature = "serde_cbor", feature = "rmp-serde")),
+        allow(unused_variables)
+    )]
+    fn encode(self, abi: &Value) -> Result<Vec<u8>, String> {
+        match self {
+            #[cfg(feature = "serde_cbor")]
+            BinaryFormat::Cbor => serde_cbor::to_vec(abi).map_err(|e| e.to_string()),
+            #[cfg(not(feature = "serde_cbor"))]
+            BinaryFormat::Cbor => Err(String::from("build with the serde_cbor feature")),
+            // with the keys of the objects, the abi is read by name
+            #[cfg(feature = "rmp-serde")]
+            BinaryFormat::MessagePack => rmp_serde::to_vec_named(abi).map_err(|e| e.to_string()),
+            #[cfg(not(feature = "rmp-serde"))]
+            BinaryFormat::MessagePack => Err(String::from("build with the rmp-serde feature")),
+        }
+    }
+}
+
+/// `<abi>.cbor` or `<abi>.msgpack` next to the abi.
+fn export_path(abi: &Path, format: BinaryFormat) -> PathBuf {
+    let mut file_name = abi.file_name().unwrap().to_os_string();
+    file_name.push(".");
+    file_name.push(format.extension());
+    abi.with_file_name(file_name)
+}
+
+/// Writes the annotated abi, conflictFields and all, in the binary `format` to `output` or
+/// next to it.
+pub fn export(abi: &Path, format: BinaryFormat, output: Option<&Path>) {
+    let abi_content = std::fs::read_to_string(abi)
+        .unwrap_or_else(|_| panic!("could not read file {}", abi.display()));
+    let value: Value = serde_json::from_str(&abi_content)
+        .unwrap_or_else(|e| panic!("could not parse {}: {}", abi.display(), e));
+    let bytes = format
+        .encode(&value)
+        .unwrap_or_else(|e| panic!("could not encode {}: {}", abi.display(), e));
+    let output = output.map_or_else(|| export_path(abi, format), Path::to_path_buf);
+    std::fs::write(&output, &bytes)
+        .unwrap_or_else(|e| panic!("could not write {}: {}", output.display(), e));
+    print!(
+        "exported {} bytes to {}",
+        bytes.len(),
+        format!("{}", output.display()).green()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_path() {
+        assert_eq!(
+            export_path(Path::new("build/Token.abi"), "msgpack".parse().unwrap()),
+            PathBuf::from("build/Token.abi.msgpack")
+        );
+        assert!("bson".parse::<BinaryFormat>().is_err());
+    }
+}
@@ -0,0 +1,37 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Runs git with `args` followed by `paths`, returns the lines it prints.
+fn git_lines(args: &[&str], paths: &[PathBuf]) -> io::Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(args)
+        .arg("--")
+        .args(paths)
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(String::from)
+        .collect())
+}
+
+/// Whether any of `paths`, files or directories, differs from the commit `reference` in the
+/// working tree or is new and untracked.
+pub fn changed_since(reference: &str, paths: &[&Path]) -> io::Result<bool> {
+    let paths = paths
+        .iter()
+        .map(|path| path.to_path_buf())
+        .collect::<Vec<PathBuf>>();
+    let changed = git_lines(&["diff", "--name-only", reference], &paths)?;
+    if !changed.is_empty() {
+        return Ok(true);
+    }
+    let untracked = git_lines(&["ls-files", "--others", "--exclude-standard"], &paths)?;
+    Ok(!untracked.is_empty())
+}
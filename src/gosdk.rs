@@ -0,0 +1,174 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::annotate::FunctionSummary;
+use crate::conflict::{Access, ConflictInfo};
+
+fn algorithm(gm: bool) -> &'static str {
+    if gm {
+        "sm3"
+    } else {
+        "keccak256"
+    }
+}
+
+/// The conflict keys of every function as JSON, the selectors hashed like the annotation.
+fn go_json(functions: &[FunctionSummary], gm: bool) -> String {
+    let descriptor = json!({
+        "hashAlgorithm": algorithm(gm),
+        "functions": functions
+            .iter()
+            .map(|function| {
+                json!({
+                    "signature": function.signature,
+                    "selector": format!("0x{:08x}", function.selector),
+                    "conflicts": function.conflicts,
+                })
+            })
+            .collect::<Vec<Value>>(),
+    });
+    serde_json::to_string_pretty(&descriptor).unwrap()
+}
+
+fn go_conflict(conflict: &ConflictInfo) -> String {
+    let mut fields = vec![format!("Kind: {}", conflict.kind.clone() as u8)];
+    for (name, value) in [
+        ("Slot", conflict.slot),
+        ("Len", conflict.len),
+        ("Offset", conflict.offset),
+        ("Size", conflict.size),
+        ("Group", conflict.group),
+    ]
+    .iter()
+    {
+        if let Some(value) = value {
+            fields.push(format!("{}: u32({})", name, value));
+        }
+    }
+    if let Some(access) = conflict.access {
+        let access = match access {
+            Access::Read => "read",
+            Access::Write => "write",
+        };
+        fields.push(format!("Access: {:?}", access));
+    }
+    if !conflict.value.is_empty() {
+        let value = conflict
+            .value
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<String>>();
+        fields.push(format!("Value: []uint32{{{}}}", value.join(", ")));
+    }
+    if !conflict.names.is_empty() {
+        let names = conflict
+            .names
+            .iter()
+            .map(|name| format!("{:?}", name))
+            .collect::<Vec<String>>();
+        fields.push(format!("Names: []string{{{}}}", names.join(", ")));
+    }
+    if let Some(note) = &conflict.note {
+        fields.push(format!("Note: {:?}", note));
+    }
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// The conflict keys of every function as go source for the bcos-go-sdk, with structs matching
+/// the conflictFields json.
+fn go_source(functions: &[FunctionSummary], gm: bool, abi: &Path) -> String {
+    let mut go = String::new();
+    writeln!(
+        go,
+        "// Code generated by conflicts_info_parse from {}. DO NOT EDIT.\n",
+        abi.display()
+    )
+    .unwrap();
+    go.push_str(
+        "package conflicts
+
+type ConflictField struct {
+\tKind   uint8    `json:\"kind\"`
+\tSlot   *uint32  `json:\"slot,omitempty\"`
+\tLen    *uint32  `json:\"len,omitempty\"`
+\tOffset *uint32  `json:\"offset,omitempty\"`
+\tSize   *uint32  `json:\"size,omitempty\"`
+\tAccess string   `json:\"access,omitempty\"`
+\tGroup  *uint32  `json:\"group,omitempty\"`
+\tValue  []uint32 `json:\"value,omitempty\"`
+\tNames  []string `json:\"names,omitempty\"`
+\tNote   string   `json:\"note,omitempty\"`
+}
+
+type FunctionConflicts struct {
+\tSignature string
+\tSelector  [4]byte
+\tConflicts []ConflictField
+}
+
+func u32(v uint32) *uint32 { return &v }
+
+",
+    );
+    writeln!(go, "const HashAlgorithm = {:?}\n", algorithm(gm)).unwrap();
+    go.push_str("var Functions = []FunctionConflicts{\n");
+    for function in functions {
+        let selector = function
+            .selector
+            .to_be_bytes()
+            .iter()
+            .map(|byte| format!("0x{:02x}", byte))
+            .collect::<Vec<String>>();
+        let conflicts = function
+            .conflicts
+            .iter()
+            .map(go_conflict)
+            .collect::<Vec<String>>();
+        writeln!(
+            go,
+            "\t{{Signature: {:?}, Selector: [4]byte{{{}}}, Conflicts: []ConflictField{{{}}}}},",
+            function.signature,
+            selector.join(", "),
+            conflicts.join(", ")
+        )
+        .unwrap();
+    }
+    go.push_str("}\n");
+    go
+}
+
+/// Writes the conflict descriptor for the go sdk, as go source for a `.go` path and as JSON
+/// otherwise.
+pub fn write_go_descriptor(path: &Path, abi: &Path, functions: &[FunctionSummary], gm: bool) {
+    let content = if path
+        .extension()
+        .map_or(false, |extension| extension == "go")
+    {
+        go_source(functions, gm, abi)
+    } else {
+        go_json(functions, gm)
+    };
+    std::fs::write(path, content)
+        .unwrap_or_else(|e| panic!("could not write {}: {}", path.display(), e));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::ConflictType;
+
+    #[test]
+    fn test_go_conflict() {
+        let mut conflict = ConflictInfo::new(ConflictType::Var, 0);
+        conflict.slot = Some(3);
+        conflict.value = vec![0];
+        conflict.names = vec![String::from("to")];
+        conflict.access = Some(Access::Write);
+        assert_eq!(
+            go_conflict(&conflict),
+            r#"{Kind: 3, Slot: u32(3), Access: "write", Value: []uint32{0}, Names: []string{"to"}}"#
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use std::convert::TryInto;
+
+/// The hash algorithm of selectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hash {
+    Keccak256,
+    /// the algorithm of GM networks
+    Sm3,
+}
+
+/// Computes the 4 bytes selector of a function signature.
+pub(crate) trait SelectorHasher: Sync {
+    fn selector(&self, signature: &str) -> u32;
+}
+
+fn selector_of(hash: &[u8]) -> u32 {
+    u32::from_be_bytes(hash[..4].try_into().unwrap())
+}
+
+/// Keccak256 by the sha3 crate.
+pub(crate) struct Sha3Keccak;
+
+impl SelectorHasher for Sha3Keccak {
+    fn selector(&self, signature: &str) -> u32 {
+        use sha3::Digest;
+        selector_of(&sha3::Keccak256::digest(signature.as_bytes()))
+    }
+}
+
+/// Keccak256 by tiny-keccak, considerably faster on batches of thousands of signatures.
+#[cfg(feature = "tiny-keccak")]
+pub(crate) struct TinyKeccak;
+
+#[cfg(feature = "tiny-keccak")]
+impl SelectorHasher for TinyKeccak {
+    fn selector(&self, signature: &str) -> u32 {
+        use tiny_keccak::Hasher;
+        let mut keccak = tiny_keccak::Keccak::v256();
+        keccak.update(signature.as_bytes());
+        let mut hash = [0u8; 32];
+        keccak.finalize(&mut hash);
+        selector_of(&hash)
+    }
+}
+
+#[cfg(feature = "gm")]
+pub(crate) struct Sm3;
+
+#[cfg(feature = "gm")]
+impl SelectorHasher for Sm3 {
+    fn selector(&self, signature: &str) -> u32 {
+        let mut sm3_hash = libsm::sm3::hash::Sm3Hash::new(signature.as_bytes());
+        selector_of(&sm3_hash.get_hash())
+    }
+}
+
+/// The hasher of SM3 selectors for `gm`, otherwise the fastest keccak256 built in.
+pub(crate) fn hasher(gm: bool) -> &'static dyn SelectorHasher {
+    if gm {
+        #[cfg(feature = "gm")]
+        return &Sm3;
+        #[cfg(not(feature = "gm"))]
+        unreachable!("SM3 selectors are only computed with the gm feature");
+    }
+    #[cfg(feature = "tiny-keccak")]
+    return &TinyKeccak;
+    #[cfg(not(feature = "tiny-keccak"))]
+    &Sha3Keccak
+}
+
+/// Every hash algorithm built in, by the name used in abis and provenance.
+pub(crate) fn algorithms() -> Vec<(&'static str, &'static dyn SelectorHasher)> {
+    // only pushed to with the gm feature
+    #[allow(unused_mut)]
+    let mut algorithms = vec![("keccak256", hasher(false))];
+    #[cfg(feature = "gm")]
+    algorithms.push(("sm3", &Sm3 as &dyn SelectorHasher));
+    algorithms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keccak_backends() {
+        assert_eq!(Sha3Keccak.selector("transfer(address,uint256)"), 0xa9059cbb);
+        assert_eq!(
+            hasher(false).selector("transfer(address,uint256)"),
+            0xa9059cbb
+        );
+    }
+}
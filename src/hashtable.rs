@@ -0,0 +1,147 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::abi::AbiEntry;
+use crate::annotate::FunctionSummary;
+use crate::get_method_signature;
+use crate::hash::algorithms;
+
+/// How the selector table is printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableFormat {
+    Json,
+    Tsv,
+}
+
+impl FromStr for TableFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(TableFormat::Json),
+            "tsv" => Ok(TableFormat::Tsv),
+            _ => Err(format!("unknown table format {}", s)),
+        }
+    }
+}
+
+/// The signature and its selector under every built in algorithm, for every function.
+fn hash_rows(entries: &[AbiEntry]) -> Vec<(String, Vec<(&'static str, u32)>)> {
+    let algorithms = algorithms();
+    entries
+        .iter()
+        .filter(|entry| entry.is_function())
+        .map(|entry| {
+            let signature = get_method_signature(entry);
+            let selectors = algorithms
+                .iter()
+                .map(|(name, hasher)| (*name, hasher.selector(&signature)))
+                .collect();
+            (signature, selectors)
+        })
+        .collect()
+}
+
+fn format_table(entries: &[AbiEntry], format: TableFormat) -> String {
+    let rows = hash_rows(entries);
+    match format {
+        TableFormat::Json => {
+            let rows = rows
+                .into_iter()
+                .map(|(signature, selectors)| {
+                    let mut row = Map::new();
+                    row.insert("signature".into(), signature.into());
+                    for (name, selector) in selectors {
+                        row.insert(name.into(), format!("0x{:08x}", selector).into());
+                    }
+                    Value::Object(row)
+                })
+                .collect::<Vec<Value>>();
+            serde_json::to_string_pretty(&rows).unwrap()
+        }
+        TableFormat::Tsv => {
+            let names = algorithms()
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<&str>>();
+            let mut tsv = format!("signature\t{}\n", names.join("\t"));
+            for (signature, selectors) in rows {
+                let selectors = selectors
+                    .iter()
+                    .map(|(_, selector)| format!("0x{:08x}", selector))
+                    .collect::<Vec<String>>();
+                tsv.push_str(&format!("{}\t{}\n", signature, selectors.join("\t")));
+            }
+            tsv
+        }
+    }
+}
+
+/// Prints the selectors of every function of `abi` under every built in hash algorithm.
+pub fn hash_table(abi: &Path, format: TableFormat) {
+    let abi_content = std::fs::read_to_string(abi)
+        .unwrap_or_else(|_| panic!("could not read file {}", abi.display()));
+    let entries: Vec<AbiEntry> = serde_json::from_str(&abi_content).unwrap();
+    print!("{}", format_table(&entries, format));
+}
+
+/// The `selectors.map` of an annotated abi, a tab separated selector, canonical signature and
+/// contract per function, the analyzer can label its outputs with.
+fn selectors_map(contract: &str, functions: &[FunctionSummary]) -> String {
+    functions
+        .iter()
+        .map(|function| {
+            format!(
+                "0x{:08x}\t{}\t{}\n",
+                function.selector, function.signature, contract
+            )
+        })
+        .collect()
+}
+
+/// Writes the `selectors.map` of `abi` to `path`, the contract is named after the abi file.
+pub fn write_selectors_map(path: &Path, abi: &Path, functions: &[FunctionSummary]) {
+    std::fs::write(path, selectors_map(&crate::contract_name(abi), functions))
+        .unwrap_or_else(|e| panic!("could not write {}: {}", path.display(), e));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_table() {
+        let entries: Vec<AbiEntry> = serde_json::from_str(
+            r#"[
+                {
+                    "inputs": [
+                        {"name": "to", "type": "address"},
+                        {"name": "amount", "type": "uint256"}
+                    ],
+                    "name": "transfer",
+                    "type": "function"
+                },
+                {"name": "Transfer", "type": "event"}
+            ]"#,
+        )
+        .unwrap();
+        let tsv = format_table(&entries, TableFormat::Tsv);
+        let lines = tsv.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("signature\tkeccak256"));
+        assert!(lines[1].starts_with("transfer(address,uint256)\t0xa9059cbb"));
+        let json: Value = serde_json::from_str(&format_table(&entries, TableFormat::Json)).unwrap();
+        assert_eq!(json[0]["keccak256"], "0xa9059cbb");
+    }
+
+    #[test]
+    fn test_selectors_map() {
+        let function = FunctionSummary::for_test("transfer(address,uint256)", vec![]);
+        assert_eq!(
+            selectors_map("Token", &[function]),
+            "0xa9059cbb\ttransfer(address,uint256)\tToken\n"
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+
+use crate::config::Config;
+use crate::get_method_signature;
+use crate::hash::algorithms;
+use crate::layout::SelectorRadix;
+use crate::merge::read_entries;
+use crate::source::{CsvOptions, MissingCsv};
+
+/// How deep below the working directory abis and analyzer outputs are looked for.
+const MAX_DEPTH: usize = 4;
+
+/// Directories of dependencies and build caches, never holding the abis of the project.
+const SKIPPED: [&str; 4] = [".git", "target", "node_modules", "cache"];
+
+/// The sections of the config, commented out.
+const EXAMPLES: &str = r#"# environment names of newer analyzer outputs and their codes
+# [environments]
+# BLOBBASEFEE = 9
+
+# more spellings of the environment names
+# [environment_aliases]
+# "block.blobbasefee" = "BLOBBASEFEE"
+
+# csv layouts whose columns differ from v1
+# [layouts.v3.Conflict_FunArgConflict]
+# selector = 2
+# value = 3
+# slot = 4
+
+# conflicts given to every function matching a pattern
+# [[rule]]
+# functions = "set*"
+# conflicts = [{ kind = "Env", value = [0] }]
+
+# rules of the lint command
+# [[lint]]
+# code = "no-all"
+# message = "serialized with All"
+# forbid = "All"
+
+# slots whose conflicts are dropped
+# ignore_slots = ["0x0"]
+"#;
+
+/// What `init` found in the project.
+#[derive(Debug, Default, PartialEq)]
+struct Detected {
+    /// like `build/*.abi`, for the directory with the most abis
+    abi_glob: Option<String>,
+    /// the first directory with analyzer csvs
+    conflict_dir: Option<PathBuf>,
+    /// the algorithm most sampled selectors match under, none if nothing matched
+    algorithm: Option<&'static str>,
+}
+
+/// The directories below `dir` with their files, breadth first.
+fn walk(dir: &Path, depth: usize, found: &mut Vec<(PathBuf, Vec<PathBuf>)>) {
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            let name = entry.file_name();
+            if !SKIPPED.iter().any(|skipped| name == *skipped) {
+                dirs.push(path);
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    dirs.sort();
+    found.push((dir.to_path_buf(), files));
+    if depth < MAX_DEPTH {
+        for dir in dirs {
+            walk(&dir, depth + 1, found);
+        }
+    }
+}
+
+/// The algorithm the most selectors of the analyzer outputs in `conflict_dir` match the
+/// functions of `abis` under.
+fn guess_algorithm(abis: &[PathBuf], conflict_dir: &Path) -> Option<&'static str> {
+    let config = Config::default();
+    let layouts = config.csv_layouts();
+    let environments = config.environments();
+    let options = CsvOptions {
+        layouts: &layouts,
+        layout: None,
+        only: None,
+        environments: &environments,
+        selector_radix: SelectorRadix::Auto,
+        gm: false,
+        missing: MissingCsv::Ignore,
+    };
+    let sample = crate::parse_conflict_info(conflict_dir, &options)
+        .ok()?
+        .into_iter()
+        .map(|conflict| conflict.selector)
+        .collect::<BTreeSet<u32>>();
+    let signatures = abis
+        .iter()
+        .flat_map(|abi| read_entries(abi))
+        .filter(|entry| entry.is_function())
+        .map(|entry| get_method_signature(&entry))
+        .collect::<Vec<String>>();
+    algorithms()
+        .into_iter()
+        .map(|(name, hasher)| {
+            let matching = signatures
+                .iter()
+                .filter(|signature| sample.contains(&hasher.selector(signature)))
+                .count();
+            (name, matching)
+        })
+        .filter(|(_, matching)| *matching > 0)
+        .max_by_key(|(_, matching)| *matching)
+        .map(|(name, _)| name)
+}
+
+fn detect(root: &Path) -> Detected {
+    let mut found = Vec::new();
+    walk(root, 0, &mut found);
+    let is_abi = |path: &&PathBuf| {
+        path.extension()
+            .map_or(false, |extension| extension == "abi")
+    };
+    let abi_dir = found
+        .iter()
+        .map(|(dir, files)| (dir, files.iter().filter(is_abi).count()))
+        .filter(|(_, count)| *count > 0)
+        .fold(
+            None,
+            |best: Option<(&PathBuf, usize)>, (dir, count)| match best {
+                Some((_, best_count)) if best_count >= count => best,
+                _ => Some((dir, count)),
+            },
+        )
+        .map(|(dir, _)| dir);
+    let conflict_dir = found
+        .iter()
+        .find(|(_, files)| {
+            files.iter().any(|file| {
+                crate::CONFLICT_CSVS
+                    .iter()
+                    .any(|(file_name, _)| file.file_name().map_or(false, |name| name == *file_name))
+            })
+        })
+        .map(|(dir, _)| relative(root, dir));
+    let abis = found
+        .iter()
+        .filter(|(dir, _)| Some(dir) == abi_dir)
+        .flat_map(|(_, files)| files.iter().filter(is_abi).cloned())
+        .collect::<Vec<PathBuf>>();
+    Detected {
+        abi_glob: abi_dir.map(|dir| format!("{}", relative(root, dir).join("*.abi").display())),
+        algorithm: match &conflict_dir {
+            Some(conflict_dir) => guess_algorithm(&abis, &root.join(conflict_dir)),
+            None => None,
+        },
+        conflict_dir,
+    }
+}
+
+fn relative(root: &Path, path: &Path) -> PathBuf {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    if relative.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative.to_path_buf()
+    }
+}
+
+/// The commented config, every section left commented out with an example. The config holds no
+/// inputs, so what was detected goes into the command line suggested at the top.
+fn config_text(detected: &Detected) -> String {
+    let abi = detected
+        .abi_glob
+        .as_deref()
+        .unwrap_or("<path/to/Contract.abi>");
+    let conflict_dir = detected.conflict_dir.as_ref().map_or_else(
+        || String::from("<analyzer outputs>"),
+        |dir| format!("{}", dir.display()),
+    );
+    let gm = if detected.algorithm == Some("sm3") {
+        " --gm"
+    } else {
+        ""
+    };
+    let lines = vec![
+        String::from(
+            "# conflicts_info_parse.toml, read from the working directory or with --config",
+        ),
+        String::from("#"),
+        format!(
+            "# abis: {}",
+            detected.abi_glob.as_deref().unwrap_or("none found")
+        ),
+        format!(
+            "# analyzer outputs: {}",
+            detected.conflict_dir.as_ref().map_or_else(
+                || String::from("none found"),
+                |dir| format!("{}", dir.display())
+            )
+        ),
+        format!(
+            "# selectors hashed with: {}",
+            detected
+                .algorithm
+                .unwrap_or("unknown, check with the doctor command")
+        ),
+        String::from("#"),
+        String::from("# annotate every abi with:"),
+        format!(
+            "#   for abi in {}; do conflicts_info_parse{} --abi \"$abi\" --path {}; done",
+            abi, gm, conflict_dir
+        ),
+        String::new(),
+        String::from(EXAMPLES),
+    ];
+    lines.join("\n")
+}
+
+/// Writes a commented config with what was found in the working directory, leaves an existing
+/// one alone unless `force`.
+pub fn init(force: bool) -> bool {
+    let path = Path::new(crate::config::DEFAULT_PATH);
+    if path.exists() && !force {
+        eprintln!(
+            "{}: {} exists, pass --force to overwrite it",
+            "error".red(),
+            path.display()
+        );
+        return false;
+    }
+    let detected = detect(Path::new("."));
+    std::fs::write(path, config_text(&detected))
+        .unwrap_or_else(|e| panic!("could not write {}: {}", path.display(), e));
+    print!("wrote {}", format!("{}", path.display()).green());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_text() {
+        let detected = Detected {
+            abi_glob: Some(String::from("build/*.abi")),
+            conflict_dir: Some(PathBuf::from("conflicts")),
+            algorithm: Some("sm3"),
+        };
+        let text = config_text(&detected);
+        assert!(text.contains("conflicts_info_parse --gm --abi \"$abi\" --path conflicts"));
+        assert!(text.contains("# selectors hashed with: sm3"));
+        assert!(text.contains("# [[rule]]"));
+        let text = config_text(&Detected::default());
+        assert!(text.contains("# abis: none found"));
+        assert!(!text.contains("--gm"));
+    }
+}
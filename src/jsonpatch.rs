@@ -0,0 +1,148 @@
+use serde_json::{json, Map, Value};
+
+use crate::abi::AbiEntry;
+use crate::get_method_signature;
+
+/// Escapes a key as a reference token of a JSON Pointer.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Appends the RFC 6902 operations turning `from` at `path` into `to`. Objects and arrays are
+/// descended into, so only the members the annotation touched are in the patch.
+fn diff_into(path: &str, from: &Value, to: &Value, ops: &mut Vec<Value>) {
+    match (from, to) {
+        _ if from == to => {}
+        (Value::Object(from), Value::Object(to)) => {
+            for key in from.keys().filter(|key| !to.contains_key(*key)) {
+                ops.push(json!({"op": "remove", "path": format!("{}/{}", path, escape(key))}));
+            }
+            for (key, value) in to {
+                let path = format!("{}/{}", path, escape(key));
+                match from.get(key) {
+                    Some(previous) => diff_into(&path, previous, value, ops),
+                    None => ops.push(json!({"op": "add", "path": path, "value": value})),
+                }
+            }
+        }
+        (Value::Array(from), Value::Array(to)) => {
+            for (index, (previous, value)) in from.iter().zip(to).enumerate() {
+                diff_into(&format!("{}/{}", path, index), previous, value, ops);
+            }
+            // from the end, so the indices of the elements before stay valid
+            for index in (to.len()..from.len()).rev() {
+                ops.push(json!({"op": "remove", "path": format!("{}/{}", path, index)}));
+            }
+            for (index, value) in to.iter().enumerate().skip(from.len()) {
+                ops.push(
+                    json!({"op": "add", "path": format!("{}/{}", path, index), "value": value}),
+                );
+            }
+        }
+        _ => ops.push(json!({"op": "replace", "path": path, "value": to})),
+    }
+}
+
+/// The RFC 6902 patch turning the document `from` into `to`.
+pub(crate) fn json_patch(from: &Value, to: &Value) -> Value {
+    let mut ops = Vec::new();
+    diff_into("", from, to, &mut ops);
+    Value::Array(ops)
+}
+
+/// The RFC 7386 merge patch turning `from` into `to`, none if they are equal. Arrays can't be
+/// merged into and are replaced as a whole.
+fn merge_patch(from: &Value, to: &Value) -> Option<Value> {
+    match (from, to) {
+        _ if from == to => None,
+        (Value::Object(from), Value::Object(to)) => {
+            let mut patch = from
+                .keys()
+                .filter(|key| !to.contains_key(*key))
+                .map(|key| (key.clone(), Value::Null))
+                .collect::<Map<String, Value>>();
+            for (key, value) in to {
+                let changed = match from.get(key) {
+                    Some(previous) => merge_patch(previous, value),
+                    None => Some(value.clone()),
+                };
+                if let Some(changed) = changed {
+                    patch.insert(key.clone(), changed);
+                }
+            }
+            Some(Value::Object(patch))
+        }
+        _ => Some(to.clone()),
+    }
+}
+
+/// The functions of an abi by signature.
+fn functions_by_signature(abi: &[Value]) -> Map<String, Value> {
+    abi.iter()
+        .filter_map(|value| {
+            let entry = AbiEntry::parse(value.clone())
+                .ok()
+                .filter(AbiEntry::is_function)?;
+            Some((get_method_signature(&entry), value.clone()))
+        })
+        .collect()
+}
+
+/// The merge patches of the functions whose entries changed from the abi `from` to `to`, keyed
+/// by signature, as abis are arrays a merge patch can't address the elements of.
+pub(crate) fn merge_patches(from: &[Value], to: &[Value]) -> Value {
+    let from = functions_by_signature(from);
+    let patches = functions_by_signature(to)
+        .into_iter()
+        .filter_map(|(signature, entry)| {
+            let previous = from.get(&signature).cloned().unwrap_or_else(|| json!({}));
+            merge_patch(&previous, &entry).map(|patch| (signature, patch))
+        })
+        .collect::<Map<String, Value>>();
+    Value::Object(patches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_patch() {
+        let from = json!([
+            {"name": "set", "type": "function", "conflictFields": [{"kind": 0}]},
+            {"name": "get", "type": "function", "selector": [1]}
+        ]);
+        let to = json!([
+            {"name": "set", "type": "function", "conflictFields": [{"kind": 3, "slot": 1}]},
+            {"name": "get", "type": "function"},
+            {"type": "provenance"}
+        ]);
+        assert_eq!(
+            json_patch(&from, &to),
+            json!([
+                {"op": "replace", "path": "/0/conflictFields/0/kind", "value": 3},
+                {"op": "add", "path": "/0/conflictFields/0/slot", "value": 1},
+                {"op": "remove", "path": "/1/selector"},
+                {"op": "add", "path": "/2", "value": {"type": "provenance"}}
+            ])
+        );
+        assert_eq!(escape("a/b~c"), "a~1b~0c");
+    }
+
+    #[test]
+    fn test_merge_patches() {
+        let from = vec![
+            json!({"inputs": [], "name": "get", "type": "function", "selector": [1]}),
+            json!({"inputs": [], "name": "set", "type": "function"}),
+        ];
+        let to = vec![
+            json!({"inputs": [], "name": "get", "type": "function"}),
+            json!({"inputs": [], "name": "set", "type": "function", "conflictFields": []}),
+            json!({"type": "provenance"}),
+        ];
+        assert_eq!(
+            merge_patches(&from, &to),
+            json!({"get()": {"selector": null}, "set()": {"conflictFields": []}})
+        );
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::BTreeMap;
+use std::num::ParseIntError;
+use std::ops::Index;
+use std::str::FromStr;
+
+use log::warn;
+use serde::Deserialize;
+
+/// The columns a record parser reads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Selector,
+    /// the kind specific column: the environment, the key indices or the constant
+    Value,
+    Slot,
+    Len,
+    Offset,
+    Size,
+    Access,
+    Group,
+    /// free text after all other columns
+    Note,
+    /// the function name, in front of the other columns
+    Function,
+}
+
+/// Where the columns of one analyzer output are.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct CsvColumns {
+    pub(crate) selector: usize,
+    #[serde(default)]
+    pub(crate) value: Option<usize>,
+    #[serde(default)]
+    pub(crate) slot: Option<usize>,
+    #[serde(default)]
+    pub(crate) len: Option<usize>,
+    #[serde(default)]
+    pub(crate) offset: Option<usize>,
+    #[serde(default)]
+    pub(crate) size: Option<usize>,
+    #[serde(default)]
+    pub(crate) access: Option<usize>,
+    #[serde(default)]
+    pub(crate) group: Option<usize>,
+    #[serde(default)]
+    pub(crate) note: Option<usize>,
+    #[serde(default)]
+    pub(crate) function: Option<usize>,
+}
+
+impl CsvColumns {
+    pub(crate) fn index(&self, column: Column) -> Option<usize> {
+        match column {
+            Column::Selector => Some(self.selector),
+            Column::Value => self.value,
+            Column::Slot => self.slot,
+            Column::Len => self.len,
+            Column::Offset => self.offset,
+            Column::Size => self.size,
+            Column::Access => self.access,
+            Column::Group => self.group,
+            Column::Note => self.note,
+            Column::Function => self.function,
+        }
+    }
+
+    /// The original columns with their optional trailing ones after the slot, the note comes
+    /// after all of them. Outputs with only a selector have no note unless configured.
+    fn v1(selector: usize, value: Option<usize>, slot: Option<usize>) -> CsvColumns {
+        let trailing = |offset: usize| slot.map(|_| 4 + offset);
+        let after_value = value.map(|value| selector.max(value) + 1);
+        CsvColumns {
+            selector,
+            value,
+            slot,
+            len: trailing(0),
+            offset: trailing(1),
+            size: trailing(2),
+            access: trailing(3),
+            group: trailing(4),
+            note: trailing(5).or(after_value),
+            function: None,
+        }
+    }
+
+    /// The columns behind a function name column.
+    fn named(&self) -> CsvColumns {
+        CsvColumns {
+            function: Some(0),
+            ..self.shifted(1)
+        }
+    }
+
+    fn shifted(&self, by: usize) -> CsvColumns {
+        let shift = |index: Option<usize>| index.map(|index| index + by);
+        CsvColumns {
+            selector: self.selector + by,
+            value: shift(self.value),
+            slot: shift(self.slot),
+            len: shift(self.len),
+            offset: shift(self.offset),
+            size: shift(self.size),
+            access: shift(self.access),
+            group: shift(self.group),
+            note: shift(self.note),
+            function: shift(self.function),
+        }
+    }
+}
+
+/// A named column layout of all analyzer outputs, keyed by csv file name without extension.
+/// Analyzer builds reorder or add columns, so layouts can also be defined in the config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvLayout {
+    pub name: String,
+    pub(crate) csvs: BTreeMap<String, CsvColumns>,
+}
+
+impl CsvLayout {
+    pub(crate) fn builtin() -> Vec<CsvLayout> {
+        let v1 = CsvLayout {
+            name: "v1".into(),
+            csvs: vec![
+                ("Conflict_EnvConflict", CsvColumns::v1(1, Some(2), Some(3))),
+                ("Conflict_MixConflict", CsvColumns::v1(0, None, None)),
+                (
+                    "Conflict_NoStorageAccessHasContractCalling",
+                    CsvColumns::v1(0, None, None),
+                ),
+                (
+                    "Conflict_FunArgConflict",
+                    CsvColumns::v1(1, Some(2), Some(3)),
+                ),
+                (
+                    "Conflict_DynaVarConsConflict",
+                    CsvColumns::v1(1, Some(3), Some(2)),
+                ),
+                (
+                    "Conflict_BasicVarConsConflict",
+                    CsvColumns::v1(1, Some(2), None),
+                ),
+                ("Conflict_NoConflict", CsvColumns::v1(0, None, None)),
+            ]
+            .into_iter()
+            .map(|(csv, columns)| (csv.to_string(), columns))
+            .collect(),
+        };
+        // v2 has the function name first
+        let v2 = CsvLayout {
+            name: "v2".into(),
+            csvs: v1
+                .csvs
+                .iter()
+                .map(|(csv, columns)| (csv.clone(), columns.named()))
+                .collect(),
+        };
+        vec![v1, v2]
+    }
+
+    pub(crate) fn columns(&self, csv: &str) -> &CsvColumns {
+        self.csvs
+            .get(csv)
+            .unwrap_or_else(|| panic!("csv layout {} has no columns for {}", self.name, csv))
+    }
+
+    /// Recognizes the layout of `csv` from its first row by where the selector is. Later layouts
+    /// are tried first, so those of the config win over the builtin ones with the selector in the
+    /// same column.
+    pub(crate) fn detect<'a>(
+        layouts: &'a [CsvLayout],
+        csv: &str,
+        record: &csv::StringRecord,
+    ) -> Option<&'a CsvLayout> {
+        let found = record.iter().position(is_selector)?;
+        layouts.iter().rev().find(|layout| {
+            layout
+                .csvs
+                .get(csv)
+                .map_or(false, |columns| columns.selector == found)
+        })
+    }
+}
+
+fn is_selector(column: &str) -> bool {
+    let hex = column.len() == 10
+        && column.starts_with("0x")
+        && column[2..].chars().all(|c| c.is_ascii_hexdigit());
+    let decimal = column.bytes().all(|c| c.is_ascii_digit()) && column.parse::<u32>().is_ok();
+    // some analyzer builds write the canonical signature instead
+    let signature = column.find('(').map_or(false, |open| open > 0) && column.ends_with(')');
+    hex || decimal || signature
+}
+
+/// How the selectors of the csvs are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectorRadix {
+    /// hex with a `0x` prefix, decimal if only digits, hex otherwise as older analyzers wrote.
+    /// Eight digits are taken as hex, the width of a selector without prefix
+    Auto,
+    Hex,
+    Decimal,
+}
+
+impl FromStr for SelectorRadix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(SelectorRadix::Auto),
+            "hex" => Ok(SelectorRadix::Hex),
+            "decimal" => Ok(SelectorRadix::Decimal),
+            _ => Err(format!("unknown selector radix {}", s)),
+        }
+    }
+}
+
+impl SelectorRadix {
+    pub(crate) fn parse(self, selector: &str) -> Result<u32, String> {
+        if self == SelectorRadix::Auto && is_ambiguous(selector) {
+            warn!(
+                "selector {} may be hex or decimal, taken as hex, pick one with --selector-radix",
+                selector
+            );
+        }
+        self.try_parse(selector)
+            .map_err(|e| format!("invalid selector {:?}: {}", selector, e))
+    }
+
+    pub(crate) fn try_parse(self, selector: &str) -> Result<u32, ParseIntError> {
+        let digits = selector.trim_start_matches("0x");
+        let radix = match self {
+            SelectorRadix::Hex => 16,
+            SelectorRadix::Decimal => 10,
+            SelectorRadix::Auto if is_ambiguous(selector) => 16,
+            SelectorRadix::Auto
+                if digits.len() == selector.len() && digits.bytes().all(|c| c.is_ascii_digit()) =>
+            {
+                10
+            }
+            SelectorRadix::Auto => 16,
+        };
+        u32::from_str_radix(digits, radix)
+    }
+}
+
+/// Whether `selector` is eight digits without prefix, hex as wide as a selector or a decimal one.
+fn is_ambiguous(selector: &str) -> bool {
+    selector.len() == 8 && selector.bytes().all(|c| c.is_ascii_digit())
+}
+
+/// A record seen through the columns of its layout.
+pub struct Row<'a> {
+    pub(crate) record: &'a csv::StringRecord,
+    pub(crate) columns: &'a CsvColumns,
+}
+
+impl<'a> Row<'a> {
+    /// The column, none if the layout or the record doesn't have it.
+    pub(crate) fn get(&self, column: Column) -> Option<&'a str> {
+        let record = self.record;
+        let index = match self.short_note() {
+            Some(note) if column == Column::Note => Some(note),
+            Some(note) if self.columns.index(column) == Some(note) => None,
+            _ => self.columns.index(column),
+        };
+        index.and_then(|index| record.get(index))
+    }
+
+    /// The column, an error if the layout or the record doesn't have it.
+    pub(crate) fn required(&self, column: Column) -> Result<&'a str, String> {
+        self.get(column)
+            .ok_or_else(|| format!("no {:?} column in {:?}", column, self.record))
+    }
+
+    /// The last column when the note follows fewer optional columns than the layout has, like
+    /// `Token,0xa9059cbb,CALLER,0x0,Token.sol:12`: a text that is neither a number nor an access.
+    fn short_note(&self) -> Option<usize> {
+        let last = self.record.len().checked_sub(1)?;
+        let columns = self.columns;
+        let fixed = [
+            Some(columns.selector),
+            columns.value,
+            columns.slot,
+            columns.function,
+        ]
+        .iter()
+        .flatten()
+        .copied()
+        .max()?;
+        if last <= fixed || columns.note.map_or(true, |note| note <= last) {
+            return None;
+        }
+        let value = self.record[last].trim();
+        let access = ["r", "w", "read", "write"]
+            .iter()
+            .any(|access| value.eq_ignore_ascii_case(access));
+        (!value.is_empty() && value.parse::<u32>().is_err() && !access).then(|| last)
+    }
+}
+
+impl Index<Column> for Row<'_> {
+    type Output = str;
+
+    fn index(&self, column: Column) -> &str {
+        self.required(column).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let layouts = CsvLayout::builtin();
+        let v1 = csv::StringRecord::from(vec!["Token", "0xa9059cbb", "CALLER", "0x0"]);
+        let v2 = csv::StringRecord::from(vec!["transfer", "Token", "0xa9059cbb", "CALLER", "0x0"]);
+        let csv = "Conflict_EnvConflict";
+        assert_eq!(CsvLayout::detect(&layouts, csv, &v1).unwrap().name, "v1");
+        let layout = CsvLayout::detect(&layouts, csv, &v2).unwrap();
+        assert_eq!(layout.name, "v2");
+        let row = Row {
+            record: &v2,
+            columns: layout.columns(csv),
+        };
+        assert_eq!(&row[Column::Selector], "0xa9059cbb");
+        assert_eq!(&row[Column::Slot], "0x0");
+        assert_eq!(row.get(Column::Len), None);
+        assert_eq!(row.get(Column::Function), Some("transfer"));
+        assert_eq!(row.get(Column::Note), None);
+        let noted = csv::StringRecord::from(vec!["Token", "0xa9059cbb", "0x1", "Token.sol:12"]);
+        let row = Row {
+            record: &noted,
+            columns: layouts[0].columns("Conflict_BasicVarConsConflict"),
+        };
+        assert_eq!(row.get(Column::Note), Some("Token.sol:12"));
+        let noted = ["Token", "0xa9059cbb", "CALLER", "0x0", "Token.sol:12"];
+        let noted = csv::StringRecord::from(noted.to_vec());
+        let row = Row {
+            record: &noted,
+            columns: layouts[0].columns(csv),
+        };
+        assert_eq!(row.get(Column::Len), None);
+        assert_eq!(row.get(Column::Note), Some("Token.sol:12"));
+        let sized = csv::StringRecord::from(vec!["Token", "0xa9059cbb", "CALLER", "0x0", "2"]);
+        let row = Row {
+            record: &sized,
+            columns: layouts[0].columns(csv),
+        };
+        assert_eq!(row.get(Column::Len), Some("2"));
+        assert_eq!(row.get(Column::Note), None);
+        let dynamic = layout.columns("Conflict_DynaVarConsConflict");
+        assert_eq!((dynamic.slot, dynamic.value), (Some(3), Some(4)));
+        assert!(CsvLayout::detect(&layouts, "Conflict_NoConflict", &v1).is_none());
+        let decimal = csv::StringRecord::from(vec!["Token", "2835717307", "CALLER", "0x0"]);
+        assert_eq!(
+            CsvLayout::detect(&layouts, csv, &decimal).unwrap().name,
+            "v1"
+        );
+        let signature = ["Token", "transfer(address,uint256)", "CALLER", "0x0"];
+        let signature = csv::StringRecord::from(signature.to_vec());
+        assert_eq!(
+            CsvLayout::detect(&layouts, csv, &signature).unwrap().name,
+            "v1"
+        );
+    }
+
+    #[test]
+    fn test_selector_radix() {
+        assert_eq!(SelectorRadix::Auto.parse("0xa9059cbb"), Ok(0xa9059cbb));
+        assert_eq!(SelectorRadix::Auto.parse("2835717307"), Ok(0xa9059cbb));
+        assert_eq!(SelectorRadix::Auto.parse("a9059cbb"), Ok(0xa9059cbb));
+        assert_eq!(SelectorRadix::Hex.parse("12345678"), Ok(0x12345678));
+        assert_eq!(SelectorRadix::Decimal.parse("12345678"), Ok(12345678));
+        assert_eq!(SelectorRadix::Auto.parse("12345678"), Ok(0x12345678));
+        assert_eq!(SelectorRadix::Auto.parse("1234567"), Ok(1234567));
+    }
+}
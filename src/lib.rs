@@ -0,0 +1,1454 @@
+//! Annotates contract abis with the conflicts the parallel scheduler of FISCO BCOS needs, the
+//! command line is built on it. [`AnnotationOptions`] configures an [`Annotator`] of the conflicts
+//! parsed from the analyzer outputs.
+
+use log::{error, info};
+use rayon::prelude::*;
+use regex::Regex;
+
+// public for the command line, the library api is what is re-exported below
+#[doc(hidden)]
+pub mod abi;
+#[doc(hidden)]
+pub mod annotate;
+#[doc(hidden)]
+pub mod builtin;
+#[doc(hidden)]
+pub mod cache;
+#[doc(hidden)]
+pub mod config;
+#[doc(hidden)]
+pub mod conflict;
+#[doc(hidden)]
+pub mod devdoc;
+#[doc(hidden)]
+pub mod diamond;
+#[doc(hidden)]
+pub mod diff;
+#[doc(hidden)]
+pub mod doctor;
+#[doc(hidden)]
+pub mod emit;
+#[doc(hidden)]
+pub mod encoding;
+#[doc(hidden)]
+pub mod explain;
+#[doc(hidden)]
+pub mod export;
+#[doc(hidden)]
+pub mod git;
+#[doc(hidden)]
+pub mod gosdk;
+#[doc(hidden)]
+pub mod hash;
+#[doc(hidden)]
+pub mod hashtable;
+#[doc(hidden)]
+pub mod init;
+#[doc(hidden)]
+pub mod jsonpatch;
+#[doc(hidden)]
+pub mod layout;
+#[doc(hidden)]
+pub mod library;
+#[doc(hidden)]
+pub mod lint;
+#[doc(hidden)]
+pub mod manifest;
+#[doc(hidden)]
+pub mod merge;
+#[doc(hidden)]
+pub mod migrate;
+#[doc(hidden)]
+pub mod options;
+#[doc(hidden)]
+pub mod parallelism;
+#[doc(hidden)]
+pub mod patch;
+#[doc(hidden)]
+pub mod profile;
+#[doc(hidden)]
+pub mod provenance;
+#[doc(hidden)]
+pub mod proxy;
+#[doc(hidden)]
+pub mod query;
+#[doc(hidden)]
+pub mod report;
+#[doc(hidden)]
+pub mod review;
+#[doc(hidden)]
+pub mod rules;
+#[doc(hidden)]
+pub mod sarif;
+#[doc(hidden)]
+pub mod schema;
+#[doc(hidden)]
+pub mod script;
+#[doc(hidden)]
+pub mod signing;
+#[doc(hidden)]
+pub mod sink;
+#[doc(hidden)]
+pub mod slots;
+#[doc(hidden)]
+pub mod source;
+#[doc(hidden)]
+pub mod strip;
+#[doc(hidden)]
+pub mod system;
+#[doc(hidden)]
+pub mod validate;
+
+pub use abi::AbiEntry;
+pub use annotate::{Annotator, CapPolicy, FunctionSummary, Warning};
+pub use conflict::{ConflictInfo, ConflictType, SlotFormat};
+pub use hash::Hash;
+pub use options::AnnotationOptions;
+pub use source::{ConflictSource, CsvOptions};
+
+use abi::AbiParam;
+use conflict::{Access, EnvironmentType, Environments};
+use layout::{Column, CsvLayout, Row, SelectorRadix};
+use source::MissingCsv;
+
+fn csv_reader(content: &[u8]) -> csv::Reader<&[u8]> {
+    // hand maintained csvs have comments, and the optional trailing columns vary per row
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(b'\t')
+        .comment(Some(b'#'))
+        .flexible(true)
+        .from_reader(content)
+}
+
+/// Feeds every record of a tab separated analyzer output to `f`. The file is memory-mapped and a
+/// single record buffer is reused, so memory stays flat regardless of the csv size. Unless forced,
+/// the layout is recognized from the first row among `layouts`.
+fn for_each_record<F: FnMut(&Row) -> Result<(), String>>(
+    csv_path: &std::path::Path,
+    layouts: &[CsvLayout],
+    forced: Option<&CsvLayout>,
+    mut f: F,
+) -> Result<(), String> {
+    let csv = csv_path.file_stem().unwrap().to_str().unwrap();
+    let file = std::fs::File::open(csv_path)
+        .map_err(|e| format!("could not open {}: {}", csv_path.display(), e))?;
+    if file.metadata().map_or(0, |metadata| metadata.len()) == 0 {
+        return Ok(());
+    }
+    // the analyzer has finished writing its outputs, nothing truncates them while mapped
+    let mmap = unsafe { memmap2::Mmap::map(&file) }
+        .map_err(|e| format!("could not map {}: {}", csv_path.display(), e))?;
+    let content = encoding::decode_csv(&mmap[..], csv_path);
+    let mut rdr = csv_reader(&content);
+    let mut record = csv::StringRecord::new();
+    let mut layout = forced;
+    while rdr
+        .read_record(&mut record)
+        .map_err(|e| format!("could not read {}: {}", csv_path.display(), e))?
+    {
+        // empty lines are skipped by the reader, lines of only blanks are not
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        if layout.is_none() {
+            layout = Some(CsvLayout::detect(layouts, csv, &record).ok_or_else(|| {
+                format!(
+                    "unknown column layout of {}, pick one with --csv-layout",
+                    csv_path.display()
+                )
+            })?);
+        }
+        f(&Row {
+            record: &record,
+            columns: layout.unwrap().columns(csv),
+        })
+        .map_err(|e| format!("{}:{}: {}", csv_path.display(), line(&record), e))?;
+    }
+    Ok(())
+}
+
+/// The line of `record` in its file for messages.
+fn line(record: &csv::StringRecord) -> u64 {
+    record.position().map_or(0, |position| position.line())
+}
+
+/// Feeds every record of the analyzer outputs merged into one file to `f` with the parser of its
+/// output, which the first column names like `Conflict_EnvConflict` or `EnvConflict`. The layout
+/// is recognized per output unless forced.
+fn for_each_merged_record<F: FnMut(RecordParser, &Row) -> Result<(), String>>(
+    path: &std::path::Path,
+    layouts: &[CsvLayout],
+    forced: Option<&CsvLayout>,
+    mut f: F,
+) -> Result<(), String> {
+    let content =
+        std::fs::read(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let content = encoding::decode_csv(&content, path);
+    let mut rdr = csv_reader(&content);
+    let mut detected: std::collections::BTreeMap<&str, &CsvLayout> = Default::default();
+    let mut merged = csv::StringRecord::new();
+    while rdr
+        .read_record(&mut merged)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?
+    {
+        if merged.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let name = merged[0].trim().trim_end_matches(".csv");
+        let (file_name, parse_record) = CONFLICT_CSVS
+            .iter()
+            .find(|(file_name, _)| {
+                let csv = file_name.trim_end_matches(".csv");
+                csv == name || csv.strip_prefix("Conflict_") == Some(name)
+            })
+            .ok_or_else(|| {
+                let line = line(&merged);
+                format!(
+                    "{}:{}: unknown analyzer output {:?}",
+                    path.display(),
+                    line,
+                    name
+                )
+            })?;
+        let csv = file_name.trim_end_matches(".csv");
+        let record = merged.iter().skip(1).collect::<csv::StringRecord>();
+        let layout = match (forced, detected.get(csv).copied()) {
+            (Some(layout), _) | (None, Some(layout)) => layout,
+            (None, None) => {
+                let layout = CsvLayout::detect(layouts, csv, &record).ok_or_else(|| {
+                    format!(
+                        "unknown column layout of {} in {}, pick one with --csv-layout",
+                        csv,
+                        path.display()
+                    )
+                })?;
+                detected.insert(csv, layout);
+                layout
+            }
+        };
+        f(
+            *parse_record,
+            &Row {
+                record: &record,
+                columns: layout.columns(csv),
+            },
+        )
+        .map_err(|e| format!("{}:{}: {}", path.display(), line(&merged), e))?;
+    }
+    Ok(())
+}
+
+/// The optional columns after the slot: the number of slots a struct spans, then the byte offset
+/// and size of a variable packed into the slot, and after the access the id of the composite key
+/// the entry belongs to.
+fn parse_optional_column(record: &Row, column: Column) -> Result<Option<u32>, String> {
+    record
+        .get(column)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("invalid {:?} {:?}", column, value))
+        })
+        .transpose()
+}
+
+/// The optional note column after all others, free text like the source location of the entry.
+fn parse_note(record: &Row) -> Option<String> {
+    record
+        .get(Column::Note)
+        .filter(|note| !note.is_empty())
+        .map(String::from)
+}
+
+/// The optional access column, slots that are only read by two functions don't conflict.
+fn parse_access(record: &Row) -> Result<Option<Access>, String> {
+    let access = match record.get(Column::Access) {
+        Some(access) => access.to_ascii_lowercase(),
+        None => return Ok(None),
+    };
+    match access.as_str() {
+        "" => Ok(None),
+        "r" | "read" => Ok(Some(Access::Read)),
+        "w" | "write" => Ok(Some(Access::Write)),
+        access => Err(format!("invalid access {:?}", access)),
+    }
+}
+
+/// The selector of a record, hashed with the algorithm of the run from the canonical signature if
+/// the csv has signatures instead of selectors.
+fn parse_selector(record: &Row, context: &RecordContext) -> Result<u32, String> {
+    let selector = record.required(Column::Selector)?.trim();
+    if selector.ends_with(')') {
+        Ok(get_method_id(selector, context.gm))
+    } else {
+        context.selector_radix.parse(selector)
+    }
+}
+
+/// The slot of a record, a hex number the analyzer may write inside a description of the slot.
+fn parse_slot(record: &Row, context: &RecordContext) -> Result<u32, String> {
+    let slot = record.required(Column::Slot)?;
+    let hex = context
+        .slot_re
+        .find(slot)
+        .ok_or_else(|| format!("slot not found {:?}", slot))?;
+    u32::from_str_radix(hex.as_str().trim_start_matches("0x"), 16)
+        .map_err(|e| format!("invalid slot {:?}: {}", slot, e))
+}
+
+fn parse_env_record(record: &Row, context: &RecordContext) -> Result<ConflictInfo, String> {
+    // info!("env_csv {:?}, {} ", &record, record.len());
+    let selector = parse_selector(record, context)?;
+    let environment = record.required(Column::Value)?;
+    let value = match context.environments.code(environment) {
+        Some(code) => vec![code],
+        None => {
+            error!("Unknown environment type: {}", environment);
+            vec![EnvironmentType::Unknown as u32]
+        }
+    };
+    let slot = parse_slot(record, context)?;
+    Ok(ConflictInfo {
+        kind: ConflictType::Env,
+        selector,
+        slot: Some(slot),
+        len: parse_optional_column(record, Column::Len)?,
+        offset: parse_optional_column(record, Column::Offset)?,
+        size: parse_optional_column(record, Column::Size)?,
+        access: parse_access(record)?,
+        group: parse_optional_column(record, Column::Group)?,
+        value,
+        names: vec![],
+        note: parse_note(record),
+        function: record.get(Column::Function).map(String::from),
+    })
+}
+
+fn parse_all_record(record: &Row, context: &RecordContext) -> Result<ConflictInfo, String> {
+    let selector = parse_selector(record, context)?;
+    // let slot = u32::from_str_radix(
+    //     slot_re
+    //         .find(&record[2])
+    //         .unwrap()
+    //         .as_str()
+    //         .trim_start_matches("0x"),
+    //     16,
+    // )
+    // .unwrap();
+    Ok(ConflictInfo {
+        kind: ConflictType::All,
+        selector,
+        slot: None,
+        len: None,
+        offset: None,
+        size: None,
+        access: None,
+        group: None,
+        value: vec![],
+        names: vec![],
+        note: parse_note(record),
+        function: record.get(Column::Function).map(String::from),
+    })
+}
+
+/// The calldata indices forming the key, in order, more than one for nested mappings like
+/// `allowance[owner][spender]`, which the analyzer lists as `0,1`.
+fn parse_key_chain(keys: &str) -> Result<Vec<u32>, String> {
+    keys.trim_matches(|c| c == '[' || c == ']')
+        .split(|c| c == ',' || c == ';')
+        .map(|key| {
+            key.trim()
+                .parse()
+                .map_err(|_| format!("invalid key index {:?}", keys))
+        })
+        .collect()
+}
+
+fn parse_var_record(record: &Row, context: &RecordContext) -> Result<ConflictInfo, String> {
+    let selector = parse_selector(record, context)?;
+    let value = parse_key_chain(record.required(Column::Value)?)?;
+    let slot = parse_slot(record, context)?;
+    Ok(ConflictInfo {
+        kind: ConflictType::Var,
+        selector,
+        slot: Some(slot),
+        len: parse_optional_column(record, Column::Len)?,
+        offset: parse_optional_column(record, Column::Offset)?,
+        size: parse_optional_column(record, Column::Size)?,
+        access: parse_access(record)?,
+        group: parse_optional_column(record, Column::Group)?,
+        value,
+        names: vec![],
+        note: parse_note(record),
+        function: record.get(Column::Function).map(String::from),
+    })
+}
+
+fn parse_const_value(value: &str) -> Result<Vec<u32>, String> {
+    let mut value_hex = value.trim_start_matches("0x").to_string();
+    if value_hex.len() % 2 != 0 {
+        value_hex.insert(0, '0');
+    }
+    let bytes =
+        hex::decode(value_hex).map_err(|e| format!("invalid const value {:?}: {}", value, e))?;
+    Ok(bytes.into_iter().map(u32::from).collect())
+}
+
+fn parse_dynamic_const_record(
+    record: &Row,
+    context: &RecordContext,
+) -> Result<ConflictInfo, String> {
+    let selector = parse_selector(record, context)?;
+    let slot = parse_slot(record, context)?;
+    Ok(ConflictInfo {
+        kind: ConflictType::Const,
+        selector,
+        slot: Some(slot),
+        len: parse_optional_column(record, Column::Len)?,
+        offset: parse_optional_column(record, Column::Offset)?,
+        size: parse_optional_column(record, Column::Size)?,
+        access: parse_access(record)?,
+        group: parse_optional_column(record, Column::Group)?,
+        value: parse_const_value(record.required(Column::Value)?)?,
+        names: vec![],
+        note: parse_note(record),
+        function: record.get(Column::Function).map(String::from),
+    })
+}
+
+fn parse_basic_const_record(record: &Row, context: &RecordContext) -> Result<ConflictInfo, String> {
+    let selector = parse_selector(record, context)?;
+    Ok(ConflictInfo {
+        kind: ConflictType::Const,
+        selector,
+        slot: None,
+        len: None,
+        offset: None,
+        size: None,
+        access: None,
+        group: None,
+        value: parse_const_value(record.required(Column::Value)?)?,
+        names: vec![],
+        note: parse_note(record),
+        function: record.get(Column::Function).map(String::from),
+    })
+}
+
+fn parse_none_record(record: &Row, context: &RecordContext) -> Result<ConflictInfo, String> {
+    let selector = parse_selector(record, context)?;
+    Ok(ConflictInfo {
+        kind: ConflictType::None,
+        selector,
+        slot: None,
+        len: None,
+        offset: None,
+        size: None,
+        access: None,
+        group: None,
+        value: vec![],
+        names: vec![],
+        note: parse_note(record),
+        function: record.get(Column::Function).map(String::from),
+    })
+}
+
+/// What record parsers share.
+struct RecordContext<'a> {
+    slot_re: Regex,
+    environments: &'a Environments,
+    selector_radix: SelectorRadix,
+    gm: bool,
+}
+
+impl RecordContext<'_> {
+    fn new(environments: &Environments, selector_radix: SelectorRadix, gm: bool) -> RecordContext {
+        RecordContext {
+            slot_re: Regex::new(r"0x([\da-f]+)").unwrap(),
+            environments,
+            selector_radix,
+            gm,
+        }
+    }
+}
+
+type RecordParser = fn(&Row, &RecordContext) -> Result<ConflictInfo, String>;
+
+/// The analyzer outputs and how a record of each of them is turned into a conflict.
+const CONFLICT_CSVS: [(&str, RecordParser); 7] = [
+    ("Conflict_EnvConflict.csv", parse_env_record),
+    ("Conflict_MixConflict.csv", parse_all_record),
+    (
+        "Conflict_NoStorageAccessHasContractCalling.csv",
+        parse_all_record,
+    ),
+    ("Conflict_FunArgConflict.csv", parse_var_record),
+    (
+        "Conflict_DynaVarConsConflict.csv",
+        parse_dynamic_const_record,
+    ),
+    (
+        "Conflict_BasicVarConsConflict.csv",
+        parse_basic_const_record,
+    ),
+    ("Conflict_NoConflict.csv", parse_none_record),
+];
+
+/// The analyzer csvs in the directory at `path`, missing ones are taken as empty.
+pub fn conflict_csvs(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    CONFLICT_CSVS
+        .iter()
+        .map(|(file_name, _)| path.join(file_name))
+        .filter(|csv_path| csv_path.exists())
+        .collect()
+}
+
+/// The conflicts of the analyzer csvs in the directory at `path`.
+pub fn parse_conflict_info(
+    path: &std::path::Path,
+    options: &CsvOptions,
+) -> Result<Vec<ConflictInfo>, String> {
+    let context = RecordContext::new(options.environments, options.selector_radix, options.gm);
+    let only = options.only;
+    // the csv files are independent, parse each of them on its own thread
+    let mut result: Vec<ConflictInfo> = CONFLICT_CSVS
+        .par_iter()
+        .map(|(file_name, parse_record)| {
+            let mut conflicts = Vec::new();
+            let csv_path = path.join(file_name);
+            if !csv_path.exists() {
+                match options.missing {
+                    MissingCsv::Error => return Err(format!("{} is missing", csv_path.display())),
+                    // reported by the source as a warning
+                    MissingCsv::Warn | MissingCsv::Ignore => return Ok(Vec::new()),
+                }
+            }
+            for_each_record(&csv_path, options.layouts, options.layout, |record| {
+                let conflict = parse_record(record, &context)?;
+                if only.map_or(true, |only| only.contains(&conflict.selector)) {
+                    conflicts.push(conflict);
+                }
+                Ok(())
+            })?;
+            Ok(conflicts)
+        })
+        .collect::<Result<Vec<Vec<ConflictInfo>>, String>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+    info!("parse conflicts completed");
+    result.sort();
+    result.dedup();
+    Ok(result)
+}
+
+/// The conflicts of the analyzer outputs merged into the file at `path`.
+pub fn parse_merged_conflict_info(
+    path: &std::path::Path,
+    options: &CsvOptions,
+) -> Result<Vec<ConflictInfo>, String> {
+    let context = RecordContext::new(options.environments, options.selector_radix, options.gm);
+    let only = options.only;
+    let mut result = Vec::new();
+    for_each_merged_record(
+        path,
+        options.layouts,
+        options.layout,
+        |parse_record, record| {
+            let conflict = parse_record(record, &context)?;
+            if only.map_or(true, |only| only.contains(&conflict.selector)) {
+                result.push(conflict);
+            }
+            Ok(())
+        },
+    )?;
+    result.sort();
+    result.dedup();
+    Ok(result)
+}
+
+#[cfg(not(feature = "ethabi"))]
+fn parse_ty(ty_info: &AbiParam) -> String {
+    const TUPLE_TY: &str = "tuple";
+
+    let ty = ty_info.ty.as_str();
+    if let Some(suffix) = ty.strip_prefix(TUPLE_TY) {
+        let component_types = ty_info
+            .components()
+            .iter()
+            .map(parse_ty)
+            .collect::<Vec<String>>()
+            .join(",");
+        format!("({}){}", component_types, suffix)
+    } else {
+        String::from(ty)
+    }
+}
+
+/// lets ethabi work out the canonical type, it handles every nesting of tuples and arrays
+#[cfg(feature = "ethabi")]
+fn parse_ty(ty_info: &AbiParam) -> String {
+    let param: ethabi::Param = serde_json::from_value(serde_json::to_value(ty_info).unwrap())
+        .unwrap_or_else(|e| panic!("invalid abi param {}: {}", ty_info.name, e));
+    ethabi::param_type::Writer::write(&param.kind)
+}
+
+pub fn get_method_signature(method: &AbiEntry) -> String {
+    let fn_name = method.name.as_deref().unwrap_or_default();
+    let sig = method
+        .inputs()
+        .iter()
+        .map(parse_ty)
+        .collect::<Vec<String>>()
+        .join(",");
+    format!("{}({})", fn_name, sig)
+}
+
+/// The contract an abi is of, named after the file without `.abi` or `.abi.json`.
+pub fn contract_name(abi: &std::path::Path) -> String {
+    let stem = abi.file_stem().unwrap().to_string_lossy();
+    stem.trim_end_matches(".abi").to_string()
+}
+
+/// The selector of `signature`, hashed with SM3 if `gm`.
+pub fn get_method_id(signature: &str, gm: bool) -> u32 {
+    hash::hasher(gm).selector(signature)
+}
+
+/// A selector given as `0x` and hex digits, or as the signature it is hashed from.
+pub fn selector_of(selector: &str, gm: bool) -> Result<u32, String> {
+    match selector.strip_prefix("0x") {
+        Some(digits) if !selector.contains('(') => u32::from_str_radix(digits, 16)
+            .map_err(|e| format!("invalid selector {}: {}", selector, e)),
+        _ => Ok(get_method_id(selector, gm)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_chain() {
+        assert_eq!(parse_key_chain("2"), Ok(vec![2]));
+        assert_eq!(parse_key_chain("0,1"), Ok(vec![0, 1]));
+        assert_eq!(parse_key_chain("[1; 0]"), Ok(vec![1, 0]));
+        assert!(parse_key_chain("owner").is_err());
+    }
+
+    #[test]
+    fn test_missing_csv() {
+        let dir = std::env::temp_dir().join("test_missing_csv");
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file_name, _) in CONFLICT_CSVS.iter() {
+            let _ = std::fs::remove_file(dir.join(file_name));
+        }
+        let csv = dir.join("Conflict_NoConflict.csv");
+        std::fs::write(&csv, "0xa9059cbb\n").unwrap();
+        assert_eq!(conflict_csvs(&dir), vec![csv]);
+        let layouts = CsvLayout::builtin();
+        let environments = Environments::default();
+        let options = |missing| CsvOptions {
+            layouts: &layouts,
+            layout: None,
+            only: None,
+            environments: &environments,
+            selector_radix: SelectorRadix::Auto,
+            gm: false,
+            missing,
+        };
+        assert!(parse_conflict_info(&dir, &options(MissingCsv::Error)).is_err());
+        for missing in [MissingCsv::Warn, MissingCsv::Ignore].iter() {
+            assert_eq!(
+                parse_conflict_info(&dir, &options(*missing)),
+                Ok(vec![ConflictInfo::new(ConflictType::None, 0xa9059cbb)])
+            );
+        }
+        let warnings = source::missing_csv_warnings(&dir, MissingCsv::Warn);
+        assert_eq!(warnings.len(), CONFLICT_CSVS.len() - 1);
+        assert_eq!(warnings[0].rule, "missing-csv");
+        assert!(source::missing_csv_warnings(&dir, MissingCsv::Ignore).is_empty());
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        let environments = Environments::default();
+        let context = RecordContext::new(&environments, SelectorRadix::Auto, false);
+        let layouts = CsvLayout::builtin();
+        let columns = layouts[0].columns("Conflict_EnvConflict");
+        for selector in &["0xa9059cbb", "2835717307", "transfer(address,uint256)"] {
+            let record = csv::StringRecord::from(vec!["Token", selector, "CALLER", "0x0"]);
+            let row = Row {
+                record: &record,
+                columns,
+            };
+            assert_eq!(parse_selector(&row, &context), Ok(0xa9059cbb));
+        }
+    }
+
+    static ABI_STR: &'static str = r#"[
+        {
+            "inputs": [],
+            "stateMutability": "nonpayable",
+            "type": "constructor"
+        },
+        {
+            "inputs": [],
+            "name": "StrRead",
+            "outputs": [
+                {
+                    "internalType": "string",
+                    "name": "",
+                    "type": "string"
+                }
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "StrWrite",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "name": "complexMap",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "a",
+                    "type": "uint256"
+                },
+                {
+                    "internalType": "uint256",
+                    "name": "b",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "view",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "complexMapReadWithConsKey",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "complexMapWriteWithConsKey",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "name": "dynaArray",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "view",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "dynaArrayReadWithConsKey",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "i",
+                    "type": "uint256"
+                }
+            ],
+            "name": "dynaArrayReadWithFuncArgKey",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "dynaArrayWriteWithConsKey",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "i",
+                    "type": "uint256"
+                }
+            ],
+            "name": "dynaArrayWriteWithFuncArgKey",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "fixedSizeVar",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "view",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "fixedSizeVarRead",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "fixedSizeVarWrite",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "x",
+                    "type": "uint256"
+                }
+            ],
+            "name": "noStorageAccessAndContractCalling",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "address",
+                    "name": "addr",
+                    "type": "address"
+                }
+            ],
+            "name": "noStorageAccessHasContractCalling",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "name": "simpleMap",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "view",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "address",
+                    "name": "",
+                    "type": "address"
+                }
+            ],
+            "name": "simpleMapForTestEnv",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "view",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "simpleMapIndirectWriteWithEnvKey",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "simpleMapReadWithConsKey",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "x",
+                    "type": "uint256"
+                }
+            ],
+            "name": "simpleMapReadWithEnvKey",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "i",
+                    "type": "uint256"
+                }
+            ],
+            "name": "simpleMapReadWithFuncArgKey",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "simpleMapWriteWithConsKey",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "simpleMapWriteWithEnvKey",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "i",
+                    "type": "uint256"
+                }
+            ],
+            "name": "simpleMapWriteWithFuncArgKey",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "x",
+                    "type": "uint256"
+                }
+            ],
+            "name": "simpleMapWriteWithMixKey",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "components": [
+                        {
+                            "internalType": "uint256",
+                            "name": "a",
+                            "type": "uint256"
+                        },
+                        {
+                            "internalType": "uint256",
+                            "name": "b",
+                            "type": "uint256"
+                        }
+                    ],
+                    "internalType": "struct S",
+                    "name": "s",
+                    "type": "tuple"
+                }
+            ],
+            "name": "simpleMapWriteWithPartStructFuncArgKey",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "x",
+                    "type": "uint256"
+                },
+                {
+                    "components": [
+                        {
+                            "internalType": "uint256",
+                            "name": "a",
+                            "type": "uint256"
+                        },
+                        {
+                            "internalType": "uint256",
+                            "name": "b",
+                            "type": "uint256"
+                        }
+                    ],
+                    "internalType": "struct S",
+                    "name": "s",
+                    "type": "tuple"
+                }
+            ],
+            "name": "simpleMapWriteWithPartStructFuncArgKey2",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "a",
+                    "type": "uint256"
+                },
+                {
+                    "components": [
+                        {
+                            "internalType": "uint256",
+                            "name": "a",
+                            "type": "uint256"
+                        },
+                        {
+                            "components": [
+                                {
+                                    "internalType": "string",
+                                    "name": "a",
+                                    "type": "string"
+                                },
+                                {
+                                    "components": [
+                                        {
+                                            "internalType": "uint256",
+                                            "name": "a",
+                                            "type": "uint256"
+                                        },
+                                        {
+                                            "internalType": "uint256",
+                                            "name": "b",
+                                            "type": "uint256"
+                                        }
+                                    ],
+                                    "internalType": "struct S",
+                                    "name": "b",
+                                    "type": "tuple"
+                                },
+                                {
+                                    "internalType": "string",
+                                    "name": "c",
+                                    "type": "string"
+                                }
+                            ],
+                            "internalType": "struct SS",
+                            "name": "b",
+                            "type": "tuple"
+                        },
+                        {
+                            "internalType": "uint256",
+                            "name": "c",
+                            "type": "uint256"
+                        }
+                    ],
+                    "internalType": "struct SSS",
+                    "name": "sss",
+                    "type": "tuple"
+                },
+                {
+                    "internalType": "uint256",
+                    "name": "c",
+                    "type": "uint256"
+                }
+            ],
+            "name": "simpleMapWriteWithPartStructFuncArgKey3",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "x",
+                    "type": "uint256"
+                },
+                {
+                    "components": [
+                        {
+                            "internalType": "string",
+                            "name": "a",
+                            "type": "string"
+                        },
+                        {
+                            "components": [
+                                {
+                                    "internalType": "uint256",
+                                    "name": "a",
+                                    "type": "uint256"
+                                },
+                                {
+                                    "internalType": "uint256",
+                                    "name": "b",
+                                    "type": "uint256"
+                                }
+                            ],
+                            "internalType": "struct S",
+                            "name": "b",
+                            "type": "tuple"
+                        },
+                        {
+                            "internalType": "string",
+                            "name": "c",
+                            "type": "string"
+                        }
+                    ],
+                    "internalType": "struct SS",
+                    "name": "ss",
+                    "type": "tuple"
+                }
+            ],
+            "name": "simpleMapWriteWithPartStructFuncArgKey4",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "a",
+                    "type": "uint256"
+                },
+                {
+                    "internalType": "uint256[]",
+                    "name": "b",
+                    "type": "uint256[]"
+                },
+                {
+                    "internalType": "uint256",
+                    "name": "c",
+                    "type": "uint256"
+                }
+            ],
+            "name": "simpleMapWriteWithPartStructFuncArgKey5",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "a",
+                    "type": "uint256"
+                },
+                {
+                    "internalType": "uint256[5]",
+                    "name": "b",
+                    "type": "uint256[5]"
+                },
+                {
+                    "internalType": "uint256",
+                    "name": "c",
+                    "type": "uint256"
+                }
+            ],
+            "name": "simpleMapWriteWithPartStructFuncArgKey6",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "simpleStruct",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "a",
+                    "type": "uint256"
+                },
+                {
+                    "internalType": "uint256",
+                    "name": "b",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "view",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "simpleStructRead",
+            "outputs": [
+                {
+                    "internalType": "uint256",
+                    "name": "",
+                    "type": "uint256"
+                }
+            ],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "simpleStructWrite",
+            "outputs": [],
+            "stateMutability": "nonpayable",
+            "type": "function"
+        },
+        {
+            "inputs": [],
+            "name": "str",
+            "outputs": [
+                {
+                    "internalType": "string",
+                    "name": "",
+                    "type": "string"
+                }
+            ],
+            "stateMutability": "view",
+            "type": "function"
+        }
+    ]"#;
+
+    #[test]
+    fn test_simple_selector() {
+        let origin_abi: Vec<AbiEntry> = serde_json::from_str(ABI_STR).unwrap();
+        let functions = origin_abi
+            .iter()
+            .filter(|v| v.is_function())
+            .collect::<Vec<&AbiEntry>>();
+
+        let str_read = functions
+            .iter()
+            .find(|v| v.name.as_deref() == Some("StrRead"))
+            .unwrap();
+        let str_read_sig = get_method_signature(str_read);
+        assert_eq!(str_read_sig, "StrRead()".to_string());
+        let str_read_selector = get_method_id(&str_read_sig, false);
+        assert_eq!(
+            str_read_selector,
+            u32::from_str_radix("4db0cdb2", 16).unwrap()
+        );
+
+        let str_write = functions
+            .iter()
+            .find(|v| v.name.as_deref() == Some("StrWrite"))
+            .unwrap();
+        let str_write_sig = get_method_signature(str_write);
+        assert_eq!(str_write_sig, "StrWrite()".to_string());
+        let str_write_selector = get_method_id(&str_write_sig, false);
+        assert_eq!(
+            str_write_selector,
+            u32::from_str_radix("7ea33fd1", 16).unwrap()
+        );
+
+        let complex_map = functions
+            .iter()
+            .find(|v| v.name.as_deref() == Some("complexMap"))
+            .unwrap();
+        let complex_map_sig = get_method_signature(complex_map);
+        assert_eq!(complex_map_sig, "complexMap(uint256)".to_string());
+        let complex_map_selector = get_method_id(&complex_map_sig, false);
+        assert_eq!(
+            complex_map_selector,
+            u32::from_str_radix("3e49c8f4", 16).unwrap()
+        );
+        let simple_map_write_with_part_struct_func_arg_key = functions
+            .iter()
+            .find(|v| v.name.as_deref() == Some("simpleMapWriteWithPartStructFuncArgKey"))
+            .unwrap();
+        let simple_map_write_with_part_struct_func_arg_key_sig =
+            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_sig,
+            "simpleMapWriteWithPartStructFuncArgKey((uint256,uint256))".to_string()
+        );
+        let simple_map_write_with_part_struct_func_arg_key_selector =
+            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_selector,
+            u32::from_str_radix("c158e6ef", 16).unwrap()
+        );
+        let simple_map_write_with_part_struct_func_arg_key = functions
+            .iter()
+            .find(|v| v.name.as_deref() == Some("simpleMapWriteWithPartStructFuncArgKey2"))
+            .unwrap();
+        let simple_map_write_with_part_struct_func_arg_key_sig =
+            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_sig,
+            "simpleMapWriteWithPartStructFuncArgKey2(uint256,(uint256,uint256))".to_string()
+        );
+        let simple_map_write_with_part_struct_func_arg_key_selector =
+            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_selector,
+            u32::from_str_radix("e407048d", 16).unwrap()
+        );
+        let simple_map_write_with_part_struct_func_arg_key = functions
+            .iter()
+            .find(|v| v.name.as_deref() == Some("simpleMapWriteWithPartStructFuncArgKey3"))
+            .unwrap();
+        let simple_map_write_with_part_struct_func_arg_key_sig =
+            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_sig,
+            "simpleMapWriteWithPartStructFuncArgKey3(uint256,(uint256,(string,(uint256,uint256),string),uint256),uint256)"
+                .to_string()
+        );
+        let simple_map_write_with_part_struct_func_arg_key_selector =
+            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_selector,
+            u32::from_str_radix("3894de57", 16).unwrap()
+        );
+
+        let simple_map_write_with_part_struct_func_arg_key = functions
+            .iter()
+            .find(|v| v.name.as_deref() == Some("simpleMapWriteWithPartStructFuncArgKey4"))
+            .unwrap();
+        let simple_map_write_with_part_struct_func_arg_key_sig =
+            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_sig,
+            "simpleMapWriteWithPartStructFuncArgKey4(uint256,(string,(uint256,uint256),string))"
+                .to_string()
+        );
+        let simple_map_write_with_part_struct_func_arg_key_selector =
+            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_selector,
+            u32::from_str_radix("9f440047", 16).unwrap()
+        );
+
+        let simple_map_write_with_part_struct_func_arg_key = functions
+            .iter()
+            .find(|v| v.name.as_deref() == Some("simpleMapWriteWithPartStructFuncArgKey5"))
+            .unwrap();
+        let simple_map_write_with_part_struct_func_arg_key_sig =
+            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_sig,
+            "simpleMapWriteWithPartStructFuncArgKey5(uint256,uint256[],uint256)".to_string()
+        );
+        let simple_map_write_with_part_struct_func_arg_key_selector =
+            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_selector,
+            u32::from_str_radix("5a0495fd", 16).unwrap()
+        );
+
+        let simple_map_write_with_part_struct_func_arg_key = functions
+            .iter()
+            .find(|v| v.name.as_deref() == Some("simpleMapWriteWithPartStructFuncArgKey6"))
+            .unwrap();
+        let simple_map_write_with_part_struct_func_arg_key_sig =
+            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_sig,
+            "simpleMapWriteWithPartStructFuncArgKey6(uint256,uint256[5],uint256)".to_string()
+        );
+        let simple_map_write_with_part_struct_func_arg_key_selector =
+            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
+        assert_eq!(
+            simple_map_write_with_part_struct_func_arg_key_selector,
+            u32::from_str_radix("d07c919a", 16).unwrap()
+        );
+    }
+}
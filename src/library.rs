@@ -0,0 +1,174 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::abi::AbiEntry;
+use crate::conflict::ConflictInfo;
+use crate::slots::find_storage_layout;
+
+/// A state variable of the storage layout of the contract calling a library.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Variable {
+    pub(crate) label: String,
+    pub(crate) slot: u32,
+    /// number of slots taken in place, one for mappings and dynamic arrays
+    pub(crate) slots: u32,
+    /// the type as library abis write storage parameters, `Set.Data` for `struct Set.Data`
+    pub(crate) ty: String,
+}
+
+/// The storage layout of the contract a library is called from, the slots of library functions
+/// are of the caller's storage they run on by delegatecall.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CallerLayout {
+    pub(crate) variables: Vec<Variable>,
+}
+
+impl CallerLayout {
+    /// The layout of the storageLayout solc writes, alone or in a combined or standard json output.
+    pub fn load(path: &Path) -> CallerLayout {
+        let content = std::fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("could not read file {}", path.display()));
+        let output: Value = serde_json::from_str(&content)
+            .unwrap_or_else(|e| panic!("invalid storage layout {}: {}", path.display(), e));
+        let layout = find_storage_layout(&output)
+            .unwrap_or_else(|| panic!("no storage layout in {}", path.display()));
+        CallerLayout::of(layout)
+    }
+
+    fn of(layout: &Value) -> CallerLayout {
+        let number = |value: &Value| value.as_str().and_then(|number| number.parse::<u32>().ok());
+        let variables = layout["storage"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|variable| {
+                let ty = &layout["types"][variable["type"].as_str()?];
+                let bytes = number(&ty["numberOfBytes"]).unwrap_or(32);
+                let label = ty["label"].as_str().unwrap_or_default();
+                Some(Variable {
+                    label: variable["label"].as_str()?.to_string(),
+                    slot: number(&variable["slot"])?,
+                    slots: ((bytes + 31) / 32).max(1),
+                    ty: label.strip_prefix("struct ").unwrap_or(label).to_string(),
+                })
+            })
+            .collect();
+        CallerLayout { variables }
+    }
+
+    /// Rebases the slots of the `conflicts` of the library function `method` onto the caller's
+    /// layout: relative to the variable its storage parameter points at, which must be the only
+    /// one of its type, or checked to be of a variable without storage parameter.
+    pub(crate) fn rebase(
+        &self,
+        method: &AbiEntry,
+        conflicts: &mut [ConflictInfo],
+    ) -> Result<(), String> {
+        let storage = method
+            .inputs()
+            .iter()
+            .filter_map(|param| param.ty.strip_suffix(" storage"))
+            .collect::<Vec<&str>>();
+        match storage.as_slice() {
+            [] => {
+                for conflict in conflicts
+                    .iter_mut()
+                    .filter(|conflict| conflict.slot.is_some())
+                {
+                    let slot = conflict.slot.unwrap();
+                    if !self.variables.iter().any(|variable| {
+                        (variable.slot..variable.slot + variable.slots).contains(&slot)
+                    }) {
+                        return Err(format!("slot {} is no variable of the caller layout", slot));
+                    }
+                }
+                Ok(())
+            }
+            [ty] => {
+                let candidates = self
+                    .variables
+                    .iter()
+                    .filter(|variable| variable.ty == *ty)
+                    .collect::<Vec<&Variable>>();
+                let variable = match candidates.as_slice() {
+                    [variable] => variable,
+                    [] => return Err(format!("the caller layout has no variable of type {}", ty)),
+                    _ => {
+                        let labels = candidates
+                            .iter()
+                            .map(|variable| variable.label.as_str())
+                            .collect::<Vec<&str>>();
+                        return Err(format!(
+                            "the caller layout has several variables of type {}: {}",
+                            ty,
+                            labels.join(", ")
+                        ));
+                    }
+                };
+                for conflict in conflicts
+                    .iter_mut()
+                    .filter(|conflict| conflict.slot.is_some())
+                {
+                    let slot = conflict.slot.unwrap();
+                    if slot + conflict.len.unwrap_or(1).max(1) > variable.slots {
+                        return Err(format!(
+                            "slot {} is past the {} slots of {}",
+                            slot, variable.slots, variable.label
+                        ));
+                    }
+                    conflict.slot = Some(variable.slot + slot);
+                }
+                Ok(())
+            }
+            _ if conflicts.iter().all(|conflict| conflict.slot.is_none()) => Ok(()),
+            _ => Err(String::from(
+                "several storage parameters, the slots may be relative to any of them",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::ConflictType;
+
+    #[test]
+    fn test_rebase() {
+        let layout = CallerLayout::of(&serde_json::json!({
+            "storage": [
+                {"label": "owner", "offset": 0, "slot": "0", "type": "t_address"},
+                {"label": "set", "offset": 0, "slot": "1", "type": "t_struct(Data)3_storage"}
+            ],
+            "types": {
+                "t_address": {"label": "address", "numberOfBytes": "20"},
+                "t_struct(Data)3_storage": {"label": "struct Set.Data", "numberOfBytes": "64"}
+            }
+        }));
+        let conflict = |slot: u32| {
+            let mut conflict = ConflictInfo::new(ConflictType::Var, 0);
+            conflict.slot = Some(slot);
+            conflict
+        };
+        let add: AbiEntry = serde_json::from_value(serde_json::json!({
+            "inputs": [
+                {"name": "self", "type": "Set.Data storage"},
+                {"name": "value", "type": "uint256"}
+            ],
+            "name": "add",
+            "type": "function"
+        }))
+        .unwrap();
+        let mut conflicts = vec![conflict(1)];
+        layout.rebase(&add, &mut conflicts).unwrap();
+        assert_eq!(conflicts[0].slot, Some(2));
+        assert!(layout.rebase(&add, &mut [conflict(2)]).is_err());
+        let owner: AbiEntry = serde_json::from_value(
+            serde_json::json!({"inputs": [], "name": "f", "type": "function"}),
+        )
+        .unwrap();
+        assert!(layout.rebase(&owner, &mut [conflict(2)]).is_ok());
+        assert!(layout.rebase(&owner, &mut [conflict(3)]).is_err());
+    }
+}
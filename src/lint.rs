@@ -0,0 +1,239 @@
+use std::fmt;
+use std::path::Path;
+
+use colored::Colorize;
+use serde::Deserialize;
+
+use crate::abi::AbiEntry;
+use crate::conflict::{ConflictInfo, Environments, CONFLICT_TYPES};
+use crate::get_method_signature;
+
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+}
+
+impl Default for Severity {
+    fn default() -> Self {
+        Severity::Error
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "{}", "error".red()),
+            Severity::Warning => write!(f, "{}", "warning".yellow()),
+        }
+    }
+}
+
+/// A `[[lint]]` of the config file. The functions it applies to are picked by `functions`, a
+/// name pattern where `*` matches anything, and `state_mutability`. Conflicts are matched by
+/// kind, like `All`, or by `Env:` and an environment name, like `Env:CALLER`.
+#[derive(Debug, Deserialize)]
+pub struct LintRule {
+    pub(crate) code: String,
+    #[serde(default)]
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+    #[serde(default)]
+    pub(crate) functions: Option<String>,
+    #[serde(default)]
+    pub(crate) state_mutability: Option<String>,
+    /// a conflict every function must carry
+    #[serde(default)]
+    pub(crate) require: Option<String>,
+    /// a conflict no function may carry
+    #[serde(default)]
+    pub(crate) forbid: Option<String>,
+    #[serde(default)]
+    pub(crate) max_entries: Option<usize>,
+}
+
+/// A function breaking a lint rule.
+pub(crate) struct LintViolation<'a> {
+    pub(crate) rule: &'a LintRule,
+    pub(crate) signature: String,
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any run of characters.
+pub(crate) fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let name = match name.strip_prefix(prefix) {
+                Some(name) => name,
+                None => return false,
+            };
+            (0..=name.len())
+                .filter(|start| name.is_char_boundary(*start))
+                .any(|start| matches_pattern(rest, &name[start..]))
+        }
+    }
+}
+
+fn matches_conflict(pattern: &str, conflict: &ConflictInfo, environments: &Environments) -> bool {
+    let (kind, environment) = match pattern.split_once(':') {
+        Some((kind, environment)) => (kind, Some(environment)),
+        None => (pattern, None),
+    };
+    let kind = CONFLICT_TYPES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(kind))
+        .map(|(_, kind)| kind)
+        .unwrap_or_else(|| panic!("unknown conflict kind in lint pattern {}", pattern));
+    if conflict.kind != *kind {
+        return false;
+    }
+    match environment {
+        Some(environment) => {
+            let code = environments
+                .code(environment)
+                .unwrap_or_else(|| panic!("unknown environment in lint pattern {}", pattern));
+            conflict.value == [code]
+        }
+        None => true,
+    }
+}
+
+impl LintRule {
+    fn applies_to(&self, entry: &AbiEntry) -> bool {
+        let name = entry.name.as_deref().unwrap_or_default();
+        self.functions
+            .as_deref()
+            .map_or(true, |pattern| matches_pattern(pattern, name))
+            && self
+                .state_mutability
+                .as_deref()
+                .map_or(true, |state_mutability| {
+                    entry.state_mutability.as_deref() == Some(state_mutability)
+                })
+    }
+
+    fn is_broken_by(&self, conflicts: &[ConflictInfo], environments: &Environments) -> bool {
+        let has = |pattern: &str| {
+            conflicts
+                .iter()
+                .any(|conflict| matches_conflict(pattern, conflict, environments))
+        };
+        self.require
+            .as_deref()
+            .map_or(false, |pattern| !has(pattern))
+            || self.forbid.as_deref().map_or(false, has)
+            || self
+                .max_entries
+                .map_or(false, |max_entries| conflicts.len() > max_entries)
+    }
+}
+
+pub(crate) fn lint_entries<'a>(
+    entries: &[AbiEntry],
+    rules: &'a [LintRule],
+    environments: &Environments,
+) -> Vec<LintViolation<'a>> {
+    let mut violations = Vec::new();
+    for entry in entries.iter().filter(|entry| entry.is_function()) {
+        let signature = get_method_signature(entry);
+        let conflicts: Vec<ConflictInfo> = match entry.extra.get("conflictFields") {
+            Some(fields) => serde_json::from_value(fields.clone())
+                .unwrap_or_else(|e| panic!("invalid conflictFields of {}: {}", signature, e)),
+            None => Vec::new(),
+        };
+        for rule in rules {
+            if rule.applies_to(entry) && rule.is_broken_by(&conflicts, environments) {
+                violations.push(LintViolation {
+                    rule,
+                    signature: signature.clone(),
+                });
+            }
+        }
+    }
+    violations
+}
+
+/// Checks the annotation of `abi` against the lint rules of the config, prints the violations
+/// and returns whether none of them is an error.
+pub fn lint(abi: &Path, rules: &[LintRule], environments: &Environments) -> bool {
+    let abi_content = std::fs::read_to_string(abi)
+        .unwrap_or_else(|_| panic!("could not read file {}", abi.display()));
+    let entries: Vec<AbiEntry> = serde_json::from_str(&abi_content).unwrap();
+    let violations = lint_entries(&entries, rules, environments);
+    for violation in &violations {
+        println!(
+            "{} {} {}: {}",
+            violation.rule.severity,
+            violation.rule.code,
+            violation.signature.yellow(),
+            violation.rule.message
+        );
+    }
+    let errors = violations
+        .iter()
+        .filter(|violation| violation.rule.severity == Severity::Error)
+        .count();
+    print!(
+        "{} lint errors, {} warnings",
+        errors,
+        violations.len() - errors
+    );
+    errors == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_entries() {
+        let config: crate::config::Config = toml::from_str(
+            r#"
+            [[lint]]
+            code = "L001"
+            message = "payable functions must include an Env CALLER entry"
+            state_mutability = "payable"
+            require = "Env:CALLER"
+
+            [[lint]]
+            code = "L002"
+            severity = "warning"
+            message = "All is forbidden for transfers"
+            functions = "transfer*"
+            forbid = "all"
+
+            [[lint]]
+            code = "L003"
+            message = "too many entries"
+            max_entries = 1
+            "#,
+        )
+        .unwrap();
+        let entries: Vec<AbiEntry> = serde_json::from_str(
+            r#"[
+                {"name": "deposit", "type": "function", "stateMutability": "payable",
+                 "conflictFields": [{"kind": 2, "value": [1]}]},
+                {"name": "transferFrom", "type": "function",
+                 "conflictFields": [{"kind": 0}, {"kind": 2, "value": [0]}]},
+                {"name": "pay", "type": "function", "stateMutability": "payable",
+                 "conflictFields": [{"kind": 2, "value": [0]}]}
+            ]"#,
+        )
+        .unwrap();
+        let violations = lint_entries(&entries, &config.lints, &config.environments())
+            .iter()
+            .map(|violation| format!("{} {}", violation.rule.code, violation.signature))
+            .collect::<Vec<String>>();
+        assert_eq!(
+            violations,
+            vec![
+                "L001 deposit()",
+                "L002 transferFrom()",
+                "L003 transferFrom()"
+            ]
+        );
+        assert!(matches_pattern("*From", "transferFrom"));
+        assert!(!matches_pattern("transfer*x", "transferFrom"));
+    }
+}
@@ -1,1139 +1,1117 @@
-use std::convert::TryInto;
-
 use colored::Colorize;
 use env_logger::Env;
-use log::{error, info};
-use regex::Regex;
-use serde::Serialize;
-use serde_json::{Map, Value};
-use serde_repr::Serialize_repr;
-use sha3::Digest;
+use log::error;
+use rayon::prelude::*;
 use structopt::StructOpt;
 
+use conflicts_info_parse::{
+    abi, annotate, builtin, cache, config, conflict, conflict_csvs, contract_name, devdoc, diamond,
+    diff, doctor, emit, explain, export, get_method_signature, git, gosdk, hash, hashtable, init,
+    layout, library, lint, manifest, merge, migrate, options, patch, profile, provenance, proxy,
+    query, report, review, sarif, schema, script, selector_of, signing, sink, slots, source, strip,
+    system, validate,
+};
+
+use abi::AbiEntry;
+use annotate::CapPolicy;
+use conflict::{ConflictType, SlotFormat, CONFLICT_TYPES};
+use diff::ColorChoice;
+use emit::Lang;
+use hashtable::TableFormat;
+use layout::SelectorRadix;
+use merge::MergePolicy;
+use provenance::ProvenanceMode;
+use sink::{OutputFormat, SinkSpec};
+use source::{ConflictSource, CsvOptions, MissingCsv};
+
 #[derive(StructOpt)]
+#[structopt(setting = structopt::clap::AppSettings::SubcommandsNegateReqs)]
 struct Cli {
+    #[structopt(subcommand)]
+    command: Option<Command>,
     /// The path of the abi json file
     #[structopt(parse(from_os_str))]
-    #[structopt(short, long)]
-    abi: std::path::PathBuf,
-    /// The path to the file to read
+    #[structopt(short, long, required = true)]
+    abi: Option<std::path::PathBuf>,
+    /// The directory of the analyzer csvs, or a file of them merged with the csv name as first
+    /// column. `{contract}` is replaced by the name of the abi file, like `analysis/{contract}/`
     #[structopt(parse(from_os_str))]
-    #[structopt(short, long)]
-    path: std::path::PathBuf,
+    #[structopt(
+        short,
+        long,
+        required_unless_one = &["devdoc", "conflicts-json", "builtin"]
+    )]
+    path: Option<std::path::PathBuf>,
+    /// A devdoc of solc declaring conflicts in `@custom:conflict slot=0x3 key=arg0` tags, merged
+    /// with the csvs or used instead of them
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    devdoc: Vec<std::path::PathBuf>,
+    /// A json array of conflictFields entries with the selector of their function
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    conflicts_json: Vec<std::path::PathBuf>,
+    /// The shipped conflicts of a FISCO BCOS precompiled contract, for annotating its abi without
+    /// the analyzer: table-factory, crud, cns, consensus, system-config, permission or
+    /// parallel-config
+    #[structopt(long)]
+    builtin: Vec<builtin::Builtin>,
+    /// A json object of hand written conflictFields by signature or selector, replacing the
+    /// conflicts found for these functions, the report notes the superseded ones
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    overrides: Option<std::path::PathBuf>,
+    /// Force the column layout of the csvs instead of recognizing it: v1, v2 which has the
+    /// function name first, or one defined in the config file
+    #[structopt(long, alias = "csv-format")]
+    csv_layout: Option<String>,
+    /// The path of the config file, conflicts_info_parse.toml in the working directory if present
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    config: Option<std::path::PathBuf>,
     /// Indicates using GM mode or not.
     #[structopt(short, long)]
     gm: bool,
+    /// How slots are written: decimal, hex or padded (32 bytes hex)
+    #[structopt(long, default_value = "decimal", possible_values = &["decimal", "hex", "padded"])]
+    slot_format: SlotFormat,
+    /// How the csvs write selectors: hex, decimal or auto, where a 0x prefix means hex and only
+    /// digits decimal, but for eight digits taken as hex with a warning.
+    #[structopt(long, default_value = "auto", possible_values = &["auto", "hex", "decimal"])]
+    selector_radix: SelectorRadix,
+    /// What to do about analyzer csvs missing from the directory: fail, or take them as empty
+    /// with or without a warning
+    #[structopt(long, default_value = "warn", possible_values = &["error", "warn", "ignore"])]
+    missing_csv: MissingCsv,
+    /// Translate the calldata word offsets of Var conflicts into parameter ordinals, following
+    /// the head/tail encoding of dynamic parameters.
+    #[structopt(long)]
+    param_ordinals: bool,
+    /// Set "parallel": true on every function whose conflicts allow parallel execution, i.e.
+    /// none of them is All.
+    #[structopt(long)]
+    mark_parallel: bool,
+    /// Insert an All entry for every mutating function without analyzer data, so it is executed
+    /// serially instead of optimistically in parallel.
+    #[structopt(long)]
+    default_all: bool,
+    /// Insert a None entry for every function that is left without entries, view functions for
+    /// example, so they can't be mistaken for functions the analyzer missed. Together with
+    /// --default-all only read-only functions get it.
+    #[structopt(long)]
+    mark_none: bool,
+    /// Only rewrite functions whose conflicts changed since the last run, tracked in a cache
+    /// file next to the abi file.
+    #[structopt(long)]
+    incremental: bool,
+    /// Skip the run if neither the abi nor the analyzer outputs changed since the git ref, for
+    /// monorepos annotating hundreds of contracts on every build
+    #[structopt(long)]
+    changed_only: Option<String>,
+    /// The path of a file caching computed selectors across runs
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    selector_cache: Option<std::path::PathBuf>,
+    /// Process the abi entry by entry instead of loading it as a whole, for huge abis.
+    #[structopt(long)]
+    stream: bool,
+    /// Where the annotated abi goes, in-place by default: in-place, stdout, sidecar for the
+    /// conflictFields by signature in <abi>.conflicts.json, file:<path>, json-patch:<path> for an
+    /// RFC 6902 patch of the abi, merge-patch:<path> for RFC 7386 merge patches by signature or
+    /// sqlite:<path> with the rusqlite feature. Can be given several times.
+    #[structopt(long, conflicts_with_all = &["stream", "low-memory"])]
+    sink: Vec<SinkSpec>,
+    /// The format of the sidecar, yaml and toml for pipelines not taking json, which writes
+    /// <abi>.conflicts.yaml or .toml instead. Only the sidecar is affected, the abi stays json and
+    /// the reports keep their own formats.
+    #[structopt(long, default_value = "json", possible_values = &["json", "yaml", "toml"])]
+    output_format: OutputFormat,
+    /// Trade speed for memory in constrained containers: implies --stream and --jobs 1, and only
+    /// keeps the analyzer output of the functions of the abi, so selectors hashed with the other
+    /// algorithm aren't reported. The conflicts of those functions are still all held in memory,
+    /// what is bounded is the analyzer output of other contracts, which is read row by row
+    #[structopt(long, conflicts_with_all = &["relaxed-json", "abi-pointer", "preserve-format"])]
+    low_memory: bool,
+    /// The number of worker threads, all cores by default
+    #[structopt(short, long)]
+    jobs: Option<usize>,
+    /// Accept abis with comments, trailing commas or single quotes as some code generators emit
+    /// them, the abi is written back as plain json.
+    #[structopt(long, conflicts_with = "stream")]
+    relaxed_json: bool,
+    /// JSON Pointer to the abi array inside a wrapping document like a build artifact, e.g.
+    /// /output/abi. Only that part is edited.
+    #[structopt(long, conflicts_with = "stream")]
+    abi_pointer: Option<String>,
+    /// Patch only the annotation into the abi text, keeping whitespace, indentation and key
+    /// order of everything else so diffs of version-controlled artifacts stay small.
+    #[structopt(long, conflicts_with_all = &["stream", "relaxed-json"])]
+    preserve_format: bool,
+    /// The path of a Markdown report listing the conflicts of every function
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    report: Option<std::path::PathBuf>,
+    /// Print how many functions are fully parallel, key-partitioned, env-serialized or fully
+    /// serialized
+    #[structopt(long)]
+    stats: bool,
+    /// Write the key-partitioned functions with the critical size of their key as the parallel
+    /// configuration of BCOS 2.x, fails if a key is no prefix of the arguments
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    parallel_config: Option<std::path::PathBuf>,
+    /// Write the parallel functions with their critical sizes as the ContractParallelConfig of the
+    /// Java SDK, as properties for a .properties path and as JSON otherwise
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    java_parallel_config: Option<std::path::PathBuf>,
+    /// Write the conflict keys of every function for the go sdk, as go source for a .go path and
+    /// as JSON otherwise
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    go_descriptor: Option<std::path::PathBuf>,
+    /// Write which functions touch every storage slot and with which kinds of conflicts as JSON,
+    /// to find hot storage worth sharding
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    slot_index: Option<std::path::PathBuf>,
+    /// The storageLayout solc writes, alone or in its combined or standard json output, naming
+    /// the slots of the slot index
+    #[structopt(parse(from_os_str))]
+    #[structopt(long, requires = "slot-index")]
+    storage_layout: Option<std::path::PathBuf>,
+    /// Print the given number of slots and environments the most functions conflict on
+    #[structopt(long)]
+    hotspots: Option<usize>,
+    /// Fail if a single slot is shared by more than this many mutating functions
+    #[structopt(long)]
+    hotspot_threshold: Option<usize>,
+    /// Write the selector, canonical signature and contract of every function tab separated for
+    /// the analyzer to label its outputs with, to selectors.map next to the abi unless given
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    selectors_map: Option<Option<std::path::PathBuf>>,
+    /// Write the warnings and the functions the analyzer output doesn't cover as a SARIF log
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    sarif: Option<std::path::PathBuf>,
+    /// A handlebars template the report is rendered with instead of the Markdown table
+    #[structopt(parse(from_os_str))]
+    #[structopt(long, requires = "report")]
+    report_template: Option<std::path::PathBuf>,
+    /// Record the tool version, settings and csv digests of the run, in a sidecar file next to
+    /// the abi or as a reserved entry at its end.
+    #[structopt(long, possible_values = &["sidecar", "entry"])]
+    provenance: Option<ProvenanceMode>,
+    /// Print the conflictFields entries added and removed by the annotation, grouped by function
+    #[structopt(long)]
+    show_diff: bool,
+    /// Only print the diff of the annotation, nothing is written
+    #[structopt(long)]
+    dry_run: bool,
+    /// Review the proposed conflictFields of every changed function before the abi is written,
+    /// accepting, skipping or editing them.
+    #[structopt(long)]
+    interactive: bool,
+    /// A rhai script whose `transform(name, signature, conflicts)` filters, rewrites or adds the
+    /// conflicts of every function, needs the rhai feature
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    script: Option<std::path::PathBuf>,
+    /// A wasm plugin adding conflicts from other analyzers or databases, needs the wasmtime
+    /// feature
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    plugin: Vec<std::path::PathBuf>,
+    /// The argument passed to every plugin, e.g. a connection string
+    #[structopt(long, default_value = "")]
+    plugin_arg: String,
+    /// Also write `"selectors": {"keccak256": .., "sm3": ..}`, so one abi serves standard and GM
+    /// clients of mixed-crypto networks without knowing the order of the selector array
+    #[structopt(long)]
+    named_selectors: bool,
+    /// Functions with more conflicts usually come from a blow-up of the analyzer and slow down
+    /// the scheduler
+    #[structopt(long)]
+    max_conflicts_per_function: Option<usize>,
+    /// Whether such functions are serialized with a single All entry or fail the run
+    #[structopt(long, default_value = "truncate", possible_values = &["truncate", "fail"])]
+    on_too_many_conflicts: CapPolicy,
+    /// Fail if a function is serialized with All, only those matching the comma separated name or
+    /// signature patterns like `transfer*` if given, as All destroys the parallelism of hot paths
+    #[structopt(long)]
+    forbid_all: Option<Option<String>>,
+    /// Only write the conflicts of these comma separated kinds, like `Var,Const`, to see how the
+    /// scheduler does with a reduced conflict model
+    #[structopt(long, use_delimiter = true, conflicts_with = "skip-kinds")]
+    only_kinds: Vec<ConflictType>,
+    /// Drop the conflicts of these comma separated kinds, like `Env`
+    #[structopt(long, use_delimiter = true)]
+    skip_kinds: Vec<ConflictType>,
+    /// Drop the conflicts on these comma separated slots, like `0x0,0x1`, added to the
+    /// ignore_slots of the config
+    #[structopt(long)]
+    ignore_slot: Vec<String>,
+    /// Sign the abi files the run writes, in place or with file sinks, with the Ed25519 key in hex
+    /// in the file, or in CONFLICTS_INFO_PARSE_SIGNING_KEY without one, into a detached <abi>.sig
+    /// next to each of them, with the ed25519-dalek feature
+    #[structopt(long)]
+    sign_key: Option<Option<std::path::PathBuf>>,
+    /// The abi is of an interface or abstract contract: fail with what to annotate instead.
+    /// Without it, such abis are recognized by none of their mutating functions having conflicts
+    #[structopt(long)]
+    expect_interface: bool,
+    /// The abi is of a library: the slots of its analyzer output are of the storage of the
+    /// calling contract, rebased onto the variables its storage parameters point at in
+    /// --caller-layout and checked to be of its variables otherwise
+    #[structopt(long, requires = "caller-layout")]
+    library: bool,
+    /// The storageLayout solc writes for the contract calling the library, alone or in its
+    /// combined or standard json output
+    #[structopt(parse(from_os_str))]
+    #[structopt(long, requires = "library")]
+    caller_layout: Option<std::path::PathBuf>,
+    /// Only annotate the function with this selector, like 0xa9059cbb, or signature, leaving the
+    /// annotations of all others untouched, e.g. after correcting a single row of the csvs
+    #[structopt(long)]
+    selector: Vec<String>,
+    /// Write the inputs and outputs with their digests, the arguments, the outcome of every
+    /// function and the warnings of the run as JSON, to run-manifest.json next to the abi unless
+    /// given, for build systems to cache on and auditors to replay the run
+    #[structopt(parse(from_os_str))]
+    #[structopt(long)]
+    run_manifest: Option<Option<std::path::PathBuf>>,
+    /// Print how long every phase of the run took on stderr: parsing the analyzer outputs and
+    /// the abi, hashing selectors, matching, serializing and writing
+    #[structopt(long)]
+    profile: bool,
+    /// Fail the run on warnings and analyzer selectors matching no function too, for CI
+    #[structopt(long)]
+    fail_on_warning: bool,
+    /// When to color the terminal output
+    #[structopt(long, default_value = "auto", possible_values = &["auto", "always", "never"])]
+    color: ColorChoice,
 }
 
-#[derive(Debug, Serialize_repr, PartialOrd, Ord, PartialEq, Eq, Clone)]
-#[repr(u8)]
-enum ConflictType {
-    All = 0,
-    #[allow(dead_code)]
-    Len,
-    Env,
-    Var,
-    Const,
-    None,
-}
-
-enum EnvironmentType {
-    Caller = 0,
-    Origin,
-    Now,
-    BlockNumber,
-    Address,
-    Unknown,
-}
-
-#[derive(Debug, Serialize, PartialOrd, Ord, PartialEq, Eq, Clone)]
-struct ConflictInfo {
-    kind: ConflictType,
-    #[serde(skip_serializing)]
-    selector: u32,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    slot: Option<u32>,
-    /// for Var, the value is the index of calldata per 32Bytes, for Env, the value is EnvironmentType
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    value: Vec<u32>,
+#[derive(StructOpt)]
+enum Command {
+    /// Upgrade the conflictFields of an abi annotated by an older version to the current format
+    Migrate {
+        /// The path of the annotated abi json file
+        #[structopt(parse(from_os_str))]
+        abi: std::path::PathBuf,
+        /// How slots are written: decimal, hex or padded (32 bytes hex)
+        #[structopt(
+            long,
+            default_value = "decimal",
+            possible_values = &["decimal", "hex", "padded"]
+        )]
+        slot_format: SlotFormat,
+    },
+    /// Check the conflictFields and selectors of an annotated abi, exits with 1 on violations
+    Validate {
+        /// The path of the annotated abi json file
+        #[structopt(parse(from_os_str))]
+        abi: std::path::PathBuf,
+        /// Also write the violations as a SARIF log
+        #[structopt(parse(from_os_str))]
+        #[structopt(long)]
+        sarif: Option<std::path::PathBuf>,
+    },
+    /// Write an annotated abi as CBOR with the serde_cbor feature or MessagePack with the
+    /// rmp-serde feature, for light clients and embedded workers
+    Export {
+        /// The path of the annotated abi json file
+        #[structopt(parse(from_os_str))]
+        abi: std::path::PathBuf,
+        #[structopt(long, possible_values = &["cbor", "msgpack"])]
+        format: export::BinaryFormat,
+        /// The path of the export, the abi path with the extension of the format appended by
+        /// default
+        #[structopt(parse(from_os_str))]
+        #[structopt(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Check the detached signature of an annotated abi, exits with 1 if it doesn't match, with
+    /// the ed25519-dalek feature
+    Verify {
+        /// The path of the annotated abi json file
+        #[structopt(parse(from_os_str))]
+        abi: std::path::PathBuf,
+        /// A file with the Ed25519 public key of the build pipeline in hex
+        #[structopt(parse(from_os_str))]
+        #[structopt(long)]
+        public_key: std::path::PathBuf,
+        /// The path of the signature, <abi>.sig by default
+        #[structopt(parse(from_os_str))]
+        #[structopt(long)]
+        signature: Option<std::path::PathBuf>,
+    },
+    /// Print the JSON Schema of conflictFields
+    Schema {
+        /// The conflictFields version, 1 is the format from before the version stamp
+        #[structopt(long, default_value = "2")]
+        version: u32,
+    },
+    /// Merge the conflictFields of two abis annotated from different analyzer runs per function
+    Merge {
+        /// The annotated abi whose entries are kept
+        #[structopt(parse(from_os_str))]
+        base: std::path::PathBuf,
+        /// The annotated abi whose conflictFields are merged in
+        #[structopt(parse(from_os_str))]
+        other: std::path::PathBuf,
+        /// The path of the merged abi
+        #[structopt(parse(from_os_str))]
+        #[structopt(short, long)]
+        output: std::path::PathBuf,
+        /// union keeps the conflicts of either abi, intersect only those of both
+        #[structopt(long, default_value = "union", possible_values = &["union", "intersect"])]
+        policy: MergePolicy,
+        /// How slots are written: decimal, hex or padded (32 bytes hex)
+        #[structopt(
+            long,
+            default_value = "decimal",
+            possible_values = &["decimal", "hex", "padded"]
+        )]
+        slot_format: SlotFormat,
+    },
+    /// Print the selector of every function under every hash algorithm built in, to compare
+    /// with the selectors of the analyzer
+    HashTable {
+        /// The path of the abi json file
+        #[structopt(parse(from_os_str))]
+        abi: std::path::PathBuf,
+        #[structopt(long, default_value = "tsv", possible_values = &["json", "tsv"])]
+        format: TableFormat,
+    },
+    /// Remove conflictFields, selectors and the provenance entry, restoring the plain abi
+    Strip {
+        /// The path of the annotated abi json file
+        #[structopt(parse(from_os_str))]
+        abi: std::path::PathBuf,
+    },
+    /// Check an annotated abi against the [[lint]] rules of the config, exits with 1 on errors
+    Lint {
+        /// The path of the annotated abi json file
+        #[structopt(parse(from_os_str))]
+        abi: std::path::PathBuf,
+    },
+    /// Generate type declarations of an annotated abi for SDKs
+    EmitTypes {
+        /// The path of the annotated abi json file
+        #[structopt(parse(from_os_str))]
+        abi: std::path::PathBuf,
+        #[structopt(long, default_value = "ts", possible_values = &["ts"])]
+        lang: Lang,
+        /// Where to write the declarations, printed if not given
+        #[structopt(parse(from_os_str))]
+        #[structopt(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Print the conflicts of the functions of an annotated abi matching all criteria given, like
+    /// `--kind Env --value CALLER` or `--slot 0x3`
+    Query {
+        /// The path of the annotated abi json file
+        #[structopt(parse(from_os_str))]
+        abi: std::path::PathBuf,
+        /// The name, signature or 0x selector of the function
+        #[structopt(long)]
+        function: Option<String>,
+        /// The storage slot, decimal or 0x hex
+        #[structopt(long)]
+        slot: Option<String>,
+        /// The kind, like Var or Env
+        #[structopt(long)]
+        kind: Option<String>,
+        /// A value, like an environment name, a calldata word or a constant
+        #[structopt(long)]
+        value: Option<String>,
+        #[structopt(long, default_value = "tsv", possible_values = &["json", "tsv"])]
+        format: TableFormat,
+    },
+    /// Annotate the abi of an EIP-2535 diamond with the conflicts the analyzer found in each of
+    /// its facets, exits with 1 if facets clash on a selector
+    Diamond {
+        /// The path of the diamond abi json file
+        #[structopt(parse(from_os_str))]
+        diamond: std::path::PathBuf,
+        /// A facet as the path of its abi and of its analyzer outputs, `Facet.abi:outputs`
+        #[structopt(long = "facet", required = true, number_of_values = 1)]
+        facets: Vec<diamond::FacetSpec>,
+        /// The path of the annotated diamond abi
+        #[structopt(parse(from_os_str))]
+        #[structopt(short, long)]
+        output: std::path::PathBuf,
+    },
+    /// Annotate the abi of an upgradeable proxy with the conflictFields of its annotated
+    /// implementation, by selector
+    Proxy {
+        /// The path of the proxy abi json file
+        #[structopt(parse(from_os_str))]
+        proxy: std::path::PathBuf,
+        /// The path of the annotated implementation abi
+        #[structopt(parse(from_os_str))]
+        implementation: std::path::PathBuf,
+        /// The path of the annotated proxy abi
+        #[structopt(parse(from_os_str))]
+        #[structopt(short, long)]
+        output: std::path::PathBuf,
+    },
+    /// Print which mutating functions of the contracts of a system may conflict across contracts,
+    /// through All or storage they share
+    System {
+        /// The paths of the annotated abis, the contracts are named after the files
+        #[structopt(parse(from_os_str), required = true, min_values = 2)]
+        abis: Vec<std::path::PathBuf>,
+        /// A json object of contract names to the address whose storage they run on, like that of
+        /// the proxy calling them by delegatecall, contracts not in it have their own storage
+        #[structopt(parse(from_os_str))]
+        #[structopt(long)]
+        deployment: Option<std::path::PathBuf>,
+    },
+    /// Write a commented conflicts_info_parse.toml into the working directory, with the abis,
+    /// analyzer outputs and hash algorithm found in the project
+    Init {
+        /// Overwrite an existing config
+        #[structopt(long)]
+        force: bool,
+    },
+    /// Diagnose the usual setup problems: missing csvs, unknown csv layouts, an abi that doesn't
+    /// parse and analyzer selectors hashed with the other algorithm. Exits with 1 on problems
+    Doctor {
+        /// The path of the abi json file
+        #[structopt(parse(from_os_str))]
+        abi: std::path::PathBuf,
+        /// The directory of the analyzer csvs
+        #[structopt(parse(from_os_str))]
+        path: std::path::PathBuf,
+    },
+    /// Describe the conflicts of a function of an annotated abi in plain sentences
+    Explain {
+        /// The path of the annotated abi json file
+        #[structopt(parse(from_os_str))]
+        abi: std::path::PathBuf,
+        /// The name, signature or 0x selector of the function
+        function: String,
+    },
 }
 
-fn parse_conflict_info(path: &std::path::Path) -> Vec<ConflictInfo> {
-    let mut result = Vec::new();
-
-    let env_csv = path.join("Conflict_EnvConflict.csv");
-    let slot_re = Regex::new(r"0x([\da-f]+)").unwrap();
-    let mut rdr = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .delimiter(b'\t')
-        .from_path(env_csv.as_path())
-        .unwrap();
-    for r in rdr.records() {
-        let record = r.unwrap();
-        // info!("env_csv {:?}, {} ", &record, record.len());
-        let selector = u32::from_str_radix(record[1].trim_start_matches("0x"), 16).unwrap();
-        let value = match record[2].as_ref() {
-            "CALLER" => vec![EnvironmentType::Caller as u32],
-            "ORIGIN" => vec![EnvironmentType::Origin as u32],
-            "TIMESTAMP" => vec![EnvironmentType::Now as u32],
-            "NUMBER" => vec![EnvironmentType::BlockNumber as u32],
-            "ADDRESS" => vec![EnvironmentType::Address as u32],
-            _ => {
-                error!("Unknown environment type: {}", &record[2]);
-                vec![EnvironmentType::Unknown as u32]
-            }
-        };
-
-        let slot = u32::from_str_radix(
-            slot_re
-                .find(&record[3])
-                .unwrap()
-                .as_str()
-                .trim_start_matches("0x"),
-            16,
-        )
-        .unwrap();
-        result.push(ConflictInfo {
-            kind: ConflictType::Env,
-            selector,
-            slot: Some(slot),
-            value,
-        });
-    }
-    let mix_csv = path.join("Conflict_MixConflict.csv");
-    rdr = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .delimiter(b'\t')
-        .from_path(mix_csv.as_path())
-        .unwrap();
-    for r in rdr.records() {
-        let record = r.unwrap();
-        let selector = u32::from_str_radix(record[0].trim_start_matches("0x"), 16).unwrap();
-        // let slot = u32::from_str_radix(
-        //     slot_re
-        //         .find(&record[2])
-        //         .unwrap()
-        //         .as_str()
-        //         .trim_start_matches("0x"),
-        //     16,
-        // )
-        // .unwrap();
-        result.push(ConflictInfo {
-            kind: ConflictType::All,
-            selector,
-            slot: None,
-            value: vec![],
-        });
-    }
-    let call_contract_csv = path.join("Conflict_NoStorageAccessHasContractCalling.csv");
-    rdr = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .delimiter(b'\t')
-        .from_path(call_contract_csv.as_path())
-        .unwrap();
-    for r in rdr.records() {
-        let record = r.unwrap();
-        let selector = u32::from_str_radix(record[0].trim_start_matches("0x"), 16).unwrap();
-        result.push(ConflictInfo {
-            kind: ConflictType::All,
-            selector,
-            slot: None,
-            value: vec![],
-        });
-    }
-
-    let var_csv = path.join("Conflict_FunArgConflict.csv");
-    rdr = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .delimiter(b'\t')
-        .from_path(var_csv.as_path())
-        .unwrap();
-    for r in rdr.records() {
-        let record = r.unwrap();
-        let selector = u32::from_str_radix(record[1].trim_start_matches("0x"), 16).unwrap();
-        let value = vec![record[2].parse().unwrap()];
-        let slot = u32::from_str_radix(
-            slot_re
-                .find(&record[3])
-                .expect(&format!("slot not found {:?}", &record[3]))
-                .as_str()
-                .trim_start_matches("0x"),
-            16,
-        )
-        .unwrap();
-        result.push(ConflictInfo {
-            kind: ConflictType::Var,
-            selector,
-            slot: Some(slot),
-            value,
-        });
-    }
-
-    let dynamic_const_csv = path.join("Conflict_DynaVarConsConflict.csv");
-    rdr = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .delimiter(b'\t')
-        .from_path(dynamic_const_csv.as_path())
-        .unwrap();
-    for r in rdr.records() {
-        let record = r.unwrap();
-        let selector = u32::from_str_radix(record[1].trim_start_matches("0x"), 16).unwrap();
-        let slot = u32::from_str_radix(
-            slot_re
-                .find(&record[2])
-                .unwrap()
-                .as_str()
-                .trim_start_matches("0x"),
-            16,
-        )
-        .unwrap();
-        let mut value_hex = record[3].trim_start_matches("0x").to_string();
-        if value_hex.len() % 2 != 0 {
-            value_hex.insert(0, '0');
-        }
-        let value = hex::decode(value_hex)
-            .unwrap()
-            .iter_mut()
-            .map(|x| *x as u32)
-            .collect();
-        result.push(ConflictInfo {
-            kind: ConflictType::Const,
-            selector,
-            slot: Some(slot),
-            value,
-        });
-    }
-
-    let basic_const_csv = path.join("Conflict_BasicVarConsConflict.csv");
-    rdr = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .delimiter(b'\t')
-        .from_path(basic_const_csv.as_path())
-        .unwrap();
-    for r in rdr.records() {
-        let record = r.unwrap();
-        let selector = u32::from_str_radix(record[1].trim_start_matches("0x"), 16).unwrap();
-        let mut value_hex = record[2].trim_start_matches("0x").to_string();
-        if value_hex.len() % 2 != 0 {
-            value_hex.insert(0, '0');
-        }
-        let value = hex::decode(value_hex)
-            .unwrap()
-            .iter_mut()
-            .map(|x| *x as u32)
-            .collect();
-        result.push(ConflictInfo {
-            kind: ConflictType::Const,
-            selector,
-            slot: None,
-            value,
-        });
+/// The analyzer output path of `abi`, with `{contract}` replaced by its contract name so one
+/// template serves every abi of the project.
+fn contract_path(path: &std::path::Path, abi: &std::path::Path) -> std::path::PathBuf {
+    let template = path.to_string_lossy();
+    if !template.contains("{contract}") {
+        return path.to_path_buf();
     }
-    let none_csv = path.join("Conflict_NoConflict.csv");
-    rdr = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .delimiter(b'\t')
-        .from_path(none_csv.as_path())
-        .unwrap();
-    for r in rdr.records() {
-        let record = r.unwrap();
-        let selector = u32::from_str_radix(record[0].trim_start_matches("0x"), 16).unwrap();
-        result.push(ConflictInfo {
-            kind: ConflictType::None,
-            selector,
-            slot: None,
-            value: vec![],
-        });
-    }
-    info!("parse conflicts completed");
-    result.sort();
-    result.dedup();
-    result
+    template.replace("{contract}", &contract_name(abi)).into()
 }
 
-fn parse_ty(ty_info: &Map<String, Value>) -> String {
-    const TUPLE_TY: &str = "tuple";
-
-    let ty = ty_info.get("type").unwrap().as_str().unwrap();
-    if ty.starts_with(TUPLE_TY) {
-        let components = ty_info.get("components").unwrap().as_array().unwrap();
-        let component_types = components
-            .iter()
-            .map(|component| parse_ty(component.as_object().unwrap()))
-            .collect::<Vec<String>>()
-            .join(",");
-        format!("({}){}", component_types, &ty[TUPLE_TY.len()..])
-    } else {
-        String::from(ty)
-    }
-}
+/// Names the parser in the profile, to compare the two on aggregator abis of tens of MB.
+const PARSE_ABI_PHASE: &str = if cfg!(feature = "simd-json") {
+    "parse abi (simd-json)"
+} else {
+    "parse abi"
+};
 
-fn get_method_signature(method: &Map<String, Value>) -> String {
-    let fn_name = String::from(method["name"].as_str().unwrap());
-    let inputs = method["inputs"].as_array().unwrap();
-    let sig = inputs
-        .iter()
-        .map(|input| parse_ty(input.as_object().unwrap()))
-        .collect::<Vec<String>>()
-        .join(",");
-    format!("{}({})", fn_name, sig)
+#[cfg(feature = "simd-json")]
+fn parse_abi_json(content: &str) -> serde_json::Value {
+    // simd-json parses in place
+    let mut bytes = content.as_bytes().to_vec();
+    simd_json::serde::from_slice(&mut bytes).unwrap()
 }
 
-fn get_method_id(signature: &str, gm: bool) -> u32 {
-    if gm {
-        let mut sm3_hash = libsm::sm3::hash::Sm3Hash::new(signature.as_bytes());
-        let hash = sm3_hash.get_hash();
-        u32::from_be_bytes(hash[..4].try_into().unwrap())
-    } else {
-        let hash = sha3::Keccak256::digest(signature.as_bytes());
-        u32::from_be_bytes(hash.as_slice()[..4].try_into().unwrap())
-    }
+#[cfg(not(feature = "simd-json"))]
+fn parse_abi_json(content: &str) -> serde_json::Value {
+    serde_json::from_str(content).unwrap()
 }
 
 fn main() {
     env_logger::Builder::from_env(Env::default().default_filter_or("error")).init();
     let args = Cli::from_args();
-    let abi_content = std::fs::read_to_string(&args.abi)
-        .expect(format!("could not read file {}", args.abi.display()).as_str());
-    let conflicts = parse_conflict_info(args.path.as_path());
-
-    let mut origin_abi: Value = serde_json::from_str(&abi_content).unwrap();
-    origin_abi
-        .as_array_mut()
-        .unwrap()
-        .iter_mut()
-        .for_each(|method| {
-            let method = method.as_object_mut().unwrap();
-            if method.contains_key("name")
-                && method.contains_key("type")
-                && method["type"] == Value::String("function".into())
-            {
-                let signature = get_method_signature(method);
-                let method_id = get_method_id(&signature, args.gm);
-                let method_conflicts: Vec<ConflictInfo> = conflicts
-                    .iter()
-                    .filter(|conflict| conflict.selector == method_id)
-                    .cloned()
-                    .collect();
-                if method_conflicts.len() != 0 {
-                    method.insert(
-                        "conflictFields".into(),
-                        serde_json::to_value(method_conflicts).unwrap(),
-                    );
-                }
-                if args.gm {
-                    method.insert(
-                        "selector".into(),
-                        serde_json::to_value(vec![get_method_id(&signature, false), method_id])
-                            .unwrap(),
-                    );
-                } else {
-                    method.insert(
-                        "selector".into(),
-                        serde_json::to_value(vec![method_id, get_method_id(&signature, true)])
-                            .unwrap(),
-                    );
+    args.color.apply();
+    let jobs = if args.low_memory { Some(1) } else { args.jobs };
+    if let Some(jobs) = jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .unwrap();
+    }
+    if args.gm && cfg!(not(feature = "gm")) {
+        error!("--gm needs SM3 support, build with the gm feature");
+        std::process::exit(1);
+    }
+    if let Some(command) = &args.command {
+        match command {
+            Command::Migrate { abi, slot_format } => {
+                if let Err(message) = migrate::migrate(abi, *slot_format) {
+                    error!("{}", message);
+                    std::process::exit(1);
                 }
             }
-        });
-    let new_abi = serde_json::to_string(&origin_abi).unwrap();
-    std::fs::write(&args.abi, new_abi).unwrap();
-    print!(
-        "parse csv and rewrite abi successfully, new abi is saved to {}",
-        format!("{}", args.abi.display()).green()
-    );
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    static ABI_STR: &'static str = r#"[
-        {
-            "inputs": [],
-            "stateMutability": "nonpayable",
-            "type": "constructor"
-        },
-        {
-            "inputs": [],
-            "name": "StrRead",
-            "outputs": [
-                {
-                    "internalType": "string",
-                    "name": "",
-                    "type": "string"
-                }
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "StrWrite",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "name": "complexMap",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "a",
-                    "type": "uint256"
-                },
-                {
-                    "internalType": "uint256",
-                    "name": "b",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "view",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "complexMapReadWithConsKey",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "complexMapWriteWithConsKey",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "name": "dynaArray",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "view",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "dynaArrayReadWithConsKey",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "i",
-                    "type": "uint256"
-                }
-            ],
-            "name": "dynaArrayReadWithFuncArgKey",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "dynaArrayWriteWithConsKey",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "i",
-                    "type": "uint256"
-                }
-            ],
-            "name": "dynaArrayWriteWithFuncArgKey",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "fixedSizeVar",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "view",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "fixedSizeVarRead",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "fixedSizeVarWrite",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "x",
-                    "type": "uint256"
-                }
-            ],
-            "name": "noStorageAccessAndContractCalling",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "address",
-                    "name": "addr",
-                    "type": "address"
-                }
-            ],
-            "name": "noStorageAccessHasContractCalling",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "name": "simpleMap",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "view",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "address",
-                    "name": "",
-                    "type": "address"
-                }
-            ],
-            "name": "simpleMapForTestEnv",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "view",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "simpleMapIndirectWriteWithEnvKey",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "simpleMapReadWithConsKey",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "x",
-                    "type": "uint256"
-                }
-            ],
-            "name": "simpleMapReadWithEnvKey",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "i",
-                    "type": "uint256"
-                }
-            ],
-            "name": "simpleMapReadWithFuncArgKey",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "simpleMapWriteWithConsKey",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "simpleMapWriteWithEnvKey",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "i",
-                    "type": "uint256"
-                }
-            ],
-            "name": "simpleMapWriteWithFuncArgKey",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "x",
-                    "type": "uint256"
+            Command::Validate { abi, sarif } => {
+                let config = config::Config::load(args.config.as_deref());
+                if !validate::validate(abi, sarif.as_deref(), &config.environments()) {
+                    std::process::exit(1);
                 }
-            ],
-            "name": "simpleMapWriteWithMixKey",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "components": [
-                        {
-                            "internalType": "uint256",
-                            "name": "a",
-                            "type": "uint256"
-                        },
-                        {
-                            "internalType": "uint256",
-                            "name": "b",
-                            "type": "uint256"
-                        }
-                    ],
-                    "internalType": "struct S",
-                    "name": "s",
-                    "type": "tuple"
-                }
-            ],
-            "name": "simpleMapWriteWithPartStructFuncArgKey",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "x",
-                    "type": "uint256"
-                },
-                {
-                    "components": [
-                        {
-                            "internalType": "uint256",
-                            "name": "a",
-                            "type": "uint256"
-                        },
-                        {
-                            "internalType": "uint256",
-                            "name": "b",
-                            "type": "uint256"
-                        }
-                    ],
-                    "internalType": "struct S",
-                    "name": "s",
-                    "type": "tuple"
-                }
-            ],
-            "name": "simpleMapWriteWithPartStructFuncArgKey2",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "a",
-                    "type": "uint256"
-                },
-                {
-                    "components": [
-                        {
-                            "internalType": "uint256",
-                            "name": "a",
-                            "type": "uint256"
-                        },
-                        {
-                            "components": [
-                                {
-                                    "internalType": "string",
-                                    "name": "a",
-                                    "type": "string"
-                                },
-                                {
-                                    "components": [
-                                        {
-                                            "internalType": "uint256",
-                                            "name": "a",
-                                            "type": "uint256"
-                                        },
-                                        {
-                                            "internalType": "uint256",
-                                            "name": "b",
-                                            "type": "uint256"
-                                        }
-                                    ],
-                                    "internalType": "struct S",
-                                    "name": "b",
-                                    "type": "tuple"
-                                },
-                                {
-                                    "internalType": "string",
-                                    "name": "c",
-                                    "type": "string"
-                                }
-                            ],
-                            "internalType": "struct SS",
-                            "name": "b",
-                            "type": "tuple"
-                        },
-                        {
-                            "internalType": "uint256",
-                            "name": "c",
-                            "type": "uint256"
-                        }
-                    ],
-                    "internalType": "struct SSS",
-                    "name": "sss",
-                    "type": "tuple"
-                },
-                {
-                    "internalType": "uint256",
-                    "name": "c",
-                    "type": "uint256"
-                }
-            ],
-            "name": "simpleMapWriteWithPartStructFuncArgKey3",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "x",
-                    "type": "uint256"
-                },
-                {
-                    "components": [
-                        {
-                            "internalType": "string",
-                            "name": "a",
-                            "type": "string"
-                        },
-                        {
-                            "components": [
-                                {
-                                    "internalType": "uint256",
-                                    "name": "a",
-                                    "type": "uint256"
-                                },
-                                {
-                                    "internalType": "uint256",
-                                    "name": "b",
-                                    "type": "uint256"
-                                }
-                            ],
-                            "internalType": "struct S",
-                            "name": "b",
-                            "type": "tuple"
-                        },
-                        {
-                            "internalType": "string",
-                            "name": "c",
-                            "type": "string"
-                        }
-                    ],
-                    "internalType": "struct SS",
-                    "name": "ss",
-                    "type": "tuple"
-                }
-            ],
-            "name": "simpleMapWriteWithPartStructFuncArgKey4",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "a",
-                    "type": "uint256"
-                },
-                {
-                    "internalType": "uint256[]",
-                    "name": "b",
-                    "type": "uint256[]"
-                },
-                {
-                    "internalType": "uint256",
-                    "name": "c",
-                    "type": "uint256"
+            }
+            Command::Schema { version } => {
+                let schema = schema::conflict_fields_schema(*version);
+                println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+            }
+            Command::Export {
+                abi,
+                format,
+                output,
+            } => export::export(abi, *format, output.as_deref()),
+            Command::Verify {
+                abi,
+                public_key,
+                signature,
+            } => {
+                if !signing::verify(abi, public_key, signature.as_deref()) {
+                    std::process::exit(1);
                 }
-            ],
-            "name": "simpleMapWriteWithPartStructFuncArgKey5",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "a",
-                    "type": "uint256"
-                },
-                {
-                    "internalType": "uint256[5]",
-                    "name": "b",
-                    "type": "uint256[5]"
-                },
-                {
-                    "internalType": "uint256",
-                    "name": "c",
-                    "type": "uint256"
+            }
+            Command::Explain { abi, function } => explain::explain(abi, function),
+            Command::Query {
+                abi,
+                function,
+                slot,
+                kind,
+                value,
+                format,
+            } => {
+                let config = config::Config::load(args.config.as_deref());
+                let query = query::Query::parse(
+                    function.as_deref(),
+                    slot.as_deref(),
+                    kind.as_deref(),
+                    value.as_deref(),
+                    &config.environments(),
+                )
+                .unwrap_or_else(|e| panic!("invalid query: {}", e));
+                query::query(abi, &query, *format);
+            }
+            Command::Diamond {
+                diamond,
+                facets,
+                output,
+            } => {
+                let config = config::Config::load(args.config.as_deref());
+                let layouts = config.csv_layouts();
+                let environments = config.environments();
+                let csv_options = CsvOptions {
+                    layouts: &layouts,
+                    layout: None,
+                    only: None,
+                    environments: &environments,
+                    selector_radix: args.selector_radix,
+                    gm: args.gm,
+                    missing: args.missing_csv,
+                };
+                if !diamond::annotate_diamond(
+                    diamond,
+                    facets,
+                    output,
+                    &csv_options,
+                    args.slot_format,
+                ) {
+                    std::process::exit(1);
                 }
-            ],
-            "name": "simpleMapWriteWithPartStructFuncArgKey6",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "simpleStruct",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "a",
-                    "type": "uint256"
-                },
-                {
-                    "internalType": "uint256",
-                    "name": "b",
-                    "type": "uint256"
+            }
+            Command::Proxy {
+                proxy,
+                implementation,
+                output,
+            } => proxy::project_proxy(proxy, implementation, output, args.gm),
+            Command::System { abis, deployment } => {
+                system::system_report(abis, deployment.as_deref())
+            }
+            Command::Init { force } => {
+                if !init::init(*force) {
+                    std::process::exit(1);
                 }
-            ],
-            "stateMutability": "view",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "simpleStructRead",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
+            }
+            Command::Doctor { abi, path } => {
+                let config = config::Config::load(args.config.as_deref());
+                if !doctor::doctor(abi, path, &config.csv_layouts(), args.gm) {
+                    std::process::exit(1);
                 }
-            ],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "simpleStructWrite",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        },
-        {
-            "inputs": [],
-            "name": "str",
-            "outputs": [
-                {
-                    "internalType": "string",
-                    "name": "",
-                    "type": "string"
+            }
+            Command::Strip { abi } => strip::strip(abi),
+            Command::HashTable { abi, format } => hashtable::hash_table(abi, *format),
+            Command::EmitTypes { abi, lang, output } => {
+                emit::emit_types(abi, *lang, output.as_deref())
+            }
+            Command::Lint { abi } => {
+                let config = config::Config::load(args.config.as_deref());
+                if !lint::lint(abi, &config.lints, &config.environments()) {
+                    std::process::exit(1);
                 }
-            ],
-            "stateMutability": "view",
-            "type": "function"
+            }
+            Command::Merge {
+                base,
+                other,
+                output,
+                policy,
+                slot_format,
+            } => merge::merge(base, other, output, *policy, *slot_format),
         }
-    ]"#;
-
-    #[test]
-    fn test_simple_selector() {
-        let origin_abi: Value = serde_json::from_str(ABI_STR).unwrap();
-        let functions = origin_abi
-            .as_array()
-            .unwrap()
-            .iter()
-            .filter(|v| {
-                let value = v.as_object().unwrap();
-                return value.contains_key("name")
-                    && value.contains_key("type")
-                    && value["type"].as_str().unwrap() == "function";
-            })
-            .collect::<Vec<&Value>>();
-
-        let str_read = functions
+        return;
+    }
+    // the abi is required without a subcommand, the csvs unless there are other sources
+    let abi = args.abi.as_deref().unwrap();
+    let path = args.path.as_deref().map(|path| contract_path(path, abi));
+    let path = path.as_deref();
+    if let Some(reference) = &args.changed_only {
+        let mut inputs = vec![abi];
+        inputs.extend(path);
+        inputs.extend(args.devdoc.iter().map(|devdoc| devdoc.as_path()));
+        inputs.extend(args.conflicts_json.iter().map(|json| json.as_path()));
+        inputs.extend(args.overrides.as_deref());
+        inputs.extend(args.config.as_deref());
+        match git::changed_since(reference, &inputs) {
+            Ok(false) => {
+                eprintln!(
+                    "{} and its analyzer outputs unchanged since {}, skipped",
+                    format!("{}", abi.display()).green(),
+                    reference
+                );
+                return;
+            }
+            Ok(true) => {}
+            Err(e) => eprintln!(
+                "{}: could not ask git what changed since {}, annotating anyway: {}",
+                "warning".yellow(),
+                reference,
+                e
+            ),
+        }
+    }
+    let config = config::Config::load(args.config.as_deref());
+    let layouts = config.csv_layouts();
+    let environments = config.environments();
+    let layout = args.csv_layout.as_deref().map(|name| {
+        layouts
             .iter()
-            .find(|v| v["name"].as_str().unwrap() == "StrRead")
-            .unwrap()
-            .as_object()
-            .unwrap();
-        let str_read_sig = get_method_signature(str_read);
-        assert_eq!(str_read_sig, "StrRead()".to_string());
-        let str_read_selector = get_method_id(&str_read_sig, false);
-        assert_eq!(
-            str_read_selector,
-            u32::from_str_radix("4db0cdb2", 16).unwrap()
-        );
+            .find(|layout| layout.name == name)
+            .unwrap_or_else(|| panic!("unknown csv layout {}", name))
+    });
+    // the analyzer outputs cover all contracts, only keep what this abi needs
+    let only = args.low_memory.then(|| {
+        annotate::abi_selectors(abi, args.gm)
+            .unwrap_or_else(|e| panic!("could not read {}: {}", abi.display(), e))
+    });
+    let csv_options = CsvOptions {
+        layouts: &layouts,
+        layout,
+        only: only.as_ref(),
+        environments: &environments,
+        selector_radix: args.selector_radix,
+        gm: args.gm,
+        missing: args.missing_csv,
+    };
+    let mut sources: Vec<Box<dyn ConflictSource>> = Vec::new();
+    match path {
+        Some(path) if path.is_file() => sources.push(Box::new(source::MergedCsvSource {
+            path,
+            options: &csv_options,
+        })),
+        Some(path) => sources.push(Box::new(source::CsvSource {
+            path,
+            options: &csv_options,
+        })),
+        None => {}
+    }
+    for devdoc in &args.devdoc {
+        sources.push(Box::new(devdoc::DevdocSource {
+            path: devdoc.clone(),
+            gm: args.gm,
+            environments: &environments,
+        }));
+    }
+    for conflicts_json in &args.conflicts_json {
+        sources.push(Box::new(source::JsonSource {
+            path: conflicts_json.clone(),
+        }));
+    }
+    for builtin in &args.builtin {
+        sources.push(Box::new(builtin::BuiltinSource {
+            builtin: builtin.clone(),
+            gm: args.gm,
+        }));
+    }
+    for plugin in &args.plugin {
+        sources.push(Box::new(source::WasmPlugin::load(plugin, &args.plugin_arg)));
+    }
+    if let Some(overrides) = &args.overrides {
+        sources.push(Box::new(source::OverridesSource {
+            path: overrides.clone(),
+            gm: args.gm,
+        }));
+    }
+    let mut profile = profile::Profile::default();
+    let (mut conflicts, superseded) = profile
+        .time("parse analyzer outputs", || source::collect(&sources))
+        .unwrap_or_else(|message| {
+            error!("{}", message);
+            std::process::exit(1);
+        });
+    // the stable sort keeps the order of the conflicts of a function
+    conflicts.sort_by_key(|conflict| conflict.selector);
+    let only_selectors = args
+        .selector
+        .iter()
+        .map(|selector| selector_of(selector, args.gm).unwrap_or_else(|e| panic!("{}", e)))
+        .collect::<std::collections::BTreeSet<u32>>();
+    if !only_selectors.is_empty() {
+        conflicts.retain(|conflict| only_selectors.contains(&conflict.selector));
+    }
+    let mut inputs = match path {
+        Some(path) if path.is_file() => vec![path.to_path_buf()],
+        Some(path) => conflict_csvs(path),
+        None => Vec::new(),
+    };
+    inputs.extend(args.devdoc.iter().cloned());
+    inputs.extend(args.conflicts_json.iter().cloned());
+    inputs.extend(args.overrides.iter().cloned());
+    let provenance = args
+        .provenance
+        .map(|_| provenance::provenance(&inputs, args.gm));
+    // digested before the abi is rewritten
+    let abi_sha256 = args
+        .run_manifest
+        .as_ref()
+        .map(|_| provenance::sha256_file(abi));
 
-        let str_write = functions
+    let options = options::AnnotationOptions::new()
+        .hash(if args.gm {
+            hash::Hash::Sm3
+        } else {
+            hash::Hash::Keccak256
+        })
+        .slot_format(args.slot_format)
+        .param_ordinals(args.param_ordinals)
+        .mark_parallel(args.mark_parallel)
+        .default_all(args.default_all)
+        .mark_none(args.mark_none)
+        .named_selectors(args.named_selectors)
+        .dry_run(args.dry_run)
+        .max_conflicts(args.max_conflicts_per_function, args.on_too_many_conflicts)
+        .fail_on_warning(args.fail_on_warning)
+        .kinds(
+            match (args.only_kinds.is_empty(), args.skip_kinds.is_empty()) {
+                (true, true) => None,
+                (false, _) => Some(args.only_kinds.clone()),
+                (true, false) => Some(
+                    CONFLICT_TYPES
+                        .iter()
+                        .map(|(_, kind)| kind.clone())
+                        .filter(|kind| !args.skip_kinds.contains(kind))
+                        .collect(),
+                ),
+            },
+        )
+        .rules(config.rules.clone())
+        .expect_interface(args.expect_interface)
+        .library(
+            args.caller_layout
+                .as_deref()
+                .filter(|_| args.library)
+                .map(library::CallerLayout::load),
+        )
+        .only_selectors(only_selectors)
+        .ignore_slots(
+            args.ignore_slot
+                .iter()
+                .flat_map(|slots| slots.split(','))
+                .chain(config.ignore_slots.iter().map(String::as_str))
+                .map(|slot| conflict::parse_slot(slot.trim()).unwrap_or_else(|e| panic!("{}", e)))
+                .collect(),
+        )
+        .forbid_all(args.forbid_all.as_ref().map(|filter| {
+            filter
+                .iter()
+                .flat_map(|filter| filter.split(','))
+                .map(String::from)
+                .collect()
+        }));
+    let mut annotator = annotate::Annotator {
+        cache: args
+            .incremental
+            .then(|| cache::AnnotationCache::load(abi, args.gm)),
+        selectors: args
+            .selector_cache
+            .as_deref()
+            .map(cache::SelectorCache::load)
+            .unwrap_or_default(),
+        provenance: match (args.provenance, &provenance) {
+            (Some(ProvenanceMode::Entry), Some(provenance)) => {
+                Some(provenance::provenance_entry(provenance.clone()))
+            }
+            _ => None,
+        },
+        reviewer: args.interactive.then(review::Reviewer::default),
+        script: args.script.as_deref().map(script::Script::load),
+        warnings: sources
             .iter()
-            .find(|v| v["name"].as_str().unwrap() == "StrWrite")
-            .unwrap()
-            .as_object()
-            .unwrap();
-        let str_write_sig = get_method_signature(str_write);
-        assert_eq!(str_write_sig, "StrWrite()".to_string());
-        let str_write_selector = get_method_id(&str_write_sig, false);
-        assert_eq!(
-            str_write_selector,
-            u32::from_str_radix("7ea33fd1", 16).unwrap()
-        );
-
-        let complex_map = functions
+            .flat_map(|source| source.warnings())
+            .collect(),
+        ..options.annotator(&conflicts)
+    };
+    let sinks = if args.sink.is_empty() {
+        vec![sink::SinkSpec::InPlace.sink(abi, args.output_format)]
+    } else {
+        args.sink
             .iter()
-            .find(|v| v["name"].as_str().unwrap() == "complexMap")
-            .unwrap()
-            .as_object()
-            .unwrap();
-        let complex_map_sig = get_method_signature(complex_map);
-        assert_eq!(complex_map_sig, "complexMap(uint256)".to_string());
-        let complex_map_selector = get_method_id(&complex_map_sig, false);
-        assert_eq!(
-            complex_map_selector,
-            u32::from_str_radix("3e49c8f4", 16).unwrap()
-        );
-        let simple_map_write_with_part_struct_func_arg_key = functions
+            .map(|spec| spec.sink(abi, args.output_format))
+            .collect()
+    };
+    // the files holding the annotated abi are signed, the other sinks write something else
+    let abi_files = if args.sink.is_empty() {
+        vec![abi]
+    } else {
+        args.sink
             .iter()
-            .find(|v| v["name"].as_str().unwrap() == "simpleMapWriteWithPartStructFuncArgKey")
-            .unwrap()
-            .as_object()
-            .unwrap();
-        let simple_map_write_with_part_struct_func_arg_key_sig =
-            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
-        assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_sig,
-            "simpleMapWriteWithPartStructFuncArgKey((uint256,uint256))".to_string()
-        );
-        let simple_map_write_with_part_struct_func_arg_key_selector =
-            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
-        assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_selector,
-            u32::from_str_radix("c158e6ef", 16).unwrap()
+            .filter_map(|spec| spec.abi_file(abi))
+            .collect::<Vec<&std::path::Path>>()
+    };
+    if args.sign_key.is_some() && abi_files.is_empty() {
+        panic!("--sign-key signs the abi files written, but no sink writes the abi to a file");
+    }
+    let written = if args.stream || args.low_memory {
+        let mut file_name = abi.file_name().unwrap().to_os_string();
+        file_name.push(".tmp");
+        let tmp = abi.with_file_name(file_name);
+        profile
+            .time("annotate", || {
+                annotate::annotate_stream(&mut annotator, abi, &tmp)
+            })
+            .unwrap_or_else(|e| panic!("could not rewrite {}: {}", abi.display(), e));
+        profile.split("annotate", "hash selectors", annotator.selectors.hashing);
+        let written = annotator.finish();
+        if written {
+            std::fs::rename(&tmp, abi).unwrap();
+        } else {
+            std::fs::remove_file(&tmp).unwrap();
+        }
+        written
+    } else {
+        let parse_start = std::time::Instant::now();
+        let abi_content = std::fs::read_to_string(abi)
+            .unwrap_or_else(|_| panic!("could not read file {}", abi.display()));
+        let mut document: serde_json::Value = if args.relaxed_json {
+            json5::from_str(&abi_content)
+                .unwrap_or_else(|e| panic!("could not parse {}: {}", abi.display(), e))
+        } else {
+            parse_abi_json(&abi_content)
+        };
+        // the abi may be wrapped in a build artifact, the rest of it is kept as is
+        let abi_value = match &args.abi_pointer {
+            Some(pointer) => document
+                .pointer_mut(pointer)
+                .unwrap_or_else(|| panic!("{} not found in {}", pointer, abi.display())),
+            None => &mut document,
+        };
+        let values: Vec<serde_json::Value> = serde_json::from_value(abi_value.take()).unwrap();
+        let mut origin_abi = values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| annotator.entry(index, value))
+            .collect::<Vec<AbiEntry>>();
+        profile.add(PARSE_ABI_PHASE, parse_start.elapsed());
+        let signatures = origin_abi
+            .par_iter()
+            .filter(|entry| entry.is_function())
+            .map(get_method_signature)
+            .collect::<Vec<String>>();
+        annotator.selectors.prefill(&signatures);
+        let prefilled = annotator.selectors.hashing;
+        profile.add("hash selectors", prefilled);
+        profile.time("match", || {
+            origin_abi
+                .iter_mut()
+                .for_each(|method| annotator.annotate(method))
+        });
+        profile.split(
+            "match",
+            "hash selectors",
+            annotator.selectors.hashing - prefilled,
         );
-        let simple_map_write_with_part_struct_func_arg_key = functions
+        let serialize_start = std::time::Instant::now();
+        let new_abi = if args.preserve_format {
+            patch::patch_abi(
+                &abi_content,
+                args.abi_pointer.as_deref(),
+                &origin_abi,
+                annotator.provenance.as_ref(),
+            )
+        } else {
+            if let Some(provenance) = &annotator.provenance {
+                origin_abi.retain(|entry| !entry.is_provenance());
+                origin_abi.push(provenance.clone());
+            }
+            match &args.abi_pointer {
+                Some(_) => {
+                    *abi_value = serde_json::to_value(&origin_abi).unwrap();
+                    serde_json::to_string(&document).unwrap()
+                }
+                // no need to build a value tree of the whole abi first
+                None => serde_json::to_string(&origin_abi).unwrap(),
+            }
+        };
+        profile.add("serialize", serialize_start.elapsed());
+        let written = annotator.finish();
+        if written {
+            profile.time("write", || {
+                sink::write_all(&sinks, &new_abi, &annotator.functions)
+            });
+        }
+        written
+    };
+    if let (Some(key), true) = (&args.sign_key, written) {
+        for abi_file in &abi_files {
+            signing::sign(abi_file, key.as_deref());
+        }
+    }
+    if args.show_diff || args.dry_run {
+        diff::print_diff(&annotator.functions);
+    }
+    if args.stats {
+        report::print_stats(&annotator.functions);
+    }
+    report::print_summary(abi, &annotator);
+    if args.profile {
+        profile.print();
+    }
+    if let Some(sarif) = &args.sarif {
+        sarif::write_sarif(sarif, abi, &sarif::annotation_findings(&annotator));
+    }
+    let mut too_hot = false;
+    if args.hotspots.is_some() || args.hotspot_threshold.is_some() {
+        let hotspots = slots::hotspots(&annotator.functions, &environments);
+        if let Some(top) = args.hotspots {
+            slots::print_hotspots(&hotspots, top);
+        }
+        let threshold = args.hotspot_threshold.unwrap_or(usize::MAX);
+        for hotspot in hotspots.iter().filter(|hotspot| hotspot.slot) {
+            if hotspot.mutating > threshold {
+                too_hot = true;
+                eprintln!(
+                    "{}: {} is shared by {} mutating functions, more than {}",
+                    "error".red(),
+                    hotspot.source,
+                    hotspot.mutating,
+                    threshold
+                );
+            }
+        }
+    }
+    let outputs = if args.stream || args.low_memory {
+        vec![abi.display().to_string()]
+    } else {
+        sinks
             .iter()
-            .find(|v| v["name"].as_str().unwrap() == "simpleMapWriteWithPartStructFuncArgKey2")
-            .unwrap()
-            .as_object()
-            .unwrap();
-        let simple_map_write_with_part_struct_func_arg_key_sig =
-            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
-        assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_sig,
-            "simpleMapWriteWithPartStructFuncArgKey2(uint256,(uint256,uint256))".to_string()
-        );
-        let simple_map_write_with_part_struct_func_arg_key_selector =
-            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
-        assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_selector,
-            u32::from_str_radix("e407048d", 16).unwrap()
-        );
-        let simple_map_write_with_part_struct_func_arg_key = functions
+            .map(|sink| sink.name())
+            .collect::<Vec<String>>()
+    };
+    if let (Some(run_manifest), Some(abi_sha256)) = (&args.run_manifest, &abi_sha256) {
+        let path = run_manifest
+            .clone()
+            .unwrap_or_else(|| abi.with_file_name(manifest::MANIFEST_FILE));
+        let inputs = inputs
             .iter()
-            .find(|v| v["name"].as_str().unwrap() == "simpleMapWriteWithPartStructFuncArgKey3")
-            .unwrap()
-            .as_object()
-            .unwrap();
-        let simple_map_write_with_part_struct_func_arg_key_sig =
-            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
-        assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_sig,
-            "simpleMapWriteWithPartStructFuncArgKey3(uint256,(uint256,(string,(uint256,uint256),string),uint256),uint256)"
-                .to_string()
+            .chain(&args.config)
+            .chain(&args.script)
+            .chain(&args.caller_layout)
+            .cloned()
+            .collect::<Vec<std::path::PathBuf>>();
+        let arguments = std::env::args().skip(1).collect::<Vec<String>>();
+        let manifest = manifest::manifest(
+            (abi, abi_sha256),
+            &inputs,
+            &outputs,
+            &arguments,
+            written,
+            &annotator,
+            annotator.failed() || too_hot,
         );
-        let simple_map_write_with_part_struct_func_arg_key_selector =
-            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
-        assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_selector,
-            u32::from_str_radix("3894de57", 16).unwrap()
+        manifest::write_manifest(&path, &manifest);
+    }
+    if annotator.failed() || too_hot {
+        std::process::exit(1);
+    }
+    // the status goes to stderr, stdout may be the abi of a stdout sink
+    if args.dry_run {
+        eprintln!(
+            "dry run, {} is left untouched",
+            format!("{}", abi.display()).green()
         );
-
-        let simple_map_write_with_part_struct_func_arg_key = functions
-            .iter()
-            .find(|v| v["name"].as_str().unwrap() == "simpleMapWriteWithPartStructFuncArgKey4")
-            .unwrap()
-            .as_object()
-            .unwrap();
-        let simple_map_write_with_part_struct_func_arg_key_sig =
-            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
-        assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_sig,
-            "simpleMapWriteWithPartStructFuncArgKey4(uint256,(string,(uint256,uint256),string))"
-                .to_string()
+        return;
+    }
+    if let (Some(ProvenanceMode::Sidecar), Some(provenance)) = (args.provenance, provenance) {
+        provenance::write_sidecar(abi, provenance);
+    }
+    if let Some(parallel_config) = &args.parallel_config {
+        report::write_parallel_config(parallel_config, &annotator.functions);
+    }
+    if let Some(java_parallel_config) = &args.java_parallel_config {
+        report::write_java_parallel_config(java_parallel_config, abi, &annotator.functions);
+    }
+    if let Some(go_descriptor) = &args.go_descriptor {
+        gosdk::write_go_descriptor(go_descriptor, abi, &annotator.functions, args.gm);
+    }
+    if let Some(slot_index) = &args.slot_index {
+        slots::write_slot_index(
+            slot_index,
+            &annotator.functions,
+            args.storage_layout.as_deref(),
         );
-        let simple_map_write_with_part_struct_func_arg_key_selector =
-            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
-        assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_selector,
-            u32::from_str_radix("9f440047", 16).unwrap()
+    }
+    if let Some(selectors_map) = &args.selectors_map {
+        let path = selectors_map
+            .clone()
+            .unwrap_or_else(|| abi.with_file_name("selectors.map"));
+        hashtable::write_selectors_map(&path, abi, &annotator.functions);
+    }
+    match (&args.report, &args.report_template) {
+        (Some(report), Some(template)) => {
+            report::write_template(report, template, abi, &annotator.functions, &superseded)
+        }
+        (Some(report), None) => {
+            report::write_markdown(report, abi, &annotator.functions, &superseded)
+        }
+        _ => {}
+    }
+    if !written {
+        eprintln!(
+            "conflicts of all {} functions unchanged, {} is left untouched",
+            annotator.unchanged,
+            format!("{}", abi.display()).green()
         );
+        return;
+    }
+    eprintln!(
+        "parse csv and rewrite abi successfully, written to {}",
+        outputs.join(", ").green()
+    );
+}
 
-        let simple_map_write_with_part_struct_func_arg_key = functions
-            .iter()
-            .find(|v| v["name"].as_str().unwrap() == "simpleMapWriteWithPartStructFuncArgKey5")
-            .unwrap()
-            .as_object()
-            .unwrap();
-        let simple_map_write_with_part_struct_func_arg_key_sig =
-            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
-        assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_sig,
-            "simpleMapWriteWithPartStructFuncArgKey5(uint256,uint256[],uint256)".to_string()
-        );
-        let simple_map_write_with_part_struct_func_arg_key_selector =
-            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
-        assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_selector,
-            u32::from_str_radix("5a0495fd", 16).unwrap()
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let simple_map_write_with_part_struct_func_arg_key = functions
-            .iter()
-            .find(|v| v["name"].as_str().unwrap() == "simpleMapWriteWithPartStructFuncArgKey6")
-            .unwrap()
-            .as_object()
-            .unwrap();
-        let simple_map_write_with_part_struct_func_arg_key_sig =
-            get_method_signature(simple_map_write_with_part_struct_func_arg_key);
+    #[test]
+    fn test_contract_path() {
+        let abi = std::path::Path::new("build/Token.abi");
         assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_sig,
-            "simpleMapWriteWithPartStructFuncArgKey6(uint256,uint256[5],uint256)".to_string()
+            contract_path(std::path::Path::new("analysis/{contract}/"), abi),
+            std::path::PathBuf::from("analysis/Token/")
         );
-        let simple_map_write_with_part_struct_func_arg_key_selector =
-            get_method_id(&simple_map_write_with_part_struct_func_arg_key_sig, false);
         assert_eq!(
-            simple_map_write_with_part_struct_func_arg_key_selector,
-            u32::from_str_radix("d07c919a", 16).unwrap()
+            contract_path(std::path::Path::new("analysis"), abi),
+            std::path::PathBuf::from("analysis")
         );
     }
 }
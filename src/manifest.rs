@@ -0,0 +1,121 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::annotate::{Annotator, Warning};
+use crate::conflict::CONFLICT_FIELDS_VERSION;
+use crate::provenance::sha256_file;
+
+/// The file name of the manifest next to the abi, when no path is given.
+pub const MANIFEST_FILE: &str = "run-manifest.json";
+
+/// A file of the run with its digest, a missing one has none.
+fn file(path: &Path) -> Value {
+    json!({
+        "path": path.to_string_lossy().replace('\\', "/"),
+        "sha256": path.is_file().then(|| sha256_file(path)),
+    })
+}
+
+fn warning(warning: &Warning) -> Value {
+    json!({
+        "rule": warning.rule,
+        "signature": warning.signature,
+        "message": warning.message,
+    })
+}
+
+/// What went into the run and what came out of it. It has no timestamp and keys are sorted, so
+/// the same inputs and arguments always give the same manifest. `failed` is whether the run
+/// exits non-zero, for the checks after the annotation too.
+pub fn manifest(
+    abi: (&Path, &str),
+    inputs: &[PathBuf],
+    outputs: &[String],
+    arguments: &[String],
+    written: bool,
+    annotator: &Annotator,
+    failed: bool,
+) -> Value {
+    let functions = annotator
+        .functions
+        .iter()
+        .map(|function| {
+            let outcome = if function.previous_fields == function.fields {
+                "unchanged"
+            } else {
+                "rewritten"
+            };
+            json!({
+                "signature": function.signature,
+                "selector": format!("0x{:08x}", function.selector),
+                "conflicts": function.conflicts.len(),
+                "outcome": outcome,
+            })
+        })
+        .collect::<Vec<Value>>();
+    let outputs = if written {
+        outputs
+            .iter()
+            .map(|output| file(Path::new(output)))
+            .collect::<Vec<Value>>()
+    } else {
+        Vec::new()
+    };
+    json!({
+        "tool": env!("CARGO_PKG_NAME"),
+        "toolVersion": env!("CARGO_PKG_VERSION"),
+        "conflictFieldsVersion": CONFLICT_FIELDS_VERSION,
+        "hashAlgorithm": if annotator.gm { "sm3" } else { "keccak256" },
+        "arguments": arguments,
+        "abi": {
+            "path": abi.0.to_string_lossy().replace('\\', "/"),
+            "sha256": abi.1,
+        },
+        "inputs": inputs.iter().map(|input| file(input)).collect::<Vec<Value>>(),
+        "outputs": outputs,
+        "functions": functions,
+        "warnings": annotator.warnings.iter().map(warning).collect::<Vec<Value>>(),
+        "errors": annotator.errors.iter().map(warning).collect::<Vec<Value>>(),
+        "failed": failed,
+    })
+}
+
+pub fn write_manifest(path: &Path, manifest: &Value) {
+    let content = serde_json::to_string_pretty(manifest).unwrap();
+    std::fs::write(path, content)
+        .unwrap_or_else(|e| panic!("could not write {}: {}", path.display(), e));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::{ConflictInfo, ConflictType};
+    use crate::options::AnnotationOptions;
+
+    #[test]
+    fn test_manifest() {
+        let conflicts = vec![ConflictInfo::new(ConflictType::All, 7)];
+        let mut annotator = AnnotationOptions::new().annotator(&conflicts);
+        annotator.warnings.push(Warning {
+            rule: "unknown-environment",
+            signature: String::from("f()"),
+            message: String::new(),
+        });
+        let arguments = vec![String::from("--abi"), String::from("a.abi")];
+        let manifest = manifest(
+            (Path::new("a.abi"), "00"),
+            &[],
+            &[String::from("a.abi")],
+            &arguments,
+            false,
+            &annotator,
+            annotator.failed(),
+        );
+        assert_eq!(manifest["abi"]["sha256"], "00");
+        assert_eq!(manifest["arguments"], json!(arguments));
+        assert_eq!(manifest["outputs"], json!([]));
+        assert_eq!(manifest["warnings"][0]["rule"], "unknown-environment");
+        assert!(!manifest["failed"].as_bool().unwrap());
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use colored::Colorize;
+
+use crate::abi::AbiEntry;
+use crate::conflict::{
+    conflict_fields, ConflictInfo, ConflictType, SlotFormat, CONFLICT_FIELDS_VERSION,
+};
+use crate::get_method_signature;
+
+/// How the conflicts of a function annotated in both abis are combined.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergePolicy {
+    /// the conflicts found by either run
+    Union,
+    /// only the conflicts both runs found, where All stands for any
+    Intersect,
+}
+
+impl FromStr for MergePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "union" => Ok(MergePolicy::Union),
+            "intersect" => Ok(MergePolicy::Intersect),
+            _ => Err(format!("unknown merge policy {}", s)),
+        }
+    }
+}
+
+pub(crate) fn read_entries(abi: &Path) -> Vec<AbiEntry> {
+    let abi_content = std::fs::read_to_string(abi)
+        .unwrap_or_else(|_| panic!("could not read file {}", abi.display()));
+    serde_json::from_str(&abi_content).unwrap()
+}
+
+/// The conflicts in the annotation of `entry`, none if it isn't annotated.
+pub(crate) fn conflicts_of(entry: &AbiEntry) -> Option<Vec<ConflictInfo>> {
+    let fields = entry.extra.get("conflictFields")?;
+    Some(serde_json::from_value(fields.clone()).unwrap_or_else(|e| {
+        panic!(
+            "invalid conflictFields of {}: {}",
+            get_method_signature(entry),
+            e
+        )
+    }))
+}
+
+fn has_all(conflicts: &[ConflictInfo]) -> bool {
+    conflicts
+        .iter()
+        .any(|conflict| conflict.kind == ConflictType::All)
+}
+
+/// All serializes the function whatever else it has, so it keeps the conflicts of the other side
+/// when intersecting instead of leaving none. None only stays if nothing else is found.
+fn merge_conflicts(
+    base: Option<Vec<ConflictInfo>>,
+    other: Option<Vec<ConflictInfo>>,
+    policy: MergePolicy,
+) -> Option<Vec<ConflictInfo>> {
+    match (base, other, policy) {
+        (Some(mut base), Some(other), MergePolicy::Union) => {
+            for conflict in other {
+                if !base.contains(&conflict) {
+                    base.push(conflict);
+                }
+            }
+            if base
+                .iter()
+                .any(|conflict| conflict.kind != ConflictType::None)
+            {
+                base.retain(|conflict| conflict.kind != ConflictType::None);
+            }
+            Some(base)
+        }
+        (Some(base), Some(other), MergePolicy::Intersect) if has_all(&base) => Some(other),
+        (Some(base), Some(other), MergePolicy::Intersect) if has_all(&other) => Some(base),
+        (Some(base), Some(other), MergePolicy::Intersect) => Some(
+            base.into_iter()
+                .filter(|conflict| other.contains(conflict))
+                .collect(),
+        ),
+        (base, other, MergePolicy::Union) => base.or(other),
+        (_, _, MergePolicy::Intersect) => None,
+    }
+}
+
+/// Combines the conflictFields of `other` into the functions of `base` with the same signature.
+/// Returns the number of functions whose conflicts changed.
+fn merge_entries(
+    base: &mut [AbiEntry],
+    other: &[AbiEntry],
+    policy: MergePolicy,
+    slot_format: SlotFormat,
+) -> usize {
+    let others = other
+        .iter()
+        .filter(|entry| entry.is_function())
+        .map(|entry| (get_method_signature(entry), entry))
+        .collect::<BTreeMap<String, &AbiEntry>>();
+    let mut merged = 0;
+    for entry in base.iter_mut().filter(|entry| entry.is_function()) {
+        let other = others.get(&get_method_signature(entry)).copied();
+        let previous = conflicts_of(entry);
+        let conflicts = merge_conflicts(previous.clone(), other.and_then(conflicts_of), policy);
+        if conflicts == previous {
+            continue;
+        }
+        merged += 1;
+        entry.extra.remove("conflictFields");
+        entry.extra.remove("conflictFieldsVersion");
+        if let Some(conflicts) = conflicts {
+            entry.extra.insert(
+                "conflictFields".into(),
+                conflict_fields(&conflicts, slot_format),
+            );
+            entry.extra.insert(
+                "conflictFieldsVersion".into(),
+                CONFLICT_FIELDS_VERSION.into(),
+            );
+        }
+    }
+    merged
+}
+
+/// Writes `base` with the conflictFields of `other` merged in to `output`, for unifying
+/// annotations of different build variants.
+pub fn merge(
+    base: &Path,
+    other: &Path,
+    output: &Path,
+    policy: MergePolicy,
+    slot_format: SlotFormat,
+) {
+    let mut entries = read_entries(base);
+    let merged = merge_entries(&mut entries, &read_entries(other), policy, slot_format);
+    std::fs::write(output, serde_json::to_string(&entries).unwrap())
+        .unwrap_or_else(|e| panic!("could not write {}: {}", output.display(), e));
+    print!(
+        "merged conflictFields of {} functions into {}",
+        merged,
+        format!("{}", output.display()).green()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_entries() {
+        let base = r#"[
+            {"name": "a", "type": "function", "inputs": [],
+             "conflictFields": [{"kind": 0}], "conflictFieldsVersion": 2},
+            {"name": "b", "type": "function", "inputs": [],
+             "conflictFields": [{"kind": 3, "slot": "0xa", "value": [0]}]},
+            {"name": "c", "type": "function", "inputs": [],
+             "conflictFields": [{"kind": 5}]}
+        ]"#;
+        let other: Vec<AbiEntry> = serde_json::from_str(
+            r#"[
+                {"name": "a", "type": "function", "inputs": [],
+                 "conflictFields": [{"kind": 2, "value": [0]}], "conflictFieldsVersion": 2},
+                {"name": "b", "type": "function", "inputs": [],
+                 "conflictFields": [{"kind": 3, "slot": 10, "value": [0]}]},
+                {"name": "c", "type": "function", "inputs": [],
+                 "conflictFields": [{"kind": 2, "value": [0]}]}
+            ]"#,
+        )
+        .unwrap();
+
+        let mut union: Vec<AbiEntry> = serde_json::from_str(base).unwrap();
+        assert_eq!(
+            merge_entries(&mut union, &other, MergePolicy::Union, SlotFormat::Decimal),
+            2
+        );
+        assert_eq!(
+            union[0].extra["conflictFields"].to_string(),
+            r#"[{"kind":0},{"kind":2,"value":[0]}]"#
+        );
+        // None is dropped once the other side found conflicts
+        assert_eq!(
+            union[2].extra["conflictFields"].to_string(),
+            r#"[{"kind":2,"value":[0]}]"#
+        );
+
+        let mut intersect: Vec<AbiEntry> = serde_json::from_str(base).unwrap();
+        assert_eq!(
+            merge_entries(
+                &mut intersect,
+                &other,
+                MergePolicy::Intersect,
+                SlotFormat::Decimal
+            ),
+            2
+        );
+        // All intersected keeps the conflicts of the other side instead of leaving none
+        assert_eq!(
+            intersect[0].extra["conflictFields"].to_string(),
+            r#"[{"kind":2,"value":[0]}]"#
+        );
+        // the same slot written differently is the same conflict
+        assert!(intersect[1].extra["conflictFields"]
+            .to_string()
+            .contains("\"0xa\""));
+    }
+}
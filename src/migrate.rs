@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use colored::Colorize;
+use serde_json::Value;
+
+use crate::abi::AbiEntry;
+use crate::conflict::{conflict_fields, ConflictInfo, SlotFormat, CONFLICT_FIELDS_VERSION};
+
+/// Rewrites the conflictFields of every function in `abi` annotated by an older version in the
+/// current format, the analyzer outputs they came from are usually long gone. Malformed entries
+/// are left as they are.
+pub fn migrate(abi: &Path, slot_format: SlotFormat) -> Result<(), String> {
+    let abi_content = std::fs::read_to_string(abi)
+        .map_err(|e| format!("could not read file {}: {}", abi.display(), e))?;
+    let values: Vec<Value> = serde_json::from_str(&abi_content)
+        .map_err(|e| format!("invalid abi {}: {}", abi.display(), e))?;
+    let mut entries = values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            AbiEntry::parse(value).unwrap_or_else(|(message, opaque)| {
+                eprintln!(
+                    "{}: entry #{}: {}, left as is",
+                    "warning".yellow(),
+                    index,
+                    message
+                );
+                opaque
+            })
+        })
+        .collect::<Vec<AbiEntry>>();
+    let migrated = migrate_entries(&mut entries, slot_format)?;
+    if migrated > 0 {
+        std::fs::write(abi, serde_json::to_string(&entries).unwrap())
+            .map_err(|e| format!("could not write {}: {}", abi.display(), e))?;
+    }
+    print!(
+        "migrated conflictFields of {} functions in {}",
+        migrated,
+        format!("{}", abi.display()).green()
+    );
+    Ok(())
+}
+
+/// Returns the number of entries migrated. The kind codes never changed, older versions only
+/// differ in also writing kind names, hex string slots and scalar values.
+fn migrate_entries(entries: &mut [AbiEntry], slot_format: SlotFormat) -> Result<usize, String> {
+    let mut migrated = 0;
+    for entry in entries {
+        let fields = match entry.extra.get("conflictFields") {
+            Some(fields) => fields,
+            None => continue,
+        };
+        let version = entry
+            .extra
+            .get("conflictFieldsVersion")
+            .and_then(Value::as_u64);
+        if version == Some(CONFLICT_FIELDS_VERSION.into()) {
+            continue;
+        }
+        let conflicts: Vec<ConflictInfo> = serde_json::from_value(fields.clone()).map_err(|e| {
+            format!(
+                "could not migrate conflictFields of {}: {}",
+                entry.display_signature(),
+                e
+            )
+        })?;
+        entry.extra.insert(
+            "conflictFields".into(),
+            conflict_fields(&conflicts, slot_format),
+        );
+        entry.extra.insert(
+            "conflictFieldsVersion".into(),
+            CONFLICT_FIELDS_VERSION.into(),
+        );
+        migrated += 1;
+    }
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_entries() {
+        let mut entries: Vec<AbiEntry> = serde_json::from_str(
+            r#"[
+                {
+                    "conflictFields": [{"kind": "Var", "slot": "0x1", "value": 0}],
+                    "inputs": [],
+                    "name": "old",
+                    "type": "function"
+                },
+                {
+                    "conflictFields": [{"kind": 0}],
+                    "conflictFieldsVersion": 2,
+                    "inputs": [],
+                    "name": "current",
+                    "type": "function"
+                }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(migrate_entries(&mut entries, SlotFormat::Decimal), Ok(1));
+        assert_eq!(
+            entries[0].extra["conflictFields"].to_string(),
+            r#"[{"kind":3,"slot":1,"value":[0]}]"#
+        );
+        assert_eq!(entries[0].extra["conflictFieldsVersion"], 2);
+        assert_eq!(migrate_entries(&mut entries, SlotFormat::Decimal), Ok(0));
+        entries[1].extra.remove("conflictFieldsVersion");
+        entries[1]
+            .extra
+            .insert("conflictFields".into(), serde_json::json!([{"kind": 99}]));
+        assert!(migrate_entries(&mut entries, SlotFormat::Decimal)
+            .unwrap_err()
+            .contains("current()"));
+    }
+}
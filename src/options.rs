@@ -0,0 +1,218 @@
+use std::collections::BTreeSet;
+
+use crate::annotate::{Annotator, CapPolicy};
+use crate::cache::SelectorCache;
+use crate::conflict::{ConflictInfo, ConflictType, SlotFormat};
+use crate::hash::Hash;
+use crate::library::CallerLayout;
+use crate::rules::ConflictRule;
+
+/// How an abi is annotated, the defaults are those of the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotationOptions {
+    hash: Hash,
+    slot_format: SlotFormat,
+    param_ordinals: bool,
+    mark_parallel: bool,
+    default_all: bool,
+    mark_none: bool,
+    named_selectors: bool,
+    dry_run: bool,
+    max_conflicts: Option<usize>,
+    cap_policy: CapPolicy,
+    forbid_all: Option<Vec<String>>,
+    fail_on_warning: bool,
+    ignore_slots: BTreeSet<u32>,
+    kinds: Option<Vec<ConflictType>>,
+    rules: Vec<ConflictRule>,
+    expect_interface: bool,
+    library: Option<CallerLayout>,
+    only_selectors: BTreeSet<u32>,
+}
+
+impl Default for AnnotationOptions {
+    fn default() -> Self {
+        AnnotationOptions {
+            hash: Hash::Keccak256,
+            slot_format: SlotFormat::Decimal,
+            param_ordinals: false,
+            mark_parallel: false,
+            default_all: false,
+            mark_none: false,
+            named_selectors: false,
+            dry_run: false,
+            max_conflicts: None,
+            cap_policy: CapPolicy::Truncate,
+            forbid_all: None,
+            fail_on_warning: false,
+            ignore_slots: BTreeSet::new(),
+            kinds: None,
+            rules: Vec::new(),
+            expect_interface: false,
+            library: None,
+            only_selectors: BTreeSet::new(),
+        }
+    }
+}
+
+impl AnnotationOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The hash algorithm selectors are matched by.
+    pub fn hash(mut self, hash: Hash) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    pub fn slot_format(mut self, slot_format: SlotFormat) -> Self {
+        self.slot_format = slot_format;
+        self
+    }
+
+    pub fn param_ordinals(mut self, param_ordinals: bool) -> Self {
+        self.param_ordinals = param_ordinals;
+        self
+    }
+
+    pub fn mark_parallel(mut self, mark_parallel: bool) -> Self {
+        self.mark_parallel = mark_parallel;
+        self
+    }
+
+    pub fn default_all(mut self, default_all: bool) -> Self {
+        self.default_all = default_all;
+        self
+    }
+
+    pub fn mark_none(mut self, mark_none: bool) -> Self {
+        self.mark_none = mark_none;
+        self
+    }
+
+    pub fn named_selectors(mut self, named_selectors: bool) -> Self {
+        self.named_selectors = named_selectors;
+        self
+    }
+
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Caps the conflicts of a function, none for no cap.
+    pub fn max_conflicts(mut self, max_conflicts: Option<usize>, policy: CapPolicy) -> Self {
+        self.max_conflicts = max_conflicts;
+        self.cap_policy = policy;
+        self
+    }
+
+    /// Fails on functions matching the patterns, or any if empty, serialized with All.
+    pub fn forbid_all(mut self, patterns: Option<Vec<String>>) -> Self {
+        self.forbid_all = patterns;
+        self
+    }
+
+    pub fn fail_on_warning(mut self, fail_on_warning: bool) -> Self {
+        self.fail_on_warning = fail_on_warning;
+        self
+    }
+
+    /// Drops the conflicts on these slots.
+    pub fn ignore_slots(mut self, slots: BTreeSet<u32>) -> Self {
+        self.ignore_slots = slots;
+        self
+    }
+
+    /// Only keeps the conflicts of these kinds, all if none.
+    pub fn kinds(mut self, kinds: Option<Vec<ConflictType>>) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    /// Rules adding conflicts to the functions matching them.
+    pub fn rules(mut self, rules: Vec<ConflictRule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Fails the run as the abi is of an interface or abstract contract.
+    pub fn expect_interface(mut self, expect_interface: bool) -> Self {
+        self.expect_interface = expect_interface;
+        self
+    }
+
+    /// The abi is of a library called from a contract of this storage layout.
+    pub fn library(mut self, caller_layout: Option<CallerLayout>) -> Self {
+        self.library = caller_layout;
+        self
+    }
+
+    /// Only annotates the functions with these selectors, all if empty.
+    pub fn only_selectors(mut self, selectors: BTreeSet<u32>) -> Self {
+        self.only_selectors = selectors;
+        self
+    }
+
+    /// An annotator of `conflicts`, sorted by selector, without caches, provenance, reviewer or
+    /// script, which can be set on it afterwards.
+    pub fn annotator(self, conflicts: &[ConflictInfo]) -> Annotator {
+        Annotator {
+            conflicts,
+            gm: self.hash == Hash::Sm3,
+            slot_format: self.slot_format,
+            param_ordinals: self.param_ordinals,
+            mark_parallel: self.mark_parallel,
+            default_all: self.default_all,
+            mark_none: self.mark_none,
+            cache: None,
+            selectors: SelectorCache::default(),
+            unchanged: 0,
+            rewritten: 0,
+            functions: Vec::new(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            provenance: None,
+            dry_run: self.dry_run,
+            reviewer: None,
+            script: None,
+            named_selectors: self.named_selectors,
+            max_conflicts: self.max_conflicts,
+            cap_policy: self.cap_policy,
+            forbid_all: self.forbid_all,
+            fail_on_warning: self.fail_on_warning,
+            ignore_slots: self.ignore_slots,
+            ignored: 0,
+            kinds: self.kinds,
+            rules: self.rules,
+            expect_interface: self.expect_interface,
+            covered_mutating: 0,
+            library: self.library,
+            only_selectors: self.only_selectors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotator() {
+        let annotator = AnnotationOptions::new().annotator(&[]);
+        assert!(!annotator.gm);
+        assert_eq!(annotator.max_conflicts, None);
+        let annotator = AnnotationOptions::new()
+            .hash(Hash::Sm3)
+            .max_conflicts(Some(1), CapPolicy::Fail)
+            .dry_run(true)
+            .annotator(&[]);
+        assert!(annotator.gm);
+        assert_eq!(
+            (annotator.max_conflicts, annotator.cap_policy),
+            (Some(1), CapPolicy::Fail)
+        );
+        assert!(annotator.dry_run);
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt;
+
+use crate::annotate::FunctionSummary;
+use crate::conflict::{ConflictInfo, ConflictType};
+
+/// How well the calls of a function run in parallel, from best to worst.
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Parallelism {
+    /// no conflicts at all
+    FullyParallel,
+    /// only calls with the same arguments at the key positions conflict
+    KeyPartitioned,
+    /// calls conflict on an environment value like msg.sender
+    EnvSerialized,
+    /// every call conflicts, on everything or on the same fixed slots
+    FullySerialized,
+}
+
+pub(crate) const PARALLELISMS: [Parallelism; 4] = [
+    Parallelism::FullyParallel,
+    Parallelism::KeyPartitioned,
+    Parallelism::EnvSerialized,
+    Parallelism::FullySerialized,
+];
+
+impl Parallelism {
+    /// The worst parallelism any of the conflicts allows.
+    pub(crate) fn of(conflicts: &[ConflictInfo]) -> Parallelism {
+        conflicts
+            .iter()
+            .map(|conflict| match conflict.kind {
+                ConflictType::None => Parallelism::FullyParallel,
+                ConflictType::Var => Parallelism::KeyPartitioned,
+                ConflictType::Env => Parallelism::EnvSerialized,
+                ConflictType::All | ConflictType::Len | ConflictType::Const => {
+                    Parallelism::FullySerialized
+                }
+            })
+            .max()
+            .unwrap_or(Parallelism::FullyParallel)
+    }
+}
+
+/// The number of leading arguments forming the conflict key of a key-partitioned function, the
+/// critical size `registerParallelFunction` of BCOS 2.x takes. An error if the key parameters
+/// are no prefix of the arguments, which the legacy configuration can't express.
+pub(crate) fn critical_size(function: &FunctionSummary) -> Option<Result<usize, String>> {
+    if Parallelism::of(&function.conflicts) != Parallelism::KeyPartitioned {
+        return None;
+    }
+    Some(match &function.key_params {
+        Some(params) if params.iter().enumerate().all(|(i, param)| i == *param) => Ok(params.len()),
+        Some(params) => Err(format!(
+            "the key parameters {:?} are no prefix of the arguments",
+            params
+        )),
+        None => Err(String::from("the key is not made of parameters")),
+    })
+}
+
+impl fmt::Display for Parallelism {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Parallelism::FullyParallel => "fully-parallel",
+            Parallelism::KeyPartitioned => "key-partitioned",
+            Parallelism::EnvSerialized => "env-serialized",
+            Parallelism::FullySerialized => "fully-serialized",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallelism() {
+        let conflict = |kind| ConflictInfo::new(kind, 0);
+        assert_eq!(Parallelism::of(&[]), Parallelism::FullyParallel);
+        assert_eq!(
+            Parallelism::of(&[conflict(ConflictType::None)]),
+            Parallelism::FullyParallel
+        );
+        assert_eq!(
+            Parallelism::of(&[conflict(ConflictType::Var), conflict(ConflictType::Env)]),
+            Parallelism::EnvSerialized
+        );
+        assert_eq!(
+            Parallelism::of(&[conflict(ConflictType::Const), conflict(ConflictType::Var)]),
+            Parallelism::FullySerialized
+        );
+    }
+
+    #[test]
+    fn test_critical_size() {
+        let conflicts = vec![ConflictInfo::new(ConflictType::Var, 0)];
+        let mut function =
+            FunctionSummary::for_test("transfer(address,address,uint256)", conflicts);
+        function.key_params = Some(vec![0, 1]);
+        assert_eq!(critical_size(&function), Some(Ok(2)));
+        function.key_params = Some(vec![1]);
+        assert!(matches!(critical_size(&function), Some(Err(_))));
+        function
+            .conflicts
+            .push(ConflictInfo::new(ConflictType::All, 0));
+        assert_eq!(critical_size(&function), None);
+    }
+}
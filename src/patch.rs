@@ -0,0 +1,455 @@
+use serde_json::Value;
+
+use crate::abi::AbiEntry;
+
+/// The keys an annotation writes into a function, everything else is left untouched.
+pub(crate) const MANAGED_KEYS: [&str; 5] = [
+    "conflictFields",
+    "conflictFieldsVersion",
+    "selector",
+    "selectors",
+    "parallel",
+];
+
+/// Byte positions of a `"key": value` member of an object.
+struct Member {
+    key: String,
+    start: usize,
+    colon: usize,
+    value_start: usize,
+    value_end: usize,
+}
+
+/// Byte positions of an element of the abi array, members are only collected for objects.
+struct Element {
+    start: usize,
+    end: usize,
+    open: Option<usize>,
+    members: Vec<Member>,
+}
+
+struct Scanner<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> u8 {
+        *self
+            .text
+            .as_bytes()
+            .get(self.pos)
+            .unwrap_or_else(|| panic!("unexpected end of json"))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.text.len() && self.peek().is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) {
+        self.skip_whitespace();
+        if self.peek() != byte {
+            panic!(
+                "expected {:?} at byte {} of the json",
+                byte as char, self.pos
+            );
+        }
+        self.pos += 1;
+    }
+
+    /// Skips whitespace, then `byte` if it comes next.
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.pos < self.text.len() && self.peek() == byte;
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn string(&mut self) -> String {
+        self.skip_whitespace();
+        let start = self.pos;
+        self.expect(b'"');
+        loop {
+            match self.peek() {
+                b'\\' => self.pos += 2,
+                b'"' => break,
+                _ => self.pos += 1,
+            }
+        }
+        self.pos += 1;
+        serde_json::from_str(&self.text[start..self.pos]).unwrap()
+    }
+
+    fn value(&mut self) {
+        self.skip_whitespace();
+        match self.peek() {
+            b'"' => {
+                self.string();
+            }
+            b'{' => {
+                self.pos += 1;
+                if !self.eat(b'}') {
+                    loop {
+                        self.string();
+                        self.expect(b':');
+                        self.value();
+                        if !self.eat(b',') {
+                            break;
+                        }
+                    }
+                    self.expect(b'}');
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                if !self.eat(b']') {
+                    loop {
+                        self.value();
+                        if !self.eat(b',') {
+                            break;
+                        }
+                    }
+                    self.expect(b']');
+                }
+            }
+            _ => {
+                while self.pos < self.text.len()
+                    && !matches!(self.peek(), b',' | b']' | b'}')
+                    && !self.peek().is_ascii_whitespace()
+                {
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Moves to the value `pointer` refers to.
+    fn pointer(&mut self, pointer: &str) {
+        for token in pointer.split('/').skip(1) {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            self.skip_whitespace();
+            match self.peek() {
+                b'{' => {
+                    self.pos += 1;
+                    loop {
+                        if self.string() == token {
+                            self.expect(b':');
+                            break;
+                        }
+                        self.expect(b':');
+                        self.value();
+                        self.expect(b',');
+                    }
+                }
+                b'[' => {
+                    self.pos += 1;
+                    let index: usize = token
+                        .parse()
+                        .unwrap_or_else(|_| panic!("invalid array index {}", token));
+                    for _ in 0..index {
+                        self.value();
+                        self.expect(b',');
+                    }
+                }
+                _ => panic!("{} not found", pointer),
+            }
+        }
+        self.skip_whitespace();
+    }
+
+    fn elements(&mut self) -> (Vec<Element>, usize) {
+        self.expect(b'[');
+        let mut elements = Vec::new();
+        if self.eat(b']') {
+            return (elements, self.pos - 1);
+        }
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            let mut element = Element {
+                start,
+                end: start,
+                open: None,
+                members: Vec::new(),
+            };
+            if self.peek() == b'{' {
+                element.open = Some(start);
+                self.pos += 1;
+                if !self.eat(b'}') {
+                    loop {
+                        self.skip_whitespace();
+                        let member_start = self.pos;
+                        let key = self.string();
+                        let colon = self.pos;
+                        self.expect(b':');
+                        self.skip_whitespace();
+                        let value_start = self.pos;
+                        self.value();
+                        element.members.push(Member {
+                            key,
+                            start: member_start,
+                            colon,
+                            value_start,
+                            value_end: self.pos,
+                        });
+                        if !self.eat(b',') {
+                            break;
+                        }
+                    }
+                    self.expect(b'}');
+                }
+            } else {
+                self.value();
+            }
+            element.end = self.pos;
+            elements.push(element);
+            if !self.eat(b',') {
+                break;
+            }
+        }
+        self.expect(b']');
+        (elements, self.pos - 1)
+    }
+}
+
+/// Writes the annotation of `entries` into the abi array of `text` at `pointer` by patching only
+/// the managed keys, so whitespace, indentation and key order elsewhere survive and diffs of
+/// version-controlled artifacts show nothing but the annotation. `entries` are the entries of the
+/// array in order; with a `provenance` it replaces the provenance entry of a previous run.
+pub fn patch_abi(
+    text: &str,
+    pointer: Option<&str>,
+    entries: &[AbiEntry],
+    provenance: Option<&AbiEntry>,
+) -> String {
+    let mut scanner = Scanner { text, pos: 0 };
+    if let Some(pointer) = pointer {
+        scanner.pointer(pointer);
+    }
+    let (elements, close) = scanner.elements();
+    assert_eq!(
+        elements.len(),
+        entries.len(),
+        "the abi entries in the text don't match the parsed ones"
+    );
+
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    let removed = entries
+        .iter()
+        .map(|entry| provenance.is_some() && entry.is_provenance())
+        .collect::<Vec<bool>>();
+    remove_spans(
+        &elements,
+        &removed,
+        |element| (element.start, element.end),
+        &mut edits,
+    );
+    for (element, entry) in elements.iter().zip(entries) {
+        if element.open.is_some() && entry.is_function() {
+            patch_object(text, element, entry, &mut edits);
+        }
+    }
+    if let Some(provenance) = provenance {
+        let json = serde_json::to_string(provenance).unwrap();
+        let kept = elements
+            .iter()
+            .zip(&removed)
+            .filter(|(_, removed)| !**removed)
+            .map(|(element, _)| element)
+            .collect::<Vec<&Element>>();
+        match kept.last() {
+            Some(last) => {
+                let separator = whitespace_before(text, last.start);
+                edits.push((last.end, last.end, format!(",{}{}", separator, json)));
+            }
+            None => edits.push((close, close, json)),
+        }
+    }
+
+    edits.sort_by_key(|(start, end, _)| (*start, *end));
+    let mut patched = String::with_capacity(text.len());
+    let mut pos = 0;
+    for (start, end, replacement) in edits {
+        patched.push_str(&text[pos..start]);
+        patched.push_str(&replacement);
+        pos = end;
+    }
+    patched.push_str(&text[pos..]);
+    patched
+}
+
+fn patch_object(
+    text: &str,
+    element: &Element,
+    entry: &AbiEntry,
+    edits: &mut Vec<(usize, usize, String)>,
+) {
+    let members = &element.members;
+    let removed = members
+        .iter()
+        .map(|member| {
+            MANAGED_KEYS.contains(&member.key.as_str()) && !entry.extra.contains_key(&member.key)
+        })
+        .collect::<Vec<bool>>();
+    remove_spans(
+        members,
+        &removed,
+        |member| (member.start, member.value_end),
+        edits,
+    );
+    let kept = members
+        .iter()
+        .zip(&removed)
+        .filter(|(_, removed)| !**removed)
+        .map(|(member, _)| member)
+        .collect::<Vec<&Member>>();
+    for member in &kept {
+        let value = match entry.extra.get(&member.key) {
+            Some(value) if MANAGED_KEYS.contains(&member.key.as_str()) => value,
+            _ => continue,
+        };
+        let current: Value =
+            serde_json::from_str(&text[member.value_start..member.value_end]).unwrap();
+        if current != *value {
+            edits.push((
+                member.value_start,
+                member.value_end,
+                serde_json::to_string(value).unwrap(),
+            ));
+        }
+    }
+    let colon = members
+        .first()
+        .map_or(":", |member| &text[member.colon..member.value_start]);
+    let insert_at = kept
+        .last()
+        .map_or(element.open.unwrap() + 1, |last| last.value_end);
+    let mut separator = kept.last().map_or(String::new(), |last| {
+        format!(",{}", whitespace_before(text, last.start))
+    });
+    for key in MANAGED_KEYS.iter() {
+        if members.iter().any(|member| member.key == *key) {
+            continue;
+        }
+        if let Some(value) = entry.extra.get(*key) {
+            edits.push((
+                insert_at,
+                insert_at,
+                format!(
+                    "{}{}{}{}",
+                    separator,
+                    serde_json::to_string(key).unwrap(),
+                    colon,
+                    serde_json::to_string(value).unwrap()
+                ),
+            ));
+            // edits at the same position keep their order
+            if separator.is_empty() {
+                separator = String::from(",");
+            }
+        }
+    }
+}
+
+/// The whitespace in front of `start`, what separates the items of an array or object.
+fn whitespace_before(text: &str, start: usize) -> &str {
+    let before = &text[..start];
+    &before[before.trim_end().len()..]
+}
+
+/// Removes the items marked in `removed` together with the comma separating them from the rest.
+fn remove_spans<T, F: Fn(&T) -> (usize, usize)>(
+    items: &[T],
+    removed: &[bool],
+    span: F,
+    edits: &mut Vec<(usize, usize, String)>,
+) {
+    let first_kept = removed.iter().position(|removed| !removed);
+    for (index, item) in items.iter().enumerate() {
+        if !removed[index] {
+            continue;
+        }
+        match first_kept {
+            // removing a leading item takes the comma after it
+            Some(first_kept) if index < first_kept => {
+                if index == 0 {
+                    edits.push((span(item).0, span(&items[first_kept]).0, String::new()));
+                }
+            }
+            _ if index == 0 => {
+                edits.push((span(item).0, span(&items[items.len() - 1]).1, String::new()));
+                return;
+            }
+            _ => edits.push((span(&items[index - 1]).1, span(item).1, String::new())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patch_abi() {
+        let text = r#"{
+  "contractName": "Token",
+  "abi": [
+    {
+      "inputs": [],
+      "name": "a",
+      "selector": [1, 2],
+      "type": "function"
+    },
+    {
+      "inputs": [],
+      "name": "b",
+      "type": "function",
+      "conflictFields": [{"kind": 0}]
+    },
+    {"type": "conflictsProvenance", "tool": "old"}
+  ]
+}"#;
+        let mut entries: Vec<AbiEntry> = serde_json::from_str(
+            r#"[
+                {"inputs": [], "name": "a", "selector": [1, 3], "type": "function",
+                 "conflictFields": [{"kind": 5}]},
+                {"inputs": [], "name": "b", "type": "function", "selector": [4, 5]},
+                {"type": "conflictsProvenance", "tool": "old"}
+            ]"#,
+        )
+        .unwrap();
+        let provenance: AbiEntry =
+            serde_json::from_str(r#"{"type": "conflictsProvenance", "tool": "new"}"#).unwrap();
+        let patched = patch_abi(text, Some("/abi"), &entries, Some(&provenance));
+        assert_eq!(
+            patched,
+            r#"{
+  "contractName": "Token",
+  "abi": [
+    {
+      "inputs": [],
+      "name": "a",
+      "selector": [1,3],
+      "type": "function",
+      "conflictFields": [{"kind":5}]
+    },
+    {
+      "inputs": [],
+      "name": "b",
+      "type": "function",
+      "selector": [4,5]
+    },
+    {"type":"conflictsProvenance","tool":"new"}
+  ]
+}"#
+        );
+        entries.truncate(1);
+        let unchanged = r#"[ {"name": "a", "type": "function", "selector": [1, 3],
+            "conflictFields": [{"kind": 5}]} ]"#;
+        assert_eq!(patch_abi(unchanged, None, &entries, None), unchanged);
+    }
+}
@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+
+use colored::Colorize;
+
+/// How long every phase of the run took, to tell slow analyzer outputs from slow hashing.
+#[derive(Default)]
+pub struct Profile {
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Profile {
+    /// Runs `f` as part of `phase`.
+    pub fn time<T>(&mut self, phase: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.add(phase, start.elapsed());
+        result
+    }
+
+    /// Adds `duration` to `phase`, phases are listed in the order they are first added.
+    pub fn add(&mut self, phase: &'static str, duration: Duration) {
+        match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += duration,
+            None => self.phases.push((phase, duration)),
+        }
+    }
+
+    /// Takes `duration`, measured inside `phase`, out of it into `part`.
+    pub fn split(&mut self, phase: &'static str, part: &'static str, duration: Duration) {
+        if let Some((_, total)) = self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            *total = total.saturating_sub(duration);
+        }
+        self.add(part, duration);
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let total = self
+            .phases
+            .iter()
+            .map(|(_, duration)| *duration)
+            .sum::<Duration>();
+        let width = self
+            .phases
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        self.phases
+            .iter()
+            .map(|(name, duration)| {
+                let share = if total.as_nanos() == 0 {
+                    0.0
+                } else {
+                    duration.as_secs_f64() / total.as_secs_f64() * 100.0
+                };
+                format!(
+                    "{:>width$}: {:>9.3} ms {:>5.1}%",
+                    name,
+                    duration.as_secs_f64() * 1000.0,
+                    share,
+                    width = width
+                )
+            })
+            .collect()
+    }
+
+    /// Prints the phases on stderr, which keeps stdout to the abi when it is written there.
+    pub fn print(&self) {
+        eprintln!("{}", "profile".bold());
+        for line in self.lines() {
+            eprintln!("{}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile() {
+        let mut profile = Profile::default();
+        profile.add("match", Duration::from_millis(30));
+        profile.split("match", "hash", Duration::from_millis(10));
+        assert_eq!(profile.time("write", || 7), 7);
+        assert_eq!(profile.phases[0], ("match", Duration::from_millis(20)));
+        assert_eq!(profile.phases[1], ("hash", Duration::from_millis(10)));
+        assert!(profile.lines()[0].starts_with("match:    20.000 ms"));
+    }
+}
@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::abi::AbiEntry;
+use crate::conflict::CONFLICT_FIELDS_VERSION;
+
+/// The type of the reserved abi entry carrying the provenance.
+pub(crate) const PROVENANCE_TYPE: &str = "conflictsProvenance";
+
+/// Where the provenance of an annotation goes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProvenanceMode {
+    /// a `<abi>.provenance.json` file next to the abi
+    Sidecar,
+    /// a reserved entry at the end of the abi
+    Entry,
+}
+
+impl FromStr for ProvenanceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sidecar" => Ok(ProvenanceMode::Sidecar),
+            "entry" => Ok(ProvenanceMode::Entry),
+            _ => Err(format!("unknown provenance mode {}", s)),
+        }
+    }
+}
+
+/// Which tool, settings and analyzer outputs produced an annotation, so it can be traced back to
+/// the analyzer run later.
+pub fn provenance(csvs: &[PathBuf], gm: bool) -> Map<String, Value> {
+    let digests = csvs
+        .iter()
+        .map(|csv| {
+            let name = csv.file_name().unwrap().to_string_lossy().into_owned();
+            (name, Value::from(sha256_file(csv)))
+        })
+        .collect::<Map<String, Value>>();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let provenance = json!({
+        "tool": env!("CARGO_PKG_NAME"),
+        "toolVersion": env!("CARGO_PKG_VERSION"),
+        "hashAlgorithm": if gm { "sm3" } else { "keccak256" },
+        "conflictFieldsVersion": CONFLICT_FIELDS_VERSION,
+        "csvSha256": digests,
+        "timestamp": timestamp,
+    });
+    match provenance {
+        Value::Object(provenance) => provenance,
+        _ => unreachable!(),
+    }
+}
+
+pub fn sha256_file(path: &Path) -> String {
+    let mut file = std::fs::File::open(path)
+        .unwrap_or_else(|e| panic!("could not open {}: {}", path.display(), e));
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).unwrap();
+    hex::encode(hasher.finalize())
+}
+
+/// The reserved abi entry holding `provenance`.
+pub fn provenance_entry(provenance: Map<String, Value>) -> AbiEntry {
+    AbiEntry {
+        name: None,
+        ty: Some(PROVENANCE_TYPE.into()),
+        inputs: None,
+        state_mutability: None,
+        extra: provenance,
+        raw: None,
+    }
+}
+
+pub fn write_sidecar(abi: &Path, provenance: Map<String, Value>) {
+    let mut file_name = abi.file_name().unwrap().to_os_string();
+    file_name.push(".provenance.json");
+    let path = abi.with_file_name(file_name);
+    let content = serde_json::to_string_pretty(&provenance).unwrap();
+    std::fs::write(&path, content)
+        .unwrap_or_else(|e| panic!("could not write provenance {}: {}", path.display(), e));
+}
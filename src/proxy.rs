@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::abi::AbiEntry;
+use crate::merge::read_entries;
+use crate::{get_method_id, get_method_signature};
+
+/// Copies the conflictFields of the implementation functions onto the proxy functions of the
+/// same selector. Returns the number projected and the signatures of the selectors only one of
+/// the abis has, those of the proxy first.
+fn project(
+    proxy: &mut [AbiEntry],
+    implementation: &[AbiEntry],
+    gm: bool,
+) -> (usize, Vec<String>, Vec<String>) {
+    let mut implementations = implementation
+        .iter()
+        .filter(|entry| entry.is_function())
+        .map(|entry| (get_method_id(&get_method_signature(entry), gm), entry))
+        .collect::<BTreeMap<u32, &AbiEntry>>();
+    let mut projected = 0;
+    let mut proxy_only = Vec::new();
+    for entry in proxy.iter_mut().filter(|entry| entry.is_function()) {
+        let signature = get_method_signature(entry);
+        let implementation = match implementations.remove(&get_method_id(&signature, gm)) {
+            Some(implementation) => implementation,
+            None => {
+                proxy_only.push(signature);
+                continue;
+            }
+        };
+        for key in &["conflictFields", "conflictFieldsVersion"] {
+            match implementation.extra.get(*key) {
+                Some(value) => entry.extra.insert(key.to_string(), value.clone()),
+                None => entry.extra.remove(*key),
+            };
+        }
+        if entry.extra.contains_key("conflictFields") {
+            projected += 1;
+        }
+    }
+    let implementation_only = implementations
+        .values()
+        .map(|entry| get_method_signature(entry))
+        .collect();
+    (projected, proxy_only, implementation_only)
+}
+
+/// Writes `proxy` annotated with the conflicts of `implementation` to `output`: transactions go
+/// to the proxy, but the analyzer only sees the implementation.
+pub fn project_proxy(proxy: &Path, implementation: &Path, output: &Path, gm: bool) {
+    let mut entries = read_entries(proxy);
+    let (projected, proxy_only, implementation_only) =
+        project(&mut entries, &read_entries(implementation), gm);
+    for signature in &proxy_only {
+        eprintln!(
+            "{}: {} of the proxy isn't in the implementation, left unannotated",
+            "warning".yellow(),
+            signature
+        );
+    }
+    for signature in &implementation_only {
+        eprintln!(
+            "{}: {} of the implementation isn't in the proxy, is the proxy abi outdated?",
+            "warning".yellow(),
+            signature
+        );
+    }
+    std::fs::write(output, serde_json::to_string(&entries).unwrap())
+        .unwrap_or_else(|e| panic!("could not write {}: {}", output.display(), e));
+    print!(
+        "projected conflictFields of {} functions into {}",
+        projected,
+        format!("{}", output.display()).green()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project() {
+        let mut proxy: Vec<AbiEntry> = serde_json::from_str(
+            r#"[
+                {"name": "transfer", "type": "function", "inputs": [{"type": "address"}]},
+                {"name": "upgradeTo", "type": "function", "inputs": [{"type": "address"}]}
+            ]"#,
+        )
+        .unwrap();
+        let implementation: Vec<AbiEntry> = serde_json::from_str(
+            r#"[
+                {"name": "transfer", "type": "function", "inputs": [{"type": "address"}],
+                 "conflictFields": [{"kind": 0}], "conflictFieldsVersion": 2},
+                {"name": "mint", "type": "function", "inputs": []}
+            ]"#,
+        )
+        .unwrap();
+        let (projected, proxy_only, implementation_only) =
+            project(&mut proxy, &implementation, false);
+        assert_eq!(projected, 1);
+        assert_eq!(proxy_only, vec!["upgradeTo(address)"]);
+        assert_eq!(implementation_only, vec!["mint()"]);
+        assert_eq!(
+            proxy[0].extra["conflictFields"].to_string(),
+            r#"[{"kind":0}]"#
+        );
+        assert!(!proxy[1].extra.contains_key("conflictFields"));
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::abi::AbiEntry;
+use crate::conflict::{ConflictInfo, ConflictType, Environments};
+use crate::get_method_signature;
+use crate::hashtable::TableFormat;
+
+/// What the conflicts asked for match, every criterion given must hold.
+#[derive(Debug, Default, PartialEq)]
+pub struct Query {
+    /// a name, signature or 0x selector
+    pub(crate) function: Option<String>,
+    pub(crate) slot: Option<u32>,
+    pub(crate) kind: Option<ConflictType>,
+    /// one of the values, like a calldata word or an environment code
+    pub(crate) value: Option<u32>,
+}
+
+fn parse_number(number: &str) -> Result<u32, String> {
+    match number.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => number.parse(),
+    }
+    .map_err(|e| format!("invalid number {:?}: {}", number, e))
+}
+
+impl Query {
+    /// The query of the command line, where the kind is a name like `Env` and the value a
+    /// number or an environment name.
+    pub fn parse(
+        function: Option<&str>,
+        slot: Option<&str>,
+        kind: Option<&str>,
+        value: Option<&str>,
+        environments: &Environments,
+    ) -> Result<Query, String> {
+        let kind = kind.map(str::parse).transpose()?;
+        let value = match value {
+            Some(value) => Some(match environments.code(value) {
+                Some(code) => code,
+                None => parse_number(value)?,
+            }),
+            None => None,
+        };
+        Ok(Query {
+            function: function.map(String::from),
+            slot: slot.map(parse_number).transpose()?,
+            kind,
+            value,
+        })
+    }
+
+    fn matches(&self, conflict: &ConflictInfo) -> bool {
+        self.slot.map_or(true, |slot| conflict.slot == Some(slot))
+            && self
+                .kind
+                .as_ref()
+                .map_or(true, |kind| conflict.kind == *kind)
+            && self
+                .value
+                .map_or(true, |value| conflict.value.contains(&value))
+    }
+}
+
+/// The signature of every annotated function the query names, with each of its conflicts
+/// matching.
+fn query_entries(entries: &[AbiEntry], query: &Query) -> Vec<(String, ConflictInfo)> {
+    let mut rows = Vec::new();
+    for entry in entries.iter().filter(|entry| entry.is_function()) {
+        if let Some(function) = &query.function {
+            if !crate::explain::matches(entry, function) {
+                continue;
+            }
+        }
+        let signature = get_method_signature(entry);
+        let conflicts: Vec<ConflictInfo> = match entry.extra.get("conflictFields") {
+            Some(fields) => serde_json::from_value(fields.clone())
+                .unwrap_or_else(|e| panic!("invalid conflictFields of {}: {}", signature, e)),
+            None => continue,
+        };
+        rows.extend(
+            conflicts
+                .into_iter()
+                .filter(|conflict| query.matches(conflict))
+                .map(|conflict| (signature.clone(), conflict)),
+        );
+    }
+    rows
+}
+
+fn format_rows(rows: &[(String, ConflictInfo)], format: TableFormat) -> String {
+    match format {
+        TableFormat::Json => {
+            let rows = rows
+                .iter()
+                .map(|(signature, conflict)| json!({"signature": signature, "conflict": conflict}))
+                .collect::<Vec<Value>>();
+            serde_json::to_string_pretty(&rows).unwrap()
+        }
+        TableFormat::Tsv => {
+            let mut tsv = String::from("signature\tconflict\n");
+            for (signature, conflict) in rows {
+                tsv.push_str(&format!("{}\t{}\n", signature, conflict));
+            }
+            tsv
+        }
+    }
+}
+
+/// Prints the conflicts of the functions of the annotated `abi` matching `query`.
+pub fn query(abi: &Path, query: &Query, format: TableFormat) {
+    let abi_content = std::fs::read_to_string(abi)
+        .unwrap_or_else(|_| panic!("could not read file {}", abi.display()));
+    let entries: Vec<AbiEntry> = serde_json::from_str(&abi_content).unwrap();
+    print!("{}", format_rows(&query_entries(&entries, query), format));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_entries() {
+        let entries: Vec<AbiEntry> = serde_json::from_str(
+            r#"[
+                {
+                    "inputs": [{"name": "to", "type": "address"}],
+                    "name": "mint",
+                    "type": "function",
+                    "conflictFields": [
+                        {"kind": 3, "slot": 3, "value": [0]},
+                        {"kind": 2, "slot": 4, "value": [0]}
+                    ]
+                },
+                {"inputs": [], "name": "pause", "type": "function"}
+            ]"#,
+        )
+        .unwrap();
+        let environments = Environments::default();
+        let query = Query::parse(None, None, Some("env"), Some("caller"), &environments).unwrap();
+        let rows = query_entries(&entries, &query);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].0, "mint(address)");
+        assert_eq!(rows[0].1.slot, Some(4));
+        let query = Query::parse(None, Some("0x3"), None, None, &environments).unwrap();
+        assert_eq!(query_entries(&entries, &query)[0].1.kind, ConflictType::Var);
+        assert!(Query::parse(None, None, Some("Slot"), None, &environments).is_err());
+    }
+}
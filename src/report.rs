@@ -0,0 +1,349 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use colored::Colorize;
+use serde_json::{json, Value};
+
+use crate::annotate::{Annotator, FunctionSummary};
+use crate::conflict::{ConflictInfo, ConflictType};
+use crate::parallelism::{critical_size, Parallelism, PARALLELISMS};
+
+/// Prints how many functions fall into every parallelism class and the fully serialized ones,
+/// the first candidates for refactoring.
+pub fn print_stats(functions: &[FunctionSummary]) {
+    for parallelism in PARALLELISMS.iter() {
+        let count = functions
+            .iter()
+            .filter(|function| Parallelism::of(&function.conflicts) == *parallelism)
+            .count();
+        println!("{:>16}: {}", parallelism.to_string(), count);
+    }
+    for function in functions {
+        if Parallelism::of(&function.conflicts) == Parallelism::FullySerialized {
+            println!("    {}", function.display_signature.as_str().red());
+        }
+    }
+}
+
+/// The header and the row of the end of run summary.
+fn summary_row(abi: &Path, annotator: &Annotator) -> [(&'static str, String); 7] {
+    let functions = &annotator.functions;
+    let none_only = |function: &&FunctionSummary| {
+        !function.conflicts.is_empty()
+            && function
+                .conflicts
+                .iter()
+                .all(|conflict| conflict.kind == ConflictType::None)
+    };
+    let annotated = functions
+        .iter()
+        .filter(|function| !function.conflicts.is_empty() && !none_only(function))
+        .count();
+    [
+        ("contract", crate::contract_name(abi)),
+        ("functions", functions.len().to_string()),
+        ("annotated", annotated.to_string()),
+        (
+            "none only",
+            functions.iter().filter(none_only).count().to_string(),
+        ),
+        (
+            "unmatched selectors",
+            annotator.unmatched_selectors().len().to_string(),
+        ),
+        ("warnings", annotator.warnings.len().to_string()),
+        ("ignored slot conflicts", annotator.ignored.to_string()),
+    ]
+}
+
+/// Prints what the run did as an aligned table on stderr, which keeps stdout to the abi when
+/// it is written there.
+pub fn print_summary(abi: &Path, annotator: &Annotator) {
+    let row = summary_row(abi, annotator);
+    let widths = row
+        .iter()
+        .map(|(header, value)| header.len().max(value.len()))
+        .collect::<Vec<usize>>();
+    let header = row
+        .iter()
+        .zip(&widths)
+        .map(|((header, _), width)| format!("{:<1$}", header, width))
+        .collect::<Vec<String>>();
+    let values = row
+        .iter()
+        .zip(&widths)
+        .enumerate()
+        .map(|(column, ((_, value), width))| match column {
+            0 => format!("{:<1$}", value, width),
+            _ => format!("{:>1$}", value, width),
+        })
+        .collect::<Vec<String>>();
+    eprintln!("{}", header.join("  ").bold());
+    eprintln!("{}", values.join("  "));
+}
+
+/// The analyzer conflicts of `function` the overrides took the place of, as sentences.
+fn superseded_of(function: &FunctionSummary, superseded: &[ConflictInfo]) -> Vec<String> {
+    superseded
+        .iter()
+        .filter(|conflict| conflict.selector == function.selector)
+        .map(|conflict| conflict.to_string())
+        .collect()
+}
+
+/// Writes the conflicts of every function of `abi` as a Markdown table for audits, noting the
+/// conflicts overrides superseded.
+pub fn write_markdown(
+    path: &Path,
+    abi: &Path,
+    functions: &[FunctionSummary],
+    superseded: &[ConflictInfo],
+) {
+    let mut content = String::new();
+    writeln!(content, "# Conflicts of {}\n", abi.display()).unwrap();
+    writeln!(
+        content,
+        "| function | selector | parallelism | critical size | conflicts |"
+    )
+    .unwrap();
+    writeln!(content, "|---|---|---|---|---|").unwrap();
+    for function in functions {
+        let conflicts = if function.conflicts.is_empty() {
+            String::from("none")
+        } else {
+            function
+                .conflicts
+                .iter()
+                .map(|conflict| conflict.to_string())
+                .collect::<Vec<String>>()
+                .join("<br>")
+        };
+        writeln!(
+            content,
+            "| `{}` | `0x{:08x}` | {} | {} | {} |",
+            function.display_signature,
+            function.selector,
+            Parallelism::of(&function.conflicts),
+            match critical_size(function) {
+                Some(Ok(critical_size)) => critical_size.to_string(),
+                Some(Err(e)) => e,
+                None => String::new(),
+            },
+            conflicts
+        )
+        .unwrap();
+    }
+    let mut notes = functions
+        .iter()
+        .map(|function| (function, superseded_of(function, superseded)))
+        .filter(|(_, superseded)| !superseded.is_empty())
+        .peekable();
+    if notes.peek().is_some() {
+        writeln!(content, "\n## Superseded\n").unwrap();
+    }
+    for (function, superseded) in notes {
+        writeln!(
+            content,
+            "- `{}`: overridden, the analyzer found {}",
+            function.display_signature,
+            superseded.join(", ")
+        )
+        .unwrap();
+    }
+    std::fs::write(path, content)
+        .unwrap_or_else(|e| panic!("could not write report {}: {}", path.display(), e));
+}
+
+/// The key-partitioned functions with their critical sizes, fails on functions whose key is no
+/// prefix of the arguments.
+fn parallel_functions(functions: &[FunctionSummary]) -> Vec<(&FunctionSummary, usize)> {
+    let mut parallel = Vec::new();
+    for function in functions {
+        match critical_size(function) {
+            Some(Ok(critical_size)) => parallel.push((function, critical_size)),
+            Some(Err(e)) => panic!(
+                "{} can't be registered as parallel function: {}",
+                function.signature, e
+            ),
+            None => {}
+        }
+    }
+    parallel
+}
+
+/// Writes the key-partitioned functions with their critical sizes as the parallel configuration
+/// of BCOS 2.x.
+pub fn write_parallel_config(path: &Path, functions: &[FunctionSummary]) {
+    let config = parallel_functions(functions)
+        .into_iter()
+        .map(|(function, critical_size)| {
+            json!({
+                "function": function.signature,
+                "selector": format!("0x{:08x}", function.selector),
+                "criticalSize": critical_size,
+            })
+        })
+        .collect::<Vec<Value>>();
+    std::fs::write(path, serde_json::to_string_pretty(&config).unwrap())
+        .unwrap_or_else(|e| panic!("could not write {}: {}", path.display(), e));
+}
+
+/// The `ContractParallelConfig` of the Java SDK, function signatures with the critical size to
+/// register them with, as properties for a `.properties` path and as JSON otherwise.
+fn java_parallel_config(path: &Path, abi: &Path, functions: &[FunctionSummary]) -> String {
+    let parallel = parallel_functions(functions);
+    if path
+        .extension()
+        .map_or(false, |extension| extension == "properties")
+    {
+        let mut properties = format!("# parallel functions of {}\n", abi.display());
+        for (function, critical_size) in parallel {
+            writeln!(properties, "{}={}", function.signature, critical_size).unwrap();
+        }
+        return properties;
+    }
+    let contract_name = abi.file_stem().unwrap().to_string_lossy();
+    let config = json!({
+        "contractName": contract_name,
+        "functions": parallel
+            .into_iter()
+            .map(|(function, critical_size)| {
+                json!({
+                    "functionName": function.signature,
+                    "selector": format!("0x{:08x}", function.selector),
+                    "criticalSize": critical_size,
+                })
+            })
+            .collect::<Vec<Value>>(),
+    });
+    serde_json::to_string_pretty(&config).unwrap()
+}
+
+pub fn write_java_parallel_config(path: &Path, abi: &Path, functions: &[FunctionSummary]) {
+    std::fs::write(path, java_parallel_config(path, abi, functions))
+        .unwrap_or_else(|e| panic!("could not write {}: {}", path.display(), e));
+}
+
+/// What a report template gets: `abi` and `functions`, each with `name`, `signature`,
+/// `displaySignature`, `selector`, `parallelism`, `criticalSize`, `conflicts` as sentences, the
+/// raw `conflictFields` and the `superseded` conflicts of the analyzer overrides replaced.
+fn template_context(
+    abi: &Path,
+    functions: &[FunctionSummary],
+    superseded: &[ConflictInfo],
+) -> Value {
+    let functions = functions
+        .iter()
+        .map(|function| {
+            json!({
+                "name": function.name,
+                "signature": function.signature,
+                "displaySignature": function.display_signature,
+                "selector": format!("0x{:08x}", function.selector),
+                "parallelism": Parallelism::of(&function.conflicts).to_string(),
+                "criticalSize": critical_size(function).and_then(Result::ok),
+                "conflicts": function
+                    .conflicts
+                    .iter()
+                    .map(|conflict| conflict.to_string())
+                    .collect::<Vec<String>>(),
+                "conflictFields": function.fields,
+                "superseded": superseded_of(function, superseded),
+            })
+        })
+        .collect::<Vec<Value>>();
+    json!({
+        "abi": abi.display().to_string(),
+        "functions": functions,
+    })
+}
+
+/// Renders the conflicts of every function with a handlebars template, for documentation
+/// formats like Confluence wiki markup or AsciiDoc.
+pub fn write_template(
+    path: &Path,
+    template: &Path,
+    abi: &Path,
+    functions: &[FunctionSummary],
+    superseded: &[ConflictInfo],
+) {
+    let template = std::fs::read_to_string(template)
+        .unwrap_or_else(|e| panic!("could not read template {}: {}", template.display(), e));
+    let mut handlebars = handlebars::Handlebars::new();
+    // the output is no html
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.set_strict_mode(true);
+    let content = handlebars
+        .render_template(&template, &template_context(abi, functions, superseded))
+        .unwrap_or_else(|e| panic!("could not render report {}: {}", path.display(), e));
+    std::fs::write(path, content)
+        .unwrap_or_else(|e| panic!("could not write report {}: {}", path.display(), e));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_java_parallel_config() {
+        let conflicts = vec![ConflictInfo::new(ConflictType::Var, 0xa9059cbb)];
+        let mut function = FunctionSummary::for_test("transfer(address,uint256)", conflicts);
+        function.key_params = Some(vec![0]);
+        let functions = vec![function];
+        let properties = java_parallel_config(
+            Path::new("parallel.properties"),
+            Path::new("Token.abi"),
+            &functions,
+        );
+        assert_eq!(
+            properties,
+            "# parallel functions of Token.abi\ntransfer(address,uint256)=1\n"
+        );
+        let config: Value = serde_json::from_str(&java_parallel_config(
+            Path::new("parallel.json"),
+            Path::new("Token.abi"),
+            &functions,
+        ))
+        .unwrap();
+        assert_eq!(config["contractName"], "Token");
+        assert_eq!(config["functions"][0]["criticalSize"], 1);
+    }
+
+    #[test]
+    fn test_template_context() {
+        let conflicts = vec![ConflictInfo::new(ConflictType::All, 0x60fe47b1)];
+        let mut function = FunctionSummary::for_test("set(uint256)", conflicts);
+        function.fields = json!([{"kind": 0}]);
+        let functions = vec![function];
+        let superseded = vec![ConflictInfo::new(ConflictType::None, 0x60fe47b1)];
+        let context = template_context(Path::new("Token.abi"), &functions, &superseded);
+        assert_eq!(context["abi"], "Token.abi");
+        assert_eq!(context["functions"][0]["selector"], "0x60fe47b1");
+        assert_eq!(context["functions"][0]["parallelism"], "fully-serialized");
+        assert!(context["functions"][0]["criticalSize"].is_null());
+        assert_eq!(context["functions"][0]["conflicts"], json!(["All"]));
+        assert_eq!(
+            context["functions"][0]["conflictFields"],
+            json!([{"kind": 0}])
+        );
+        assert_eq!(context["functions"][0]["superseded"], json!(["None"]));
+    }
+
+    #[test]
+    fn test_summary_row() {
+        let conflicts = vec![
+            ConflictInfo::new(ConflictType::All, 7),
+            ConflictInfo::new(ConflictType::None, 0x60fe47b1),
+        ];
+        let mut annotator = crate::options::AnnotationOptions::new().annotator(&conflicts);
+        let mut function = FunctionSummary::for_test("set(uint256)", conflicts[1..].to_vec());
+        function.fields = json!([{"kind": 1}]);
+        annotator.functions.push(function);
+        let row = summary_row(Path::new("build/Token.abi"), &annotator);
+        let values = row
+            .iter()
+            .map(|(_, value)| value.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(values, vec!["Token", "1", "0", "1", "1", "0", "0"]);
+    }
+}
@@ -0,0 +1,112 @@
+use std::io::{BufRead, Write};
+
+use colored::Colorize;
+use serde_json::Value;
+
+use crate::conflict::ConflictInfo;
+
+/// Asks the reviewer about the proposed conflictFields of every function before they are
+/// written, a human checkpoint between the analyzer and the on-chain registration.
+#[derive(Default)]
+pub struct Reviewer {
+    accept_all: bool,
+}
+
+impl Reviewer {
+    /// The conflictFields to write for the function, none to leave it as it is.
+    pub(crate) fn review(
+        &mut self,
+        signature: &str,
+        previous: &Value,
+        fields: Value,
+    ) -> Option<Value> {
+        if self.accept_all {
+            return Some(fields);
+        }
+        let stdin = std::io::stdin();
+        let mut input = stdin.lock();
+        self.review_with(
+            &mut input,
+            &mut std::io::stdout(),
+            signature,
+            previous,
+            fields,
+        )
+    }
+
+    fn review_with<R: BufRead, W: Write>(
+        &mut self,
+        input: &mut R,
+        output: &mut W,
+        signature: &str,
+        previous: &Value,
+        fields: Value,
+    ) -> Option<Value> {
+        writeln!(output, "{}", signature.bold()).unwrap();
+        if !previous.is_null() {
+            writeln!(output, "{}", format!("- {}", previous).as_str().red()).unwrap();
+        }
+        writeln!(output, "{}", format!("+ {}", fields).as_str().green()).unwrap();
+        loop {
+            write!(output, "[a]ccept, [s]kip, [e]dit, [A]ccept all remaining? ").unwrap();
+            output.flush().unwrap();
+            let mut answer = String::new();
+            if input.read_line(&mut answer).unwrap() == 0 {
+                // nobody is there to answer anymore, keep what is written
+                return None;
+            }
+            match answer.trim() {
+                "a" => return Some(fields),
+                "s" => return None,
+                "A" => {
+                    self.accept_all = true;
+                    return Some(fields);
+                }
+                "e" => {
+                    write!(output, "conflictFields: ").unwrap();
+                    output.flush().unwrap();
+                    let mut edited = String::new();
+                    input.read_line(&mut edited).unwrap();
+                    // written as the reviewer typed it, once it reads as conflicts
+                    let edited = serde_json::from_str::<Value>(&edited).and_then(|edited| {
+                        serde_json::from_value::<Vec<ConflictInfo>>(edited.clone()).map(|_| edited)
+                    });
+                    match edited {
+                        Ok(edited) => return Some(edited),
+                        Err(e) => writeln!(output, "invalid conflictFields: {}", e).unwrap(),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_review() {
+        let mut reviewer = Reviewer::default();
+        let mut output = Vec::new();
+        let mut review = |answers: &str| {
+            reviewer.review_with(
+                &mut answers.as_bytes(),
+                &mut output,
+                "set(uint256)",
+                &Value::Null,
+                json!([{"kind": 0}]),
+            )
+        };
+        assert_eq!(review("s\n"), None);
+        assert_eq!(
+            review("x\ne\n{}\ne\n[{\"kind\": 99}]\ne\n[{\"kind\": 3}]\n"),
+            Some(json!([{"kind": 3}]))
+        );
+        assert_eq!(review(""), None);
+        assert_eq!(review("A\n"), Some(json!([{"kind": 0}])));
+        assert!(reviewer.accept_all);
+    }
+}
@@ -0,0 +1,61 @@
+use serde::Deserialize;
+
+use crate::conflict::ConflictInfo;
+use crate::lint::matches_pattern;
+
+/// A `[[rule]]` of the config file, giving every function whose name or signature matches
+/// `functions`, where `*` matches anything, the `conflicts` on top of those found, like
+/// `{ kind = "Env", value = [0] }` for the caller of administrative functions.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ConflictRule {
+    pub(crate) functions: String,
+    pub(crate) conflicts: Vec<ConflictInfo>,
+}
+
+impl ConflictRule {
+    pub(crate) fn applies(&self, name: &str, signature: &str) -> bool {
+        matches_pattern(&self.functions, name) || matches_pattern(&self.functions, signature)
+    }
+}
+
+/// The conflicts the rules give the function, with its selector.
+pub(crate) fn rule_conflicts(
+    rules: &[ConflictRule],
+    name: &str,
+    signature: &str,
+    selector: u32,
+) -> Vec<ConflictInfo> {
+    rules
+        .iter()
+        .filter(|rule| rule.applies(name, signature))
+        .flat_map(|rule| rule.conflicts.iter().cloned())
+        .map(|conflict| ConflictInfo {
+            selector,
+            ..conflict
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::ConflictType;
+
+    #[test]
+    fn test_rule_conflicts() {
+        let mut caller = ConflictInfo::new(ConflictType::Env, 0);
+        caller.value = vec![0];
+        let rules = vec![ConflictRule {
+            functions: String::from("set*"),
+            conflicts: vec![caller.clone()],
+        }];
+        assert_eq!(
+            rule_conflicts(&rules, "setOwner", "setOwner(address)", 7),
+            vec![ConflictInfo {
+                selector: 7,
+                ..caller
+            }]
+        );
+        assert!(rule_conflicts(&rules, "transfer", "transfer(address,uint256)", 7).is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::annotate::Annotator;
+use crate::conflict::ConflictType;
+use crate::validate::Violation;
+
+/// The rules findings are reported under, with their description.
+const RULES: [(&str, &str); 15] = [
+    (
+        "unresolved-key",
+        "A Var key points at no parameter head of the function",
+    ),
+    (
+        "other-hash-algorithm",
+        "An analyzer selector only matches a function under the other hash algorithm",
+    ),
+    (
+        "unmatched-selector",
+        "An analyzer selector matches no function of the abi",
+    ),
+    (
+        "uncovered-function",
+        "A mutating function has no conflicts, the analyzer may have missed it",
+    ),
+    (
+        "inconsistent-none",
+        "A function is marked None besides having conflicts",
+    ),
+    (
+        "too-many-conflicts",
+        "A function has more conflicts than the maximum",
+    ),
+    (
+        "forbidden-all",
+        "A function --forbid-all covers is serialized with All",
+    ),
+    (
+        "malformed-entry",
+        "An abi entry can't be annotated and is left as is",
+    ),
+    (
+        "missing-csv",
+        "An analyzer csv is missing from the directory and taken as empty",
+    ),
+    (
+        "unknown-environment",
+        "An Env conflict of the analyzer output has an unknown environment",
+    ),
+    (
+        "function-name-mismatch",
+        "The analyzer output names the function of a selector differently than the abi",
+    ),
+    (
+        "interface-abi",
+        "The abi is of an interface or abstract contract, which has no conflicts",
+    ),
+    (
+        "library-slot",
+        "A slot of a library function is of no variable of the caller layout",
+    ),
+    (
+        "unknown-selector",
+        "A selector given with --selector matches no function of the abi",
+    ),
+    (
+        "invalid-annotation",
+        "The conflictFields or selector of a function are invalid",
+    ),
+];
+
+/// One result, `location` is the signature of the function or the selector it is about.
+pub struct Finding {
+    pub(crate) rule: &'static str,
+    pub(crate) level: &'static str,
+    pub(crate) location: String,
+    pub(crate) message: String,
+}
+
+/// The warnings of the run and the gaps in the coverage of the abi by the analyzer output.
+pub fn annotation_findings(annotator: &Annotator) -> Vec<Finding> {
+    let (warning_level, note_level) = if annotator.fail_on_warning {
+        ("error", "error")
+    } else {
+        ("warning", "note")
+    };
+    let mut findings = annotator
+        .warnings
+        .iter()
+        .map(|warning| Finding {
+            rule: warning.rule,
+            level: warning_level,
+            location: warning.signature.clone(),
+            message: warning.message.clone(),
+        })
+        .collect::<Vec<Finding>>();
+    findings.extend(annotator.errors.iter().map(|error| Finding {
+        rule: error.rule,
+        level: "error",
+        location: error.signature.clone(),
+        message: error.message.clone(),
+    }));
+    for selector in annotator.unmatched_selectors() {
+        findings.push(Finding {
+            rule: "unmatched-selector",
+            level: note_level,
+            location: format!("0x{:08x}", selector),
+            message: format!("selector 0x{:08x} matches no function", selector),
+        });
+    }
+    for function in &annotator.functions {
+        let none = function
+            .conflicts
+            .iter()
+            .any(|conflict| conflict.kind == ConflictType::None);
+        if function.mutating && function.conflicts.is_empty() {
+            findings.push(Finding {
+                rule: "uncovered-function",
+                level: "warning",
+                location: function.signature.clone(),
+                message: format!("{} has no conflicts", function.signature),
+            });
+        } else if none && function.conflicts.len() > 1 {
+            findings.push(Finding {
+                rule: "inconsistent-none",
+                level: "error",
+                location: function.signature.clone(),
+                message: format!(
+                    "{} is marked None besides {} other conflicts",
+                    function.signature,
+                    function.conflicts.len() - 1
+                ),
+            });
+        }
+    }
+    findings
+}
+
+pub(crate) fn violation_findings(violations: &[Violation]) -> Vec<Finding> {
+    violations
+        .iter()
+        .map(|violation| Finding {
+            rule: "invalid-annotation",
+            level: "error",
+            location: violation.signature.clone(),
+            message: violation.message.clone(),
+        })
+        .collect()
+}
+
+/// The SARIF 2.1.0 log of the findings, located in the abi and at the function.
+fn sarif_log(abi: &Path, findings: &[Finding]) -> Value {
+    let rules = RULES
+        .iter()
+        .map(|(id, description)| json!({"id": id, "shortDescription": {"text": description}}))
+        .collect::<Vec<Value>>();
+    let results = findings
+        .iter()
+        .map(|finding| {
+            json!({
+                "ruleId": finding.rule,
+                "level": finding.level,
+                "message": {"text": finding.message},
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {"uri": abi.to_string_lossy().replace('\\', "/")}
+                    },
+                    "logicalLocations": [{
+                        "fullyQualifiedName": finding.location,
+                        "kind": "function",
+                    }],
+                }],
+            })
+        })
+        .collect::<Vec<Value>>();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    })
+}
+
+pub fn write_sarif(path: &Path, abi: &Path, findings: &[Finding]) {
+    let content = serde_json::to_string_pretty(&sarif_log(abi, findings)).unwrap();
+    std::fs::write(path, content)
+        .unwrap_or_else(|e| panic!("could not write {}: {}", path.display(), e));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sarif_log() {
+        let findings = vec![Finding {
+            rule: "uncovered-function",
+            level: "warning",
+            location: String::from("set(uint256)"),
+            message: String::from("set(uint256) has no conflicts"),
+        }];
+        let log = sarif_log(Path::new("Token.abi"), &findings);
+        let run = &log["runs"][0];
+        assert_eq!(
+            run["tool"]["driver"]["rules"].as_array().unwrap().len(),
+            RULES.len()
+        );
+        assert_eq!(run["results"][0]["ruleId"], "uncovered-function");
+        assert_eq!(
+            run["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "Token.abi"
+        );
+        assert_eq!(
+            run["results"][0]["locations"][0]["logicalLocations"][0]["fullyQualifiedName"],
+            "set(uint256)"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use serde_json::{json, Value};
+
+use crate::conflict::{CONFLICT_FIELDS_VERSION, CONFLICT_TYPES};
+
+/// JSON Schema of the conflictFields of spec `version`, for SDKs in other languages to validate
+/// their parsers against.
+pub fn conflict_fields_schema(version: u32) -> Value {
+    let kind_codes = json!({
+        "type": "integer",
+        "minimum": 0,
+        "maximum": CONFLICT_TYPES.len() - 1,
+        "description": CONFLICT_TYPES
+            .iter()
+            .enumerate()
+            .map(|(code, (name, _))| format!("{} {}", code, name))
+            .collect::<Vec<String>>()
+            .join(", "),
+    });
+    let slot = json!({
+        "oneOf": [
+            {"type": "integer", "minimum": 0},
+            {"type": "string", "pattern": "^0x[0-9a-f]{1,64}$"}
+        ]
+    });
+    let indices = json!({"type": "array", "items": {"type": "integer", "minimum": 0}});
+    let entry = match version {
+        // before the version stamp kinds could be names and values scalars
+        1 => json!({
+            "type": "object",
+            "required": ["kind"],
+            "properties": {
+                "kind": {
+                    "oneOf": [
+                        kind_codes,
+                        {
+                            "type": "string",
+                            "enum": CONFLICT_TYPES
+                                .iter()
+                                .map(|(name, _)| *name)
+                                .collect::<Vec<&str>>()
+                        }
+                    ]
+                },
+                "slot": slot,
+                "value": {"oneOf": [{"type": "integer", "minimum": 0}, indices]}
+            }
+        }),
+        CONFLICT_FIELDS_VERSION => json!({
+            "type": "object",
+            "required": ["kind"],
+            "additionalProperties": false,
+            "properties": {
+                "kind": kind_codes,
+                "slot": slot,
+                "len": {"type": "integer", "minimum": 1},
+                "offset": {"type": "integer", "minimum": 0, "maximum": 31},
+                "size": {"type": "integer", "minimum": 1, "maximum": 32},
+                "value": indices,
+                "names": {"type": "array", "items": {"type": "string"}},
+                "access": {"enum": ["read", "write"]},
+                "group": {"type": "integer", "minimum": 0},
+                "note": {"type": "string"}
+            }
+        }),
+        _ => panic!("unknown conflictFields version {}", version),
+    };
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": format!("conflictFields version {}", version),
+        "type": "array",
+        "items": entry,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::{Access, ConflictInfo, ConflictType};
+
+    #[test]
+    fn test_schema_covers_all_fields() {
+        let conflict = ConflictInfo {
+            kind: ConflictType::Var,
+            selector: 0,
+            slot: Some(1),
+            len: Some(2),
+            offset: Some(0),
+            size: Some(16),
+            value: vec![0, 1],
+            names: vec!["owner".into(), "spender".into()],
+            note: Some("Token.sol:12".into()),
+            function: None,
+            access: Some(Access::Write),
+            group: Some(0),
+        };
+        let schema = conflict_fields_schema(CONFLICT_FIELDS_VERSION);
+        let properties = schema["items"]["properties"].as_object().unwrap();
+        for field in serde_json::to_value(&conflict)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .keys()
+        {
+            assert!(properties.contains_key(field), "{} is missing", field);
+        }
+        assert_eq!(
+            conflict_fields_schema(1)["items"]["properties"]["kind"]["oneOf"][1]["enum"][5],
+            "None"
+        );
+    }
+}
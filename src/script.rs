@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use crate::conflict::ConflictInfo;
+
+/// A user script transforming the conflicts of every function, e.g.
+///
+/// ```rhai
+/// fn transform(name, signature, conflicts) {
+///     if name.starts_with("view") {
+///         conflicts.retain(|conflict| conflict.kind != 2);
+///     }
+///     conflicts
+/// }
+/// ```
+///
+/// The conflicts are maps shaped like the conflictFields entries.
+#[cfg(feature = "rhai")]
+pub struct Script {
+    engine: rhai::Engine,
+    ast: rhai::AST,
+}
+
+#[cfg(feature = "rhai")]
+impl Script {
+    pub fn load(path: &Path) -> Script {
+        let engine = rhai::Engine::new();
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .unwrap_or_else(|e| panic!("could not compile script {}: {}", path.display(), e));
+        Script { engine, ast }
+    }
+
+    /// Lets the `transform` function of the script filter, rewrite or add conflicts of a function.
+    pub(crate) fn transform(
+        &self,
+        name: &str,
+        signature: &str,
+        selector: u32,
+        conflicts: Vec<ConflictInfo>,
+    ) -> Vec<ConflictInfo> {
+        let result: rhai::Dynamic = self
+            .engine
+            .call_fn(
+                &mut rhai::Scope::new(),
+                &self.ast,
+                "transform",
+                (
+                    name.to_string(),
+                    signature.to_string(),
+                    rhai::serde::to_dynamic(&conflicts).unwrap(),
+                ),
+            )
+            .unwrap_or_else(|e| panic!("script failed on {}: {}", signature, e));
+        let mut conflicts: Vec<ConflictInfo> =
+            rhai::serde::from_dynamic(&result).unwrap_or_else(|e| {
+                panic!("script returned invalid conflicts for {}: {}", signature, e)
+            });
+        for conflict in &mut conflicts {
+            conflict.selector = selector;
+        }
+        conflicts
+    }
+}
+
+/// Without the rhai feature there are no scripts.
+#[cfg(not(feature = "rhai"))]
+pub enum Script {}
+
+#[cfg(not(feature = "rhai"))]
+impl Script {
+    pub fn load(path: &Path) -> Script {
+        panic!(
+            "could not run script {}, build with the rhai feature",
+            path.display()
+        );
+    }
+
+    pub(crate) fn transform(
+        &self,
+        _: &str,
+        _: &str,
+        _: u32,
+        _: Vec<ConflictInfo>,
+    ) -> Vec<ConflictInfo> {
+        match *self {}
+    }
+}
+
+#[cfg(all(test, feature = "rhai"))]
+mod tests {
+    use super::*;
+    use crate::conflict::ConflictType;
+
+    #[test]
+    fn test_transform() {
+        let engine = rhai::Engine::new();
+        let ast = engine
+            .compile(
+                r#"
+                fn transform(name, signature, conflicts) {
+                    if name.starts_with("view") {
+                        return [];
+                    }
+                    conflicts.push(#{ kind: 0 });
+                    conflicts
+                }
+                "#,
+            )
+            .unwrap();
+        let script = Script { engine, ast };
+        let conflicts = vec![ConflictInfo::new(ConflictType::Env, 7)];
+        assert!(script
+            .transform("viewBalance", "viewBalance()", 7, conflicts.clone())
+            .is_empty());
+        let transformed = script.transform("set", "set()", 7, conflicts);
+        assert_eq!(transformed.len(), 2);
+        assert_eq!(transformed[1].kind, ConflictType::All);
+        assert_eq!(transformed[1].selector, 7);
+    }
+}
@@ -0,0 +1,123 @@
+use std::path::{Path, PathBuf};
+
+/// Holds the hex signing key when no key file is given, for build pipelines keeping it in a
+/// secret store.
+pub(crate) const SIGNING_KEY_ENV: &str = "CONFLICTS_INFO_PARSE_SIGNING_KEY";
+
+/// The detached signature of `abi`, `<abi>.sig`.
+pub(crate) fn signature_path(abi: &Path) -> PathBuf {
+    let mut file_name = abi.file_name().unwrap().to_os_string();
+    file_name.push(".sig");
+    abi.with_file_name(file_name)
+}
+
+/// The `len` bytes written as hex, with or without `0x`, in `text`.
+fn decode_hex(text: &str, len: usize, what: &str) -> Result<Vec<u8>, String> {
+    let text = text.trim();
+    let bytes = hex::decode(text.strip_prefix("0x").unwrap_or(text))
+        .map_err(|e| format!("invalid {}: {}", what, e))?;
+    if bytes.len() != len {
+        return Err(format!(
+            "invalid {}: {} bytes instead of {}",
+            what,
+            bytes.len(),
+            len
+        ));
+    }
+    Ok(bytes)
+}
+
+fn read_text(path: &Path) -> String {
+    std::fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("could not read file {}", path.display()))
+}
+
+/// The signature of `content` by the Ed25519 `secret` and the public key it verifies with.
+#[cfg(feature = "ed25519-dalek")]
+fn sign_bytes(secret: &[u8], content: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+
+    let secret = SecretKey::from_bytes(secret).map_err(|e| e.to_string())?;
+    let public = PublicKey::from(&secret);
+    let signature = Keypair { secret, public }.sign(content);
+    Ok((signature.to_bytes().to_vec(), public.to_bytes().to_vec()))
+}
+
+#[cfg(not(feature = "ed25519-dalek"))]
+fn sign_bytes(_: &[u8], _: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    Err(String::from("build with the ed25519-dalek feature"))
+}
+
+#[cfg(feature = "ed25519-dalek")]
+fn verify_bytes(public: &[u8], signature: &[u8], content: &[u8]) -> Result<bool, String> {
+    use ed25519_dalek::{PublicKey, Signature, Verifier};
+
+    let public = PublicKey::from_bytes(public).map_err(|e| e.to_string())?;
+    let signature = Signature::from_bytes(signature).map_err(|e| e.to_string())?;
+    Ok(public.verify(content, &signature).is_ok())
+}
+
+#[cfg(not(feature = "ed25519-dalek"))]
+fn verify_bytes(_: &[u8], _: &[u8], _: &[u8]) -> Result<bool, String> {
+    Err(String::from("build with the ed25519-dalek feature"))
+}
+
+/// Signs the abi file as it is with the Ed25519 key in hex in `key`, or in the environment
+/// variable if none, and writes the signature in hex to `<abi>.sig`.
+pub fn sign(abi: &Path, key: Option<&Path>) {
+    let key = match key {
+        Some(key) => read_text(key),
+        None => std::env::var(SIGNING_KEY_ENV)
+            .unwrap_or_else(|_| panic!("no key file given and {} is not set", SIGNING_KEY_ENV)),
+    };
+    let (signature, public) = decode_hex(&key, 32, "signing key")
+        .and_then(|secret| sign_bytes(&secret, &std::fs::read(abi).unwrap()))
+        .unwrap_or_else(|e| panic!("could not sign {}: {}", abi.display(), e));
+    let path = signature_path(abi);
+    std::fs::write(&path, hex::encode(signature))
+        .unwrap_or_else(|e| panic!("could not write {}: {}", path.display(), e));
+    eprintln!(
+        "signed {} with the key {}",
+        abi.display(),
+        hex::encode(public)
+    );
+}
+
+/// Whether the signature in `signature`, `<abi>.sig` by default, is one of the abi file by the
+/// Ed25519 key whose public half is in hex in `public_key`.
+pub fn verify(abi: &Path, public_key: &Path, signature: Option<&Path>) -> bool {
+    let signature_path = signature.map_or_else(|| signature_path(abi), Path::to_path_buf);
+    let verified = decode_hex(&read_text(public_key), 32, "public key")
+        .and_then(|public| {
+            let signature = decode_hex(&read_text(&signature_path), 64, "signature")?;
+            verify_bytes(&public, &signature, &std::fs::read(abi).unwrap())
+        })
+        .unwrap_or_else(|e| panic!("could not verify {}: {}", abi.display(), e));
+    if verified {
+        println!("{} is signed by the key", abi.display());
+    } else {
+        println!(
+            "{} doesn't match the signature {}, it was changed or signed by another key",
+            abi.display(),
+            signature_path.display()
+        );
+    }
+    verified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(decode_hex(" 0x0102\n", 2, "key"), Ok(vec![1, 2]));
+        assert!(decode_hex("0102", 32, "key")
+            .unwrap_err()
+            .contains("2 bytes instead of 32"));
+        assert_eq!(
+            signature_path(Path::new("build/Token.abi")),
+            PathBuf::from("build/Token.abi.sig")
+        );
+    }
+}
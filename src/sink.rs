@@ -0,0 +1,345 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::annotate::FunctionSummary;
+
+/// Somewhere the result of a run goes, several of them can be written at once.
+pub trait OutputSink {
+    /// Names the sink in messages.
+    fn name(&self) -> String;
+
+    /// Writes the annotated abi text and what was found for its functions.
+    fn write(&self, abi: &str, functions: &[FunctionSummary]) -> Result<(), String>;
+}
+
+/// The annotated abi into the file it was read from, or another one.
+pub(crate) struct FileSink {
+    pub(crate) path: PathBuf,
+}
+
+impl OutputSink for FileSink {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn write(&self, abi: &str, _: &[FunctionSummary]) -> Result<(), String> {
+        std::fs::write(&self.path, abi).map_err(|e| e.to_string())
+    }
+}
+
+pub(crate) struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn name(&self) -> String {
+        String::from("stdout")
+    }
+
+    fn write(&self, abi: &str, _: &[FunctionSummary]) -> Result<(), String> {
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        writeln!(stdout, "{}", abi).map_err(|e| e.to_string())
+    }
+}
+
+/// How the sidecar is written, for configuration management pipelines not taking json. The abi
+/// itself stays json.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "toml" => Ok(OutputFormat::Toml),
+            _ => Err(format!("unknown output format {}", s)),
+        }
+    }
+}
+
+impl OutputFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Toml => "toml",
+        }
+    }
+
+    fn render(self, value: &Value) -> Result<String, String> {
+        match self {
+            OutputFormat::Json => Ok(serde_json::to_string_pretty(value).unwrap()),
+            OutputFormat::Yaml => serde_yaml::to_string(value).map_err(|e| e.to_string()),
+            // toml::Value writes the plain values of a table before its tables, which toml
+            // requires, the empty conflictFields of a function may follow the arrays of others
+            OutputFormat::Toml => toml::Value::try_from(value)
+                .and_then(|value| toml::to_string_pretty(&value))
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// The conflictFields by signature in `<abi>.conflicts.json`, or the extension of another
+/// format, leaving the abi untouched.
+pub(crate) struct SidecarSink {
+    pub(crate) path: PathBuf,
+    pub(crate) format: OutputFormat,
+}
+
+impl SidecarSink {
+    pub(crate) fn of(abi: &Path, format: OutputFormat) -> SidecarSink {
+        let mut file_name = abi.file_name().unwrap().to_os_string();
+        file_name.push(".conflicts.");
+        file_name.push(format.extension());
+        SidecarSink {
+            path: abi.with_file_name(file_name),
+            format,
+        }
+    }
+}
+
+fn sidecar(functions: &[FunctionSummary]) -> Value {
+    let fields = functions
+        .iter()
+        .map(|function| (function.signature.clone(), function.fields.clone()))
+        .collect::<Map<String, Value>>();
+    Value::Object(fields)
+}
+
+impl OutputSink for SidecarSink {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn write(&self, _: &str, functions: &[FunctionSummary]) -> Result<(), String> {
+        let content = self.format.render(&sidecar(functions))?;
+        std::fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+}
+
+/// The RFC 6902 patch turning the abi as it was read into the annotated one, for downstream
+/// systems owning the artifact to apply themselves.
+pub(crate) struct JsonPatchSink {
+    pub(crate) path: PathBuf,
+    /// the abi before the run
+    pub(crate) original: String,
+}
+
+impl OutputSink for JsonPatchSink {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn write(&self, abi: &str, _: &[FunctionSummary]) -> Result<(), String> {
+        let original: Value = serde_json::from_str(&self.original).map_err(|e| e.to_string())?;
+        let annotated: Value = serde_json::from_str(abi).map_err(|e| e.to_string())?;
+        let patch = crate::jsonpatch::json_patch(&original, &annotated);
+        let content = serde_json::to_string_pretty(&patch).unwrap();
+        std::fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+}
+
+/// The RFC 7386 merge patches of the changed functions keyed by signature, for consumers
+/// preferring merge patches to the positional paths of a json patch.
+pub(crate) struct MergePatchSink {
+    pub(crate) path: PathBuf,
+    /// the abi before the run
+    pub(crate) original: String,
+}
+
+impl OutputSink for MergePatchSink {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn write(&self, abi: &str, _: &[FunctionSummary]) -> Result<(), String> {
+        let original: Vec<Value> =
+            serde_json::from_str(&self.original).map_err(|e| e.to_string())?;
+        let annotated: Vec<Value> = serde_json::from_str(abi).map_err(|e| e.to_string())?;
+        let patches = crate::jsonpatch::merge_patches(&original, &annotated);
+        let content = serde_json::to_string_pretty(&patches).unwrap();
+        std::fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+}
+
+/// A `functions` table of signature, selector and conflictFields json, replacing the rows of
+/// earlier runs for the same signatures.
+#[cfg(feature = "rusqlite")]
+pub(crate) struct SqliteSink {
+    pub(crate) path: PathBuf,
+}
+
+#[cfg(feature = "rusqlite")]
+impl OutputSink for SqliteSink {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn write(&self, _: &str, functions: &[FunctionSummary]) -> Result<(), String> {
+        let mut connection = rusqlite::Connection::open(&self.path).map_err(|e| e.to_string())?;
+        let transaction = connection.transaction().map_err(|e| e.to_string())?;
+        transaction
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS functions (
+                    signature TEXT PRIMARY KEY,
+                    selector TEXT NOT NULL,
+                    conflict_fields TEXT NOT NULL
+                )",
+            )
+            .map_err(|e| e.to_string())?;
+        for function in functions {
+            transaction
+                .execute(
+                    "INSERT OR REPLACE INTO functions (signature, selector, conflict_fields)
+                     VALUES (?1, ?2, ?3)",
+                    rusqlite::params![
+                        function.signature,
+                        format!("0x{:08x}", function.selector),
+                        function.fields.to_string()
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        transaction.commit().map_err(|e| e.to_string())
+    }
+}
+
+/// A sink on the command line: `in-place`, `stdout`, `sidecar`, `file:<path>`,
+/// `json-patch:<path>`, `merge-patch:<path>` or `sqlite:<path>`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SinkSpec {
+    InPlace,
+    Stdout,
+    Sidecar,
+    File(PathBuf),
+    JsonPatch(PathBuf),
+    MergePatch(PathBuf),
+    Sqlite(PathBuf),
+}
+
+impl FromStr for SinkSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("file", path)) => Ok(SinkSpec::File(path.into())),
+            Some(("json-patch", path)) => Ok(SinkSpec::JsonPatch(path.into())),
+            Some(("merge-patch", path)) => Ok(SinkSpec::MergePatch(path.into())),
+            Some(("sqlite", path)) => Ok(SinkSpec::Sqlite(path.into())),
+            _ => match s {
+                "in-place" => Ok(SinkSpec::InPlace),
+                "stdout" => Ok(SinkSpec::Stdout),
+                "sidecar" => Ok(SinkSpec::Sidecar),
+                _ => Err(format!("unknown sink {}", s)),
+            },
+        }
+    }
+}
+
+/// The abi as it is before the run, sinks writing a patch are built before it is overwritten.
+fn read_original(abi: &Path) -> String {
+    std::fs::read_to_string(abi).unwrap_or_else(|_| panic!("could not read file {}", abi.display()))
+}
+
+impl SinkSpec {
+    /// The file the sink writes the annotated abi to, none if it writes something else.
+    pub fn abi_file<'a>(&'a self, abi: &'a Path) -> Option<&'a Path> {
+        match self {
+            SinkSpec::InPlace => Some(abi),
+            SinkSpec::File(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    pub fn sink(&self, abi: &Path, format: OutputFormat) -> Box<dyn OutputSink> {
+        match self {
+            SinkSpec::InPlace => Box::new(FileSink {
+                path: abi.to_path_buf(),
+            }),
+            SinkSpec::Stdout => Box::new(StdoutSink),
+            SinkSpec::Sidecar => Box::new(SidecarSink::of(abi, format)),
+            SinkSpec::File(path) => Box::new(FileSink { path: path.clone() }),
+            SinkSpec::JsonPatch(path) => Box::new(JsonPatchSink {
+                path: path.clone(),
+                original: read_original(abi),
+            }),
+            SinkSpec::MergePatch(path) => Box::new(MergePatchSink {
+                path: path.clone(),
+                original: read_original(abi),
+            }),
+            #[cfg(feature = "rusqlite")]
+            SinkSpec::Sqlite(path) => Box::new(SqliteSink { path: path.clone() }),
+            #[cfg(not(feature = "rusqlite"))]
+            SinkSpec::Sqlite(path) => panic!(
+                "could not write {}, build with the rusqlite feature",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Writes the result of the run to every sink.
+pub fn write_all(sinks: &[Box<dyn OutputSink>], abi: &str, functions: &[FunctionSummary]) {
+    for sink in sinks {
+        sink.write(abi, functions)
+            .unwrap_or_else(|e| panic!("could not write {}: {}", sink.name(), e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sink_spec() {
+        assert_eq!("in-place".parse(), Ok(SinkSpec::InPlace));
+        assert_eq!(
+            "file:out/Token.abi".parse(),
+            Ok(SinkSpec::File(PathBuf::from("out/Token.abi")))
+        );
+        assert_eq!(
+            "sqlite:conflicts.db".parse(),
+            Ok(SinkSpec::Sqlite(PathBuf::from("conflicts.db")))
+        );
+        assert_eq!(
+            "json-patch:Token.abi.patch".parse(),
+            Ok(SinkSpec::JsonPatch(PathBuf::from("Token.abi.patch")))
+        );
+        assert!("ftp:x".parse::<SinkSpec>().is_err());
+        let abi = Path::new("Token.abi");
+        assert_eq!(SinkSpec::InPlace.abi_file(abi), Some(abi));
+        assert_eq!(SinkSpec::Sidecar.abi_file(abi), None);
+        assert_eq!(
+            SidecarSink::of(Path::new("build/Token.abi"), OutputFormat::Json).path,
+            PathBuf::from("build/Token.abi.conflicts.json")
+        );
+        assert_eq!(
+            SidecarSink::of(Path::new("build/Token.abi"), "yaml".parse().unwrap()).path,
+            PathBuf::from("build/Token.abi.conflicts.yaml")
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let sidecar = serde_json::json!({
+            "approve(address,uint256)": [
+                {"kind": 3, "slot": 1, "value": [0]},
+                {"kind": 2, "value": [0]}
+            ],
+            "name()": [],
+        });
+        let yaml = OutputFormat::Yaml.render(&sidecar).unwrap();
+        assert_eq!(serde_yaml::from_str::<Value>(&yaml).unwrap(), sidecar);
+        let toml = OutputFormat::Toml.render(&sidecar).unwrap();
+        assert_eq!(toml::from_str::<Value>(&toml).unwrap(), sidecar);
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::annotate::FunctionSummary;
+use crate::conflict::{ConflictInfo, ConflictType, Environments};
+
+/// The slots a conflict touches, all of a struct stored across several.
+fn slots_of(conflict: &ConflictInfo) -> impl Iterator<Item = u32> {
+    let slot = conflict.slot;
+    let len = conflict.len.unwrap_or(1).max(1);
+    slot.into_iter()
+        .flat_map(move |slot| slot..slot.saturating_add(len))
+}
+
+/// The `storageLayout` solc writes, alone or in a combined or standard json output.
+pub(crate) fn find_storage_layout(output: &Value) -> Option<&Value> {
+    if output.get("storage").map_or(false, Value::is_array) {
+        return Some(output);
+    }
+    if let Some(layout) = output.get("storageLayout") {
+        return find_storage_layout(layout);
+    }
+    output
+        .as_object()?
+        .values()
+        .filter(|value| value.is_object())
+        .find_map(find_storage_layout)
+}
+
+/// The state variables of a solc storage layout by slot, packed ones share a slot.
+fn storage_names(path: &Path) -> BTreeMap<u32, Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("could not read file {}", path.display()));
+    let output: Value = serde_json::from_str(&content)
+        .unwrap_or_else(|e| panic!("invalid storage layout {}: {}", path.display(), e));
+    let layout = find_storage_layout(&output)
+        .unwrap_or_else(|| panic!("no storage layout in {}", path.display()));
+    let mut names = BTreeMap::new();
+    for variable in layout["storage"].as_array().into_iter().flatten() {
+        let slot = variable["slot"].as_str().and_then(|slot| slot.parse().ok());
+        if let (Some(slot), Some(label)) = (slot, variable["label"].as_str()) {
+            names
+                .entry(slot)
+                .or_insert_with(Vec::new)
+                .push(label.to_string());
+        }
+    }
+    names
+}
+
+/// The functions conflicting on every slot with the kinds of their conflicts.
+fn functions_by_slot(
+    functions: &[FunctionSummary],
+) -> BTreeMap<u32, BTreeMap<&str, BTreeSet<String>>> {
+    let mut index = BTreeMap::new();
+    for function in functions {
+        for conflict in &function.conflicts {
+            for slot in slots_of(conflict) {
+                index
+                    .entry(slot)
+                    .or_insert_with(BTreeMap::new)
+                    .entry(function.signature.as_str())
+                    .or_insert_with(BTreeSet::new)
+                    .insert(conflict.kind.to_string());
+            }
+        }
+    }
+    index
+}
+
+/// A slot or an environment many functions conflict on.
+#[derive(Debug, PartialEq)]
+pub struct Hotspot {
+    pub source: String,
+    /// whether it is a storage slot, the threshold only applies to those
+    pub slot: bool,
+    pub(crate) functions: usize,
+    pub mutating: usize,
+}
+
+/// The slots and environments by the number of distinct functions conflicting on them, most
+/// first.
+pub fn hotspots(functions: &[FunctionSummary], environments: &Environments) -> Vec<Hotspot> {
+    let mut sources = BTreeMap::new();
+    for function in functions {
+        for conflict in &function.conflicts {
+            let mut keys = slots_of(conflict)
+                .map(|slot| (true, format!("slot 0x{:x}", slot)))
+                .collect::<Vec<(bool, String)>>();
+            if conflict.kind == ConflictType::Env {
+                keys.extend(conflict.value.iter().map(|code| {
+                    let name = environments.name(*code).unwrap_or("UNKNOWN");
+                    (false, format!("env {}", name))
+                }));
+            }
+            for key in keys {
+                sources
+                    .entry(key)
+                    .or_insert_with(BTreeMap::new)
+                    .insert(function.signature.as_str(), function.mutating);
+            }
+        }
+    }
+    let mut hotspots = sources
+        .into_iter()
+        .map(|((slot, source), functions)| Hotspot {
+            source,
+            slot,
+            functions: functions.len(),
+            mutating: functions.values().filter(|mutating| **mutating).count(),
+        })
+        .collect::<Vec<Hotspot>>();
+    hotspots.sort_by(|a, b| b.functions.cmp(&a.functions));
+    hotspots
+}
+
+/// Prints the `top` hotspots.
+pub fn print_hotspots(hotspots: &[Hotspot], top: usize) {
+    for hotspot in hotspots.iter().take(top) {
+        eprintln!(
+            "{:>5} functions, {:>5} mutating: {}",
+            hotspot.functions, hotspot.mutating, hotspot.source
+        );
+    }
+}
+
+/// The slot centric view of the conflicts, the slots with the variables stored in them and the
+/// functions touching them.
+fn slot_index(functions: &[FunctionSummary], names: &BTreeMap<u32, Vec<String>>) -> Value {
+    let slots = functions_by_slot(functions)
+        .into_iter()
+        .map(|(slot, functions)| {
+            let functions = functions
+                .into_iter()
+                .map(|(signature, kinds)| json!({"signature": signature, "kinds": kinds}))
+                .collect::<Vec<Value>>();
+            json!({
+                "slot": format!("0x{:x}", slot),
+                "names": names.get(&slot).cloned().unwrap_or_default(),
+                "functions": functions,
+            })
+        })
+        .collect::<Vec<Value>>();
+    Value::Array(slots)
+}
+
+/// Writes the slot index of `functions` as json, naming the slots from the solc storage layout
+/// if given.
+pub fn write_slot_index(path: &Path, functions: &[FunctionSummary], storage_layout: Option<&Path>) {
+    let names = storage_layout.map(storage_names).unwrap_or_default();
+    let content = serde_json::to_string_pretty(&slot_index(functions, &names)).unwrap();
+    std::fs::write(path, content)
+        .unwrap_or_else(|e| panic!("could not write {}: {}", path.display(), e));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_index() {
+        let mut var = ConflictInfo::new(ConflictType::Var, 1);
+        var.slot = Some(3);
+        var.len = Some(2);
+        let mut read = ConflictInfo::new(ConflictType::Const, 1);
+        read.slot = Some(4);
+        let functions = vec![FunctionSummary::for_test("set(uint256)", vec![var, read])];
+        let mut names = BTreeMap::new();
+        names.insert(3, vec![String::from("balances")]);
+        let index = slot_index(&functions, &names);
+        assert_eq!(index.as_array().unwrap().len(), 2);
+        assert_eq!(index[0]["slot"], "0x3");
+        assert_eq!(index[0]["names"], json!(["balances"]));
+        assert_eq!(index[1]["functions"][0]["kinds"], json!(["Const", "Var"]));
+    }
+
+    #[test]
+    fn test_hotspots() {
+        let summary = |signature: &str, mutating: bool, conflicts: Vec<ConflictInfo>| {
+            let mut function = FunctionSummary::for_test(signature, conflicts);
+            function.mutating = mutating;
+            function
+        };
+        let mut var = ConflictInfo::new(ConflictType::Var, 0);
+        var.slot = Some(3);
+        let mut env = ConflictInfo::new(ConflictType::Env, 0);
+        env.value = vec![0];
+        let functions = vec![
+            summary("a()", true, vec![var.clone(), env]),
+            summary("b()", true, vec![var.clone()]),
+            summary("c()", false, vec![var]),
+        ];
+        let hotspots = hotspots(&functions, &Environments::default());
+        assert_eq!(hotspots.len(), 2);
+        assert_eq!(hotspots[0].source, "slot 0x3");
+        assert_eq!((hotspots[0].functions, hotspots[0].mutating), (3, 2));
+        assert_eq!(hotspots[1].source, "env CALLER");
+        assert!(!hotspots[1].slot);
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use log::info;
+
+use crate::annotate::Warning;
+use crate::conflict::{ConflictInfo, ConflictType, Environments};
+use crate::layout::{CsvLayout, SelectorRadix};
+
+/// Something producing the conflicts of functions, the analyzer csvs or a plugin wrapping a
+/// proprietary analyzer or a database.
+pub trait ConflictSource {
+    /// Names the source in messages.
+    fn name(&self) -> String;
+
+    fn load(&self) -> Result<Vec<ConflictInfo>, String>;
+
+    /// What looks wrong about the source without stopping the run, reported with the warnings
+    /// of the annotation.
+    fn warnings(&self) -> Vec<Warning> {
+        Vec::new()
+    }
+
+    /// Whether the conflicts replace those of all other sources for the same functions.
+    fn replaces(&self) -> bool {
+        false
+    }
+}
+
+/// What to do about an analyzer csv missing from the directory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MissingCsv {
+    Error,
+    /// treat it as empty with a warning
+    Warn,
+    /// treat it as empty, analyzers only writing the csvs they have rows for
+    Ignore,
+}
+
+impl FromStr for MissingCsv {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(MissingCsv::Error),
+            "warn" => Ok(MissingCsv::Warn),
+            "ignore" => Ok(MissingCsv::Ignore),
+            _ => Err(format!("unknown missing csv policy {}", s)),
+        }
+    }
+}
+
+/// How the analyzer csvs are read.
+pub struct CsvOptions<'a> {
+    pub layouts: &'a [CsvLayout],
+    pub layout: Option<&'a CsvLayout>,
+    /// only keep the conflicts of these selectors
+    pub only: Option<&'a BTreeSet<u32>>,
+    pub environments: &'a Environments,
+    pub selector_radix: SelectorRadix,
+    /// hash signatures written instead of selectors with SM3
+    pub gm: bool,
+    pub missing: MissingCsv,
+}
+
+/// The csvs the analyzer wrote into a directory.
+pub struct CsvSource<'a> {
+    pub path: &'a Path,
+    pub options: &'a CsvOptions<'a>,
+}
+
+impl ConflictSource for CsvSource<'_> {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Vec<ConflictInfo>, String> {
+        crate::parse_conflict_info(self.path, self.options)
+    }
+
+    fn warnings(&self) -> Vec<Warning> {
+        missing_csv_warnings(self.path, self.options.missing)
+    }
+}
+
+/// The analyzer csvs missing from the directory at `path`, if the policy is to warn about them.
+pub(crate) fn missing_csv_warnings(path: &Path, missing: MissingCsv) -> Vec<Warning> {
+    if missing != MissingCsv::Warn {
+        return Vec::new();
+    }
+    crate::CONFLICT_CSVS
+        .iter()
+        .map(|(file_name, _)| path.join(file_name))
+        .filter(|csv_path| !csv_path.exists())
+        .map(|csv_path| Warning {
+            rule: "missing-csv",
+            signature: csv_path.display().to_string(),
+            message: String::from("the analyzer output is missing, taken as empty"),
+        })
+        .collect()
+}
+
+/// The analyzer csvs merged into one file, the first column of every row names the csv it
+/// comes from.
+pub struct MergedCsvSource<'a> {
+    pub path: &'a Path,
+    pub options: &'a CsvOptions<'a>,
+}
+
+impl ConflictSource for MergedCsvSource<'_> {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Vec<ConflictInfo>, String> {
+        crate::parse_merged_conflict_info(self.path, self.options)
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// A json array of conflictFields entries, each with the `selector` of its function, like the
+/// plugins return.
+pub struct JsonSource {
+    pub path: PathBuf,
+}
+
+impl ConflictSource for JsonSource {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Vec<ConflictInfo>, String> {
+        read_json(&self.path)
+    }
+}
+
+/// Hand written conflictFields by function signature or selector like `0xa9059cbb`, replacing
+/// what the other sources found for these functions. An empty array gives the function a None
+/// entry.
+pub struct OverridesSource {
+    pub path: PathBuf,
+    pub gm: bool,
+}
+
+impl ConflictSource for OverridesSource {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Vec<ConflictInfo>, String> {
+        let overrides: BTreeMap<String, Vec<ConflictInfo>> = read_json(&self.path)?;
+        let mut conflicts = Vec::new();
+        for (signature, mut overridden) in overrides {
+            let selector = crate::selector_of(&signature, self.gm)?;
+            if overridden.is_empty() {
+                overridden.push(ConflictInfo::new(ConflictType::None, selector));
+            }
+            conflicts.extend(overridden.into_iter().map(|conflict| ConflictInfo {
+                selector,
+                ..conflict
+            }));
+        }
+        Ok(conflicts)
+    }
+
+    fn replaces(&self) -> bool {
+        true
+    }
+}
+
+/// A wasm module exporting its `memory`, `alloc(len) -> ptr` and `conflicts(ptr, len) -> u64`.
+/// `conflicts` gets the plugin argument and returns the pointer in the high and the length in
+/// the low 32 bits of a json array of conflictFields entries, each with the `selector` of its
+/// function.
+#[cfg(feature = "wasmtime")]
+pub struct WasmPlugin {
+    path: std::path::PathBuf,
+    argument: String,
+}
+
+#[cfg(feature = "wasmtime")]
+impl WasmPlugin {
+    pub fn load(path: &Path, argument: &str) -> WasmPlugin {
+        WasmPlugin {
+            path: path.to_path_buf(),
+            argument: argument.to_string(),
+        }
+    }
+
+    fn call(&self) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let engine = wasmtime::Engine::default();
+        let module = wasmtime::Module::from_file(&engine, &self.path)?;
+        let mut store = wasmtime::Store::new(&engine, ());
+        let instance = wasmtime::Instance::new(&mut store, &module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or("the plugin exports no memory")?;
+        let alloc = instance.get_typed_func::<u32, u32, _>(&mut store, "alloc")?;
+        let conflicts = instance.get_typed_func::<(u32, u32), u64, _>(&mut store, "conflicts")?;
+        let argument = self.argument.as_bytes();
+        let ptr = alloc.call(&mut store, argument.len() as u32)?;
+        memory.write(&mut store, ptr as usize, argument)?;
+        let result = conflicts.call(&mut store, (ptr, argument.len() as u32))?;
+        let (ptr, len) = ((result >> 32) as usize, (result & 0xffff_ffff) as usize);
+        let data = memory.data(&store);
+        // the plugin is not trusted to return a range of its memory
+        let output = data.get(ptr..ptr.saturating_add(len)).ok_or_else(|| {
+            format!(
+                "the plugin returned {}..{}, outside its memory of {} bytes",
+                ptr,
+                ptr.saturating_add(len),
+                data.len()
+            )
+        })?;
+        Ok(output.to_vec())
+    }
+}
+
+#[cfg(feature = "wasmtime")]
+impl ConflictSource for WasmPlugin {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn load(&self) -> Result<Vec<ConflictInfo>, String> {
+        let output = self.call().map_err(|e| format!("plugin failed: {}", e))?;
+        serde_json::from_slice(&output)
+            .map_err(|e| format!("plugin returned invalid conflicts: {}", e))
+    }
+}
+
+/// Without the wasmtime feature there are no plugins.
+#[cfg(not(feature = "wasmtime"))]
+pub enum WasmPlugin {}
+
+#[cfg(not(feature = "wasmtime"))]
+impl WasmPlugin {
+    pub fn load(path: &Path, _: &str) -> WasmPlugin {
+        panic!(
+            "could not load plugin {}, build with the wasmtime feature",
+            path.display()
+        );
+    }
+}
+
+#[cfg(not(feature = "wasmtime"))]
+impl ConflictSource for WasmPlugin {
+    fn name(&self) -> String {
+        match *self {}
+    }
+
+    fn load(&self) -> Result<Vec<ConflictInfo>, String> {
+        match *self {}
+    }
+}
+
+/// The conflicts of all sources, sorted and without duplicates. Those of replacing sources are
+/// the only ones kept for their functions, the ones they supersede are returned second for the
+/// report.
+pub fn collect(
+    sources: &[Box<dyn ConflictSource + '_>],
+) -> Result<(Vec<ConflictInfo>, Vec<ConflictInfo>), String> {
+    let mut conflicts = Vec::new();
+    let mut replacing = Vec::new();
+    for source in sources {
+        let found = source
+            .load()
+            .map_err(|e| format!("could not load conflicts from {}: {}", source.name(), e))?;
+        info!("{} conflicts from {}", found.len(), source.name());
+        if source.replaces() {
+            replacing.extend(found);
+        } else {
+            conflicts.extend(found);
+        }
+    }
+    let replaced = replacing
+        .iter()
+        .map(|conflict| conflict.selector)
+        .collect::<BTreeSet<u32>>();
+    let (mut superseded, mut conflicts): (Vec<ConflictInfo>, Vec<ConflictInfo>) = conflicts
+        .into_iter()
+        .partition(|conflict| replaced.contains(&conflict.selector));
+    conflicts.extend(replacing);
+    for conflicts in [&mut conflicts, &mut superseded] {
+        conflicts.sort();
+        conflicts.dedup();
+    }
+    Ok((conflicts, superseded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(Vec<ConflictInfo>, bool);
+
+    impl ConflictSource for Fixed {
+        fn name(&self) -> String {
+            String::from("fixed")
+        }
+
+        fn load(&self) -> Result<Vec<ConflictInfo>, String> {
+            Ok(self.0.clone())
+        }
+
+        fn replaces(&self) -> bool {
+            self.1
+        }
+    }
+
+    struct Failing;
+
+    impl ConflictSource for Failing {
+        fn name(&self) -> String {
+            String::from("failing")
+        }
+
+        fn load(&self) -> Result<Vec<ConflictInfo>, String> {
+            Err(String::from("broken"))
+        }
+    }
+
+    #[test]
+    fn test_collect_replaces() {
+        let sources: Vec<Box<dyn ConflictSource>> = vec![
+            Box::new(Fixed(
+                vec![
+                    ConflictInfo::new(ConflictType::All, 1),
+                    ConflictInfo::new(ConflictType::All, 2),
+                ],
+                false,
+            )),
+            Box::new(Fixed(vec![ConflictInfo::new(ConflictType::None, 2)], true)),
+        ];
+        let (conflicts, superseded) = collect(&sources).unwrap();
+        assert_eq!(
+            conflicts,
+            vec![
+                ConflictInfo::new(ConflictType::All, 1),
+                ConflictInfo::new(ConflictType::None, 2)
+            ]
+        );
+        assert_eq!(superseded, vec![ConflictInfo::new(ConflictType::All, 2)]);
+        let sources: Vec<Box<dyn ConflictSource>> = vec![Box::new(Failing)];
+        assert_eq!(
+            collect(&sources).unwrap_err(),
+            "could not load conflicts from failing: broken"
+        );
+    }
+
+    #[cfg(feature = "wasmtime")]
+    #[test]
+    fn test_wasm_plugin() {
+        let plugin = |name: &str, result: u64| {
+            let path = std::env::temp_dir().join(name);
+            let module = format!(
+                r#"(module
+                    (memory (export "memory") 1)
+                    (data (i32.const 16) "[{{\"kind\":0,\"selector\":7}}]")
+                    (func (export "alloc") (param i32) (result i32) i32.const 1024)
+                    (func (export "conflicts") (param i32 i32) (result i64) i64.const {}))"#,
+                result
+            );
+            std::fs::write(&path, module).unwrap();
+            WasmPlugin::load(&path, "")
+        };
+        let conflicts = plugin("test_wasm_plugin.wat", 16 << 32 | 25)
+            .load()
+            .unwrap();
+        assert_eq!(conflicts, vec![ConflictInfo::new(ConflictType::All, 7)]);
+        let outside = plugin("test_wasm_plugin_outside.wat", 65530 << 32 | 100).load();
+        assert!(outside.unwrap_err().contains("outside its memory"));
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::abi::AbiEntry;
+use crate::patch::MANAGED_KEYS;
+
+/// Removes the annotation and the provenance entry, returns the number of functions stripped.
+fn strip_entries(entries: &mut Vec<AbiEntry>) -> usize {
+    entries.retain(|entry| !entry.is_provenance());
+    let mut stripped = 0;
+    for entry in entries.iter_mut() {
+        let before = entry.extra.len();
+        for key in MANAGED_KEYS.iter() {
+            entry.extra.remove(*key);
+        }
+        if entry.extra.len() != before {
+            stripped += 1;
+        }
+    }
+    stripped
+}
+
+/// Restores the plain abi from an annotated one, e.g. to withdraw the annotation or regenerate
+/// it from scratch.
+pub fn strip(abi: &Path) {
+    let abi_content = std::fs::read_to_string(abi)
+        .unwrap_or_else(|_| panic!("could not read file {}", abi.display()));
+    let mut entries: Vec<AbiEntry> = serde_json::from_str(&abi_content).unwrap();
+    let stripped = strip_entries(&mut entries);
+    std::fs::write(abi, serde_json::to_string(&entries).unwrap()).unwrap();
+    print!(
+        "stripped the annotation of {} functions from {}",
+        stripped,
+        format!("{}", abi.display()).green()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_entries() {
+        let mut entries: Vec<AbiEntry> = serde_json::from_str(
+            r#"[
+                {"name": "a", "type": "function", "outputs": [], "selector": [1, 2],
+                 "conflictFields": [{"kind": 0}], "conflictFieldsVersion": 2, "parallel": true},
+                {"name": "b", "type": "function", "outputs": []},
+                {"type": "conflictsProvenance", "tool": "conflicts_info_parse"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(strip_entries(&mut entries), 1);
+        assert_eq!(
+            serde_json::to_value(&entries).unwrap(),
+            serde_json::json!([
+                {"name": "a", "type": "function", "outputs": []},
+                {"name": "b", "type": "function", "outputs": []}
+            ])
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::conflict::{ConflictInfo, ConflictType};
+use crate::merge::{conflicts_of, read_entries};
+use crate::{contract_name, get_method_signature};
+
+/// The mutating functions of an annotated abi and the storage they run on.
+struct Contract {
+    name: String,
+    /// the address whose storage the functions change, contracts called by delegatecall or
+    /// libraries share that of the caller
+    storage: String,
+    /// the conflicts by signature, none if the function isn't annotated
+    functions: Vec<(String, Option<Vec<ConflictInfo>>)>,
+}
+
+fn read_contract(abi: &Path, deployment: &BTreeMap<String, String>) -> Contract {
+    let name = contract_name(abi);
+    let functions = read_entries(abi)
+        .iter()
+        .filter(|entry| entry.is_function() && entry.is_mutating())
+        .map(|entry| (get_method_signature(entry), conflicts_of(entry)))
+        .collect();
+    Contract {
+        storage: deployment
+            .get(&name)
+            .cloned()
+            .unwrap_or_else(|| name.clone()),
+        name,
+        functions,
+    }
+}
+
+fn slots(conflict: &ConflictInfo) -> Option<(u32, u32)> {
+    let slot = conflict.slot?;
+    Some((slot, slot.saturating_add(conflict.len.unwrap_or(1).max(1))))
+}
+
+/// Why two functions of different contracts may conflict, none if they can't. All conflicts with
+/// everything, slots only matter if the contracts share storage.
+fn cross_conflict(
+    a: &Option<Vec<ConflictInfo>>,
+    b: &Option<Vec<ConflictInfo>>,
+    shared_storage: bool,
+) -> Option<String> {
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return Some(String::from("not annotated")),
+    };
+    let all = |conflicts: &[ConflictInfo]| {
+        conflicts
+            .iter()
+            .any(|conflict| conflict.kind == ConflictType::All)
+    };
+    if all(a) || all(b) {
+        return Some(String::from("serialized with All"));
+    }
+    if !shared_storage {
+        return None;
+    }
+    a.iter().filter_map(slots).find_map(|(start, end)| {
+        b.iter()
+            .filter_map(slots)
+            .find(|(other_start, other_end)| start < *other_end && *other_start < end)
+            .map(|(other_start, _)| format!("both on slot 0x{:x}", start.max(other_start)))
+    })
+}
+
+/// The pairs of functions of different contracts that may conflict with the reason, and the
+/// number of pairs.
+fn cross_conflicts(contracts: &[Contract]) -> (Vec<String>, usize) {
+    let mut conflicting = Vec::new();
+    let mut pairs = 0;
+    for (index, contract) in contracts.iter().enumerate() {
+        for other in &contracts[index + 1..] {
+            let shared_storage = contract.storage == other.storage;
+            for (signature, conflicts) in &contract.functions {
+                for (other_signature, other_conflicts) in &other.functions {
+                    pairs += 1;
+                    if let Some(reason) = cross_conflict(conflicts, other_conflicts, shared_storage)
+                    {
+                        conflicting.push(format!(
+                            "{}.{} and {}.{}: {}",
+                            contract.name, signature, other.name, other_signature, reason
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    (conflicting, pairs)
+}
+
+/// Prints which functions of the contracts of a system, annotated in `abis`, may conflict with
+/// those of the other contracts. The deployment map names the storage address every contract
+/// named after its abi file runs on, to tell which of them share storage.
+pub fn system_report(abis: &[PathBuf], deployment: Option<&Path>) {
+    let deployment: BTreeMap<String, String> = match deployment {
+        Some(path) => {
+            let content = std::fs::read_to_string(path)
+                .unwrap_or_else(|_| panic!("could not read file {}", path.display()));
+            serde_json::from_str(&content)
+                .unwrap_or_else(|e| panic!("invalid deployment map {}: {}", path.display(), e))
+        }
+        None => BTreeMap::new(),
+    };
+    let contracts = abis
+        .iter()
+        .map(|abi| read_contract(abi, &deployment))
+        .collect::<Vec<Contract>>();
+    let (conflicting, pairs) = cross_conflicts(&contracts);
+    for line in &conflicting {
+        println!("{}", line);
+    }
+    println!(
+        "{} of {} pairs of mutating functions of different contracts may conflict",
+        conflicting.len(),
+        pairs
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cross_conflicts() {
+        let mut var = ConflictInfo::new(ConflictType::Var, 0);
+        var.slot = Some(3);
+        let mut packed = ConflictInfo::new(ConflictType::Const, 0);
+        packed.slot = Some(2);
+        packed.len = Some(2);
+        let contract = |name: &str, storage: &str, conflicts: Vec<ConflictInfo>| Contract {
+            name: name.into(),
+            storage: storage.into(),
+            functions: vec![(String::from("f()"), Some(conflicts))],
+        };
+        let contracts = vec![
+            contract("Token", "0x1", vec![var.clone()]),
+            contract("Logic", "0x1", vec![packed]),
+            contract("Exchange", "0x2", vec![var]),
+        ];
+        let (conflicting, pairs) = cross_conflicts(&contracts);
+        assert_eq!(pairs, 3);
+        assert_eq!(
+            conflicting,
+            vec!["Token.f() and Logic.f(): both on slot 0x3"]
+        );
+        assert_eq!(
+            cross_conflict(&None, &Some(vec![]), false),
+            Some(String::from("not annotated"))
+        );
+    }
+}
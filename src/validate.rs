@@ -0,0 +1,229 @@
+use std::path::Path;
+
+use colored::Colorize;
+use serde_json::Value;
+
+use crate::abi::AbiEntry;
+use crate::cache::SelectorCache;
+use crate::conflict::{ConflictInfo, ConflictType, Environments, CONFLICT_FIELDS_VERSION};
+use crate::get_method_signature;
+
+const FIELDS: [&str; 10] = [
+    "kind", "slot", "len", "offset", "size", "value", "names", "access", "group", "note",
+];
+
+/// A problem found in the annotation of one function.
+pub(crate) struct Violation {
+    pub(crate) signature: String,
+    pub(crate) message: String,
+}
+
+/// Checks the conflictFields and selectors of an annotated abi, prints the violations, writes
+/// them to the `sarif` log if any and returns whether there were none.
+pub fn validate(abi: &Path, sarif: Option<&Path>, environments: &Environments) -> bool {
+    let abi_content = std::fs::read_to_string(abi)
+        .unwrap_or_else(|_| panic!("could not read file {}", abi.display()));
+    let entries: Vec<AbiEntry> = serde_json::from_str(&abi_content).unwrap();
+    let violations = validate_entries(&entries, environments);
+    if let Some(sarif) = sarif {
+        crate::sarif::write_sarif(sarif, abi, &crate::sarif::violation_findings(&violations));
+    }
+    for violation in &violations {
+        println!("{}: {}", violation.signature.yellow(), violation.message);
+    }
+    if violations.is_empty() {
+        print!(
+            "conflictFields of {} are valid",
+            format!("{}", abi.display()).green()
+        );
+    } else {
+        print!("{} violations found", violations.len().to_string().red());
+    }
+    violations.is_empty()
+}
+
+pub(crate) fn validate_entries(
+    entries: &[AbiEntry],
+    environments: &Environments,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for entry in entries.iter().filter(|entry| entry.is_function()) {
+        let signature = get_method_signature(entry);
+        let mut violation = |message: String| {
+            violations.push(Violation {
+                signature: signature.clone(),
+                message,
+            })
+        };
+        if let Some(selector) = entry.extra.get("selector") {
+            let expected = SelectorCache::default().method_ids(&signature);
+            if *selector != serde_json::to_value(&expected).unwrap() {
+                violation(format!(
+                    "selector {} doesn't match the signature, expected {:?}",
+                    selector, expected
+                ));
+            }
+        }
+        let fields = match entry.extra.get("conflictFields") {
+            Some(fields) => fields,
+            None => continue,
+        };
+        let version = entry.extra.get("conflictFieldsVersion");
+        let current = match version {
+            None => {
+                violation("no conflictFieldsVersion, annotated by an older version".into());
+                false
+            }
+            Some(version) if version.as_u64() == Some(CONFLICT_FIELDS_VERSION.into()) => true,
+            Some(version) => {
+                violation(format!("unknown conflictFieldsVersion {}", version));
+                continue;
+            }
+        };
+        let fields = match fields.as_array() {
+            Some(fields) => fields,
+            None => {
+                violation("conflictFields is not an array".into());
+                continue;
+            }
+        };
+        for (index, field) in fields.iter().enumerate() {
+            for message in validate_field(entry, field, current, environments) {
+                violation(format!("conflictFields[{}]: {}", index, message));
+            }
+        }
+    }
+    violations
+}
+
+/// Structural checks first, the meaning is only checked once the entry parses.
+fn validate_field(
+    entry: &AbiEntry,
+    field: &Value,
+    current: bool,
+    environments: &Environments,
+) -> Vec<String> {
+    let mut messages = Vec::new();
+    let object = match field.as_object() {
+        Some(object) => object,
+        None => return vec!["not an object".into()],
+    };
+    if current {
+        for key in object.keys().filter(|key| !FIELDS.contains(&key.as_str())) {
+            messages.push(format!("unknown field {}", key));
+        }
+        if object.get("kind").map_or(false, Value::is_string) {
+            messages.push("kind is a name instead of a code".into());
+        }
+        if object.get("value").map_or(false, Value::is_number) {
+            messages.push("value is a number instead of an array".into());
+        }
+    }
+    let conflict: ConflictInfo = match serde_json::from_value(field.clone()) {
+        Ok(conflict) => conflict,
+        Err(e) => {
+            messages.push(e.to_string());
+            return messages;
+        }
+    };
+    match conflict.kind {
+        ConflictType::Env => {
+            if conflict.value.len() != 1 || !environments.is_valid(conflict.value[0]) {
+                messages.push(format!("invalid environment {:?}", conflict.value));
+            }
+        }
+        ConflictType::Var => {
+            if conflict.slot.is_none() {
+                messages.push("Var without slot".into());
+            }
+            if conflict.value.is_empty() {
+                messages.push("Var without key index".into());
+            }
+            if !conflict.names.is_empty() && conflict.names.len() != conflict.value.len() {
+                messages.push("names don't match the key indices".into());
+            }
+            for index in &conflict.value {
+                if entry.param_at_word(*index).is_none() {
+                    messages.push(format!("key index {} is past the parameters", index));
+                }
+            }
+        }
+        ConflictType::Const => {
+            if conflict.value.is_empty() {
+                messages.push("Const without value".into());
+            }
+        }
+        ConflictType::All | ConflictType::None => {
+            if conflict.slot.is_some() || !conflict.value.is_empty() {
+                messages.push(format!("{} doesn't take slot or value", conflict.kind));
+            }
+        }
+        ConflictType::Len => {}
+    }
+    match (conflict.offset, conflict.size) {
+        (Some(offset), Some(size)) if offset + size > 32 => messages.push(format!(
+            "bytes {}..{} exceed the slot",
+            offset,
+            offset + size
+        )),
+        (Some(_), None) | (None, Some(_)) => messages.push("offset and size go together".into()),
+        _ => {}
+    }
+    if conflict.len == Some(0) {
+        messages.push("len 0 covers no slot".into());
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_entries() {
+        let entries: Vec<AbiEntry> = serde_json::from_str(
+            r#"[
+                {
+                    "conflictFields": [
+                        {"kind": 3, "slot": 1, "value": [0]},
+                        {"kind": 2, "slot": 0, "value": [7]},
+                        {"kind": 0, "slot": 2},
+                        {"kind": 3, "slot": 1, "value": [2], "extra": 1},
+                        {"kind": 9}
+                    ],
+                    "conflictFieldsVersion": 2,
+                    "inputs": [{"name": "to", "type": "address"}],
+                    "name": "set",
+                    "type": "function"
+                },
+                {
+                    "conflictFields": [{"kind": "Var", "slot": 1, "value": 0}],
+                    "inputs": [{"name": "to", "type": "address"}],
+                    "name": "old",
+                    "type": "function"
+                }
+            ]"#,
+        )
+        .unwrap();
+        let messages = validate_entries(&entries, &Environments::default())
+            .into_iter()
+            .map(|violation| format!("{}: {}", violation.signature, violation.message))
+            .collect::<Vec<String>>();
+        assert_eq!(messages.len(), 6);
+        assert!(messages[0].starts_with("set(address): conflictFields[1]: invalid environment"));
+        assert!(messages[1].starts_with("set(address): conflictFields[2]: All doesn't"));
+        assert_eq!(
+            messages[2],
+            "set(address): conflictFields[3]: unknown field extra"
+        );
+        assert_eq!(
+            messages[3],
+            "set(address): conflictFields[3]: key index 2 is past the parameters"
+        );
+        assert!(messages[4].starts_with("set(address): conflictFields[4]: "));
+        assert_eq!(
+            messages[5],
+            "old(address): no conflictFieldsVersion, annotated by an older version"
+        );
+    }
+}